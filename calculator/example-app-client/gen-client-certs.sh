@@ -0,0 +1,53 @@
+#!/bin/sh
+# Issue the credentials of the clients the workload runner starts (see config/workload.toml),
+# laid out like ca-root/cli1001: a PKCS#8 key, its certificate and the chain of the
+# certificate followed by the one of the CA.
+#
+# Replicas only accept clients whose certificates were signed by the CA they trust,
+# so this needs the certificate and the key of the CA the replicas were issued by.
+# Clients that already have credentials are left as they are.
+#
+# Usage: ./gen-client-certs.sh <CA certificate> <CA key> [first node id] [clients]
+#
+# The defaults cover the clients of the shipped workload.toml, 1002 to 1010,
+# cli1001 being shipped already
+set -eu
+
+if [ $# -lt 2 ]; then
+    echo "Usage: $0 <CA certificate> <CA key> [first node id] [clients]" >&2
+    exit 1
+fi
+
+ca_crt=$1
+ca_key=$2
+first=${3:-1002}
+count=${4:-9}
+
+ca_root="$(dirname "$0")/ca-root"
+
+id=$first
+last=$((first + count - 1))
+
+while [ "$id" -le "$last" ]; do
+    dir="$ca_root/cli$id"
+
+    if [ -e "$dir" ]; then
+        echo "Keeping the credentials in $dir"
+    else
+        mkdir -p "$dir"
+
+        openssl req -new -newkey rsa:2048 -nodes -subj "/CN=cli$id" \
+            -keyout "$dir/key" -out "$dir/csr" 2>/dev/null
+
+        openssl x509 -req -in "$dir/csr" -CA "$ca_crt" -CAkey "$ca_key" -CAcreateserial \
+            -CAserial "$dir/srl" -days 36500 -sha256 -out "$dir/crt" 2>/dev/null
+
+        cat "$dir/crt" "$ca_crt" > "$dir/chain"
+
+        rm "$dir/csr" "$dir/srl"
+
+        echo "Issued the credentials in $dir"
+    fi
+
+    id=$((id + 1))
+done
@@ -0,0 +1,102 @@
+use atlas_common::async_runtime;
+use atlas_default_configs::get_reconfig_config;
+use atlas_default_configs::crypto::FolderPathConstructor;
+use config::File;
+use config::FileFormat::Toml;
+use example_app::app::messages::Operation;
+use example_app::app::wire;
+use example_app::logging::{init_logging, NodeRole};
+use example_app::tolerance::{Quorum, Tolerance};
+use example_app_client::registers::RegisterClient;
+use example_app_client::settings;
+use log::{error, info};
+
+const USAGE: &str = "Usage: example-app-registers <register> <get | add | sub | mul | div | rem | pow> [value] | example-app-registers digest";
+
+/// Operate on a register of the divisible state replica, such as `3 add 5` or `3 get`
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let reconfig_config = get_reconfig_config::<FolderPathConstructor>(None).unwrap();
+
+    let logging_cfg = settings::parse_logging_conf(File::new("config/logging.toml", Toml).required(false)).unwrap();
+
+    let _log_handle = init_logging(reconfig_config.node_id, NodeRole::Client, &logging_cfg).unwrap();
+
+    let nodes_cfg = settings::parse_nodes_conf(File::new("config/nodes.toml", Toml)).unwrap();
+
+    let deployment_cfg = settings::parse_deployment_conf(File::new("config/deployment.toml", Toml)).unwrap();
+
+    let quorum = match Quorum::<Tolerance>::check(nodes_cfg.replica_count(), &deployment_cfg) {
+        Ok(quorum) => quorum,
+        Err(err) => {
+            error!("Refusing to start client: {}", err);
+
+            std::process::exit(1);
+        }
+    };
+
+    let codec_cfg = settings::parse_codec_conf(File::new("config/codec.toml", Toml).required(false)).unwrap();
+
+    wire::set_codec(codec_cfg.codec).unwrap();
+
+    wire::set_limits(codec_cfg.limits).unwrap();
+
+    let retry_policy = settings::parse_retry_conf(File::new("config/requests.toml", Toml).required(false)).unwrap();
+
+    let connection_cfg = settings::parse_connection_conf(File::new("config/requests.toml", Toml).required(false)).unwrap();
+
+    let client = async_runtime::block_on(RegisterClient::connect(connection_cfg))
+        .unwrap()
+        .with_retry_policy(retry_policy);
+
+    if let Err(err) = async_runtime::block_on(client.check_tolerance(quorum.f())) {
+        error!("Refusing to start client: {}", err);
+
+        std::process::exit(1);
+    }
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = match args.as_slice() {
+        ["digest"] => async_runtime::block_on(client.digest()).map(|digest| info!("The digest of the registers is {:?}", digest)),
+        [register, "get"] => {
+            let register = parse(register);
+
+            async_runtime::block_on(client.get(register)).map(|value| info!("Register {} is {}", register, value))
+        }
+        [register, operation, value] => {
+            let register = parse(register);
+
+            let operation = match *operation {
+                "add" => Operation::Add,
+                "sub" => Operation::Sub,
+                "mul" => Operation::Mult,
+                "div" => Operation::Divide,
+                "rem" => Operation::Remainder,
+                "pow" => Operation::Exponent,
+                _ => usage(),
+            };
+
+            async_runtime::block_on(client.apply(register, operation, parse(value)))
+                .map(|value| info!("Register {} is now {}", register, value))
+        }
+        _ => usage(),
+    };
+
+    if let Err(err) = result {
+        error!("Failed to execute {}: {}", args.join(" "), err);
+
+        std::process::exit(1);
+    }
+}
+
+fn parse<T: std::str::FromStr>(argument: &str) -> T {
+    argument.parse().unwrap_or_else(|_| usage())
+}
+
+fn usage() -> ! {
+    error!("{}", USAGE);
+
+    std::process::exit(2);
+}
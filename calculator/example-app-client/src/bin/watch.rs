@@ -0,0 +1,50 @@
+use atlas_default_configs::get_reconfig_config;
+use atlas_default_configs::crypto::FolderPathConstructor;
+use config::File;
+use config::FileFormat::Toml;
+use example_app::app::subscriptions::WatchFilter;
+use example_app::logging::{init_logging, NodeRole};
+use example_app::tolerance::{Quorum, Tolerance};
+use example_app_client::settings;
+use example_app_client::watch::Watcher;
+use log::{error, info};
+
+/// Print the value every time it changes, or only when it crosses
+/// the threshold given as the first argument
+fn main() {
+    let filter = match std::env::args().nth(1) {
+        Some(threshold) => WatchFilter::Crossing { threshold: threshold.parse().expect("The threshold must be an integer") },
+        None => WatchFilter::All,
+    };
+
+    let reconfig_config = get_reconfig_config::<FolderPathConstructor>(None).unwrap();
+
+    let logging_cfg = settings::parse_logging_conf(File::new("config/logging.toml", Toml).required(false)).unwrap();
+
+    let _log_handle = init_logging(reconfig_config.node_id, NodeRole::Client, &logging_cfg).unwrap();
+
+    let nodes_cfg = settings::parse_nodes_conf(File::new("config/nodes.toml", Toml)).unwrap();
+
+    let deployment_cfg = settings::parse_deployment_conf(File::new("config/deployment.toml", Toml)).unwrap();
+
+    let quorum = match Quorum::<Tolerance>::check(nodes_cfg.replica_count(), &deployment_cfg) {
+        Ok(quorum) => quorum,
+        Err(err) => {
+            error!("Refusing to start watcher: {}", err);
+
+            std::process::exit(1);
+        }
+    };
+
+    let watch_cfg = settings::parse_watch_conf(File::new("config/watch.toml", Toml).required(false)).unwrap();
+
+    let watcher = Watcher::connect(&nodes_cfg, &watch_cfg, filter, quorum.f()).unwrap();
+
+    info!("Watching the value with filter {:?}", filter);
+
+    for notification in watcher {
+        info!("The value is now {} (sequence number {:?})", notification.value, notification.seq_no);
+    }
+
+    info!("Every replica closed its watch connection");
+}
@@ -0,0 +1,81 @@
+use atlas_common::async_runtime;
+use config::File;
+use config::FileFormat::Toml;
+use example_app::app::wire;
+use example_app::app::wire::Codec;
+use example_app::logging::{init_logging, NodeRole};
+use example_app::tolerance::{Quorum, Tolerance};
+use example_app_client::settings;
+use example_app_client::workload::{run_workload, workload_messages, WorkloadReport};
+use log::{error, info};
+
+/// Run many logical clients at once, each with its own node id and credentials,
+/// as described by `config/workload.toml`. Prints a single JSON report, with the outcome
+/// of every client and the size and encoding time of the workload's requests, replies
+/// and state with each codec
+fn main() {
+    let logging_cfg = settings::parse_logging_conf(File::new("config/logging.toml", Toml).required(false)).unwrap();
+
+    let workload_cfg = settings::parse_workload_conf(File::new("config/workload.toml", Toml)).unwrap();
+
+    let _log_handle = init_logging(workload_cfg.first_client_id.into(), NodeRole::Client, &logging_cfg).unwrap();
+
+    let nodes_cfg = settings::parse_nodes_conf(File::new("config/nodes.toml", Toml)).unwrap();
+
+    let deployment_cfg = settings::parse_deployment_conf(File::new("config/deployment.toml", Toml)).unwrap();
+
+    let quorum = match Quorum::<Tolerance>::check(nodes_cfg.replica_count(), &deployment_cfg) {
+        Ok(quorum) => quorum,
+        Err(err) => {
+            error!("Refusing to start the workload: {}", err);
+
+            std::process::exit(1);
+        }
+    };
+
+    let codec_cfg = settings::parse_codec_conf(File::new("config/codec.toml", Toml).required(false)).unwrap();
+
+    wire::set_codec(codec_cfg.codec).unwrap();
+
+    wire::set_limits(codec_cfg.limits).unwrap();
+
+    let retry_policy = settings::parse_retry_conf(File::new("config/requests.toml", Toml).required(false)).unwrap();
+
+    let reports = match async_runtime::block_on(run_workload(&workload_cfg, &retry_policy, quorum.f())) {
+        Ok(reports) => reports,
+        Err(err) => {
+            error!("Refusing to run the workload: {}", err);
+
+            std::process::exit(1);
+        }
+    };
+
+    let completed: usize = reports.iter().map(|report| report.completed).sum();
+    let failed: usize = reports.iter().map(|report| report.failed).sum();
+    let throughput: f64 = reports.iter().map(|report| report.throughput_ops).sum();
+
+    info!("{} clients completed {} operations ({} failed), at {:.1} ops/s in total", reports.len(), completed, failed, throughput);
+
+    let messages = workload_messages(&workload_cfg);
+
+    let mut codecs = Vec::new();
+
+    for codec in Codec::ALL {
+        let measured = [
+            wire::measure(codec, &messages.requests),
+            wire::measure(codec, &messages.replies),
+            wire::measure(codec, std::slice::from_ref(&messages.state)),
+        ];
+
+        for stats in measured {
+            match stats {
+                Ok(stats) => codecs.push(stats),
+                Err(err) => error!("Failed to measure the {:?} codec: {}", codec, err),
+            }
+        }
+    }
+
+    let report = WorkloadReport { clients: reports, codecs };
+
+    println!("{}", serde_json::to_string(&report).unwrap());
+}
@@ -0,0 +1,529 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use anyhow::anyhow;
+use futures::future::{select, Either};
+use futures::lock::Mutex as AsyncMutex;
+use futures::stream::{self, BoxStream, StreamExt};
+use futures_timer::Delay;
+use log::warn;
+use serde::Deserialize;
+use thiserror::Error;
+use atlas_client::client;
+use atlas_client::client::ClientConfig;
+use atlas_client::client::ordered_client::Ordered;
+use atlas_client::client::unordered_client::{Unordered, UnorderedClientMode};
+use atlas_client::concurrent_client::ConcurrentClient;
+use atlas_common::crypto::hash::Digest;
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use atlas_default_configs::{get_network_configurations, get_reconfig_config};
+use atlas_default_configs::crypto::FolderPathConstructor;
+use example_app::app::messages::{AppData, Operation, OperationError, ReadConsistency, Reply, Request};
+use example_app::state::session_base;
+use example_app::tolerance::Tolerance;
+use example_app::trace::{ActiveSpan, RequestTraceId, Stage, Tracer};
+use crate::{ClientNetwork, ClientNode, ExampleClient, ReconfProtocol};
+
+/// How many requests a [CalculatorClient] can have outstanding, unless told otherwise
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 10;
+
+/// How long to wait before asking again for a read that replicas have not caught up to
+const INITIAL_READ_BACKOFF: Duration = Duration::from_millis(10);
+
+const MAX_READ_BACKOFF: Duration = Duration::from_millis(250);
+
+/// The order in which the replies of a batch are returned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplyOrder {
+    /// In the order the requests were submitted in
+    Submission,
+    /// As soon as they arrive
+    Completion,
+}
+
+/// How many replies the client waits for, in unordered requests
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnorderedReplies {
+    /// Wait for f+1 matching replies
+    #[default]
+    Quorum,
+    /// Take the first reply, which is what [ReadConsistency::Any] reads need.
+    /// BFT reads are then ordered instead, as a single reply cannot be trusted
+    First,
+}
+
+/// How the client talks to the replicas
+#[derive(Deserialize, Clone, Debug)]
+pub struct ConnectionConfig {
+    /// How many requests to keep outstanding at a time
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    #[serde(default)]
+    pub unordered_replies: UnorderedReplies,
+}
+
+/// A value, along with the sequence number it reflects
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Versioned {
+    pub value: i32,
+    /// For unordered reads, the latest one executed by the replicas that replied
+    pub seq_no: SeqNo,
+    /// The digest of the whole state, for [Operation::Digest]
+    pub digest: Option<Digest>,
+}
+
+#[derive(Error, Debug)]
+pub enum CalculatorError {
+    /// The replicas executed the request, but the operation could not be applied
+    #[error(transparent)]
+    Operation(#[from] OperationError),
+    #[error("Failed to bootstrap the client: {0:#}")]
+    Bootstrap(anyhow::Error),
+    #[error("Failed to get a reply from the replicas: {0:#}")]
+    Communication(anyhow::Error),
+    #[error("Fewer than a quorum of replicas replied within {timeout:?}, in each of {attempts} attempts")]
+    QuorumTimeout { timeout: Duration, attempts: u32 },
+    #[error("This node was deployed to tolerate f = {expected}, but the replicas tolerate f = {replicas}")]
+    ToleranceMismatch { expected: usize, replicas: i32 },
+}
+
+/// How long to wait for the replies to a request, and how many times to submit it
+#[derive(Deserialize, Clone, Debug)]
+pub struct RetryPolicy {
+    /// In milliseconds
+    #[serde(default = "default_request_timeout")]
+    request_timeout: u64,
+    #[serde(default = "default_max_attempts")]
+    max_attempts: u32,
+}
+
+/// A client for the calculator, which hides how requests reach the replicas.
+///
+/// Ordered operations (everything that changes the value, and [CalculatorClient::cas])
+/// go through the ordering protocol, while [CalculatorClient::get] is an unordered read
+pub struct CalculatorClient {
+    node_id: NodeId,
+    client: ConcurrentClient<ReconfProtocol, AppData, ClientNetwork>,
+    tracer: Option<Tracer>,
+    retry_policy: RetryPolicy,
+    max_concurrent_requests: usize,
+    unordered_replies: UnorderedReplies,
+    /// The latest sequence number reflected by a reply we got
+    last_seen: Mutex<SeqNo>,
+    /// The id of the trace of our next request without an id, when tracing
+    next_trace: AtomicU64,
+    /// The id of our next ordered request, which stays the same when it is resubmitted.
+    /// Zero while we have no session with the replicas
+    next_request: AtomicU64,
+    /// Held while opening a session, so concurrent requests do not replace each other's
+    opening_session: AsyncMutex<()>,
+}
+
+impl CalculatorClient {
+    /// Connect to the replicas, reading this node's identity and the network
+    /// configuration from the `config` folder
+    pub async fn connect() -> Result<Self, CalculatorError> {
+        Self::connect_with(ConnectionConfig::default()).await
+    }
+
+    /// Like [CalculatorClient::connect], with the given connection settings
+    pub async fn connect_with(connection: ConnectionConfig) -> Result<Self, CalculatorError> {
+        Self::bootstrap(None, connection).await
+    }
+
+    /// Connect with the identity of the given client, instead of the one in `nodes.toml`.
+    ///
+    /// Its credentials are read from `ca-root/cli<node id>`
+    pub async fn connect_as(node_id: NodeId, connection: ConnectionConfig) -> Result<Self, CalculatorError> {
+        Self::bootstrap(Some(node_id), connection).await
+    }
+
+    async fn bootstrap(node_id: Option<NodeId>, connection: ConnectionConfig) -> Result<Self, CalculatorError> {
+        let reconfig_config = get_reconfig_config::<FolderPathConstructor>(node_id.map(|node_id| node_id.0))
+            .map_err(CalculatorError::Bootstrap)?;
+
+        let node_id = reconfig_config.node_id;
+
+        let (network_conf, _pool_config) = get_network_configurations(node_id)
+            .map_err(CalculatorError::Bootstrap)?;
+
+        let client_cfg = ClientConfig {
+            unordered_rq_mode: connection.unordered_replies.into(),
+            node: network_conf,
+            reconfiguration: reconfig_config,
+        };
+
+        let client = client::bootstrap_client::<ReconfProtocol, AppData, ClientNode, Tolerance>(node_id, client_cfg).await
+            .map_err(CalculatorError::Bootstrap)?;
+
+        Self::from_client(client, connection)
+    }
+
+    /// Wrap an already bootstrapped client, which must have been configured
+    /// with the same unordered reply mode as `connection`
+    pub fn from_client(client: ExampleClient, connection: ConnectionConfig) -> Result<Self, CalculatorError> {
+        let node_id = client.id();
+
+        let max_concurrent_requests = connection.max_concurrent_requests.max(1);
+
+        let client = ConcurrentClient::from_client(client, max_concurrent_requests)
+            .map_err(CalculatorError::Bootstrap)?;
+
+        Ok(Self {
+            node_id,
+            client,
+            tracer: None,
+            retry_policy: RetryPolicy::default(),
+            max_concurrent_requests,
+            unordered_replies: connection.unordered_replies,
+            last_seen: Mutex::new(SeqNo::ZERO),
+            next_trace: AtomicU64::new(initial_trace_id()),
+            next_request: AtomicU64::new(0),
+            opening_session: AsyncMutex::new(()),
+        })
+    }
+
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy,
+            ..self
+        }
+    }
+
+    /// Emit a span for every request issued by this client
+    pub fn with_tracer(self, tracer: Option<Tracer>) -> Self {
+        Self {
+            tracer,
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.node_id
+    }
+
+    pub fn max_concurrent_requests(&self) -> usize {
+        self.max_concurrent_requests
+    }
+
+    pub async fn add(&self, value: i32) -> Result<i32, CalculatorError> {
+        self.ordered(Request::new(Operation::Add, value)).await.map(|reply| reply.value)
+    }
+
+    pub async fn sub(&self, value: i32) -> Result<i32, CalculatorError> {
+        self.ordered(Request::new(Operation::Sub, value)).await.map(|reply| reply.value)
+    }
+
+    pub async fn mul(&self, value: i32) -> Result<i32, CalculatorError> {
+        self.ordered(Request::new(Operation::Mult, value)).await.map(|reply| reply.value)
+    }
+
+    pub async fn div(&self, value: i32) -> Result<i32, CalculatorError> {
+        self.ordered(Request::new(Operation::Divide, value)).await.map(|reply| reply.value)
+    }
+
+    pub async fn rem(&self, value: i32) -> Result<i32, CalculatorError> {
+        self.ordered(Request::new(Operation::Remainder, value)).await.map(|reply| reply.value)
+    }
+
+    pub async fn pow(&self, exponent: i32) -> Result<i32, CalculatorError> {
+        self.ordered(Request::new(Operation::Exponent, exponent)).await.map(|reply| reply.value)
+    }
+
+    /// Read the current value, which at least f+1 replicas agree on
+    pub async fn get(&self) -> Result<i32, CalculatorError> {
+        self.read(ReadConsistency::BFT).await.map(|reply| reply.value)
+    }
+
+    /// Read the current value with the given consistency
+    pub async fn read(&self, consistency: ReadConsistency) -> Result<Versioned, CalculatorError> {
+        self.execute_versioned(Request::new(Operation::Get { consistency }, 0)).await
+    }
+
+    /// The latest sequence number reflected by a reply to this client
+    pub fn last_seen(&self) -> SeqNo {
+        *self.last_seen.lock().unwrap()
+    }
+
+    /// The digest of the state of the replicas, along with the sequence number it reflects.
+    ///
+    /// The request is ordered, so that every replica computes the digest at the same
+    /// sequence number and f+1 of their replies can match
+    pub async fn digest(&self) -> Result<(Digest, SeqNo), CalculatorError> {
+        let reply = self.execute_versioned(Request::new(Operation::Digest, 0)).await?;
+
+        let digest = reply.digest
+            .ok_or_else(|| CalculatorError::Communication(anyhow!("The replicas replied to a digest request without a digest")))?;
+
+        Ok((digest, reply.seq_no))
+    }
+
+    /// Check that the replicas were deployed to tolerate `f` faults, like this client was.
+    ///
+    /// A client that expects fewer faults than the replicas tolerate would trust too few replies
+    pub async fn check_tolerance(&self, f: usize) -> Result<(), CalculatorError> {
+        let replicas = self.execute(Request::new(Operation::Tolerance, 0)).await?;
+
+        if usize::try_from(replicas).ok() != Some(f) {
+            return Err(CalculatorError::ToleranceMismatch { expected: f, replicas });
+        }
+
+        Ok(())
+    }
+
+    /// Set the value to `new`, if it is currently `expected`.
+    ///
+    /// Fails with [OperationError::CompareAndSwapFailed] otherwise, carrying the current value
+    pub async fn cas(&self, expected: i32, new: i32) -> Result<i32, CalculatorError> {
+        self.ordered(Request::new(Operation::CompareAndSwap { expected }, new)).await.map(|reply| reply.value)
+    }
+
+    pub async fn execute(&self, request: Request) -> Result<i32, CalculatorError> {
+        self.execute_versioned(request).await.map(|reply| reply.value)
+    }
+
+    /// Execute a request, taking the path its consistency requires when it is a read.
+    ///
+    /// Read your writes is never behind the latest sequence number this client has seen
+    pub async fn execute_versioned(&self, request: Request) -> Result<Versioned, CalculatorError> {
+        let consistency = match request.operation() {
+            Operation::Get { consistency } => *consistency,
+            // Every replica answers with the same f, so f+1 replies match like in a BFT read
+            Operation::Tolerance => ReadConsistency::BFT,
+            _ => return self.ordered(request).await,
+        };
+
+        match consistency {
+            // Only takes the first reply when the connection does, see [UnorderedReplies]
+            ReadConsistency::Any => self.unordered(request).await,
+            ReadConsistency::BFT if self.unordered_replies == UnorderedReplies::Quorum => self.unordered(request).await,
+            // Without f+1 matching unordered replies, ordering the read is the only way to be sure of it
+            ReadConsistency::BFT | ReadConsistency::Linearizable => self.ordered(request).await,
+            ReadConsistency::ReadYourWrites { after } => {
+                let consistency = ReadConsistency::ReadYourWrites { after: after.max(self.last_seen()) };
+
+                self.unordered_until_executed(Request::new(Operation::Get { consistency }, request.value())).await
+            }
+        }
+    }
+
+    /// Submit all the requests, keeping up to the maximum amount of concurrent requests outstanding.
+    ///
+    /// Each reply comes with the index of its request in `requests`
+    pub fn submit_batch(&self, requests: Vec<Request>, order: ReplyOrder) -> BoxStream<'_, (usize, Result<i32, CalculatorError>)> {
+        let replies = stream::iter(requests.into_iter().enumerate())
+            .map(move |(index, request)| async move { (index, self.execute(request).await) });
+
+        match order {
+            ReplyOrder::Submission => replies.buffered(self.max_concurrent_requests).boxed(),
+            ReplyOrder::Completion => replies.buffer_unordered(self.max_concurrent_requests).boxed(),
+        }
+    }
+
+    /// Ordered requests carry an id, so the replicas only execute them once,
+    /// no matter how many times they are resubmitted
+    async fn ordered(&self, request: Request) -> Result<Versioned, CalculatorError> {
+        let request_id = self.next_request_id().await?;
+
+        let result = self.submit(request.with_id(request_id), self.retry_policy.max_attempts(), |request| self.client.update::<Ordered>(request)).await;
+
+        // The replicas evicted our session, so the next request opens a new one. The request
+        // itself may have been executed before the eviction, so it is not safe to resubmit it
+        if let Err(CalculatorError::Operation(OperationError::ExpiredRequest(_))) = &result {
+            let _ = self.next_request.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| {
+                (next >> 32 == request_id >> 32).then_some(0)
+            });
+        }
+
+        result
+    }
+
+    /// The id of our next ordered request, opening a session with the replicas when we have none.
+    ///
+    /// The replicas hand out the epoch of the session, so the ids never repeat
+    /// across sessions, even when the client restarts
+    async fn next_request_id(&self) -> Result<u64, CalculatorError> {
+        loop {
+            if let Ok(request_id) = self.next_request.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| (next != 0).then_some(next + 1)) {
+                return Ok(request_id);
+            }
+
+            let _opening = self.opening_session.lock().await;
+
+            if self.next_request.load(Ordering::Relaxed) != 0 {
+                continue;
+            }
+
+            let epoch = self.submit(Request::new(Operation::OpenSession, 0), self.retry_policy.max_attempts(), |request| self.client.update::<Ordered>(request)).await?;
+
+            let base = session_base(epoch.value as u32);
+
+            self.next_request.store(base + 1, Ordering::Relaxed);
+
+            return Ok(base);
+        }
+    }
+
+    /// Replicas stamp unordered replies with the latest sequence number they executed, so f+1
+    /// of them only match while the replicas are not executing anything. When they fail to match
+    /// within a single timeout, the request is ordered instead, which every replica answers at
+    /// the same sequence number
+    async fn unordered(&self, request: Request) -> Result<Versioned, CalculatorError> {
+        let send = |request| self.client.update::<Unordered>(request);
+
+        if self.unordered_replies == UnorderedReplies::First {
+            return self.submit(request, self.retry_policy.max_attempts(), send).await;
+        }
+
+        match self.submit(request.clone(), 1, send).await {
+            Err(CalculatorError::QuorumTimeout { .. }) => self.ordered(request).await,
+            result => result,
+        }
+    }
+
+    /// Replicas refuse reads that require a sequence number they have not executed yet,
+    /// so we keep asking until they catch up, or the request timeout expires
+    async fn unordered_until_executed(&self, request: Request) -> Result<Versioned, CalculatorError> {
+        let deadline = Instant::now() + self.retry_policy.timeout();
+
+        let mut backoff = INITIAL_READ_BACKOFF;
+
+        loop {
+            match self.unordered(request.clone()).await {
+                Err(CalculatorError::Operation(OperationError::NotExecutedYet { .. })) if Instant::now() + backoff < deadline => {
+                    Delay::new(backoff).await;
+
+                    backoff = (backoff * 2).min(MAX_READ_BACKOFF);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Submit the request until a quorum replies within the timeout, or we run out of attempts
+    async fn submit<F, Fut>(&self, request: Request, max_attempts: u32, send: F) -> Result<Versioned, CalculatorError>
+        where F: Fn(Request) -> Fut,
+              Fut: Future<Output=atlas_common::error::Result<Reply>> {
+        let timeout = self.retry_policy.timeout();
+
+        // A single span covers every attempt, as they all carry the same id, which the replicas' spans join
+        let mut span = self.tracer.as_ref().map(|tracer| {
+            let trace_id = match request.id() {
+                Some(request_id) => RequestTraceId::new(self.node_id, request_id),
+                None => RequestTraceId::local(self.node_id, self.next_trace.fetch_add(1, Ordering::Relaxed)),
+            };
+
+            tracer.start_span(trace_id, Stage::ClientRequest)
+        });
+
+        for attempt in 1..=max_attempts {
+            match select(pin!(send(request.clone())), Delay::new(timeout)).await {
+                Either::Left((reply, _)) => return self.finish(span, attempt, reply),
+                Either::Right(_) => warn!("No quorum replied to {:?} within {:?} (attempt {} of {})", request, timeout, attempt, max_attempts),
+            }
+        }
+
+        if let Some(mut span) = span.take() {
+            span.set_attribute("calculator.attempts", max_attempts);
+            span.finish();
+        }
+
+        Err(CalculatorError::QuorumTimeout { timeout, attempts: max_attempts })
+    }
+
+    fn finish(&self, span: Option<ActiveSpan<'_>>, attempts: u32, reply: atlas_common::error::Result<Reply>) -> Result<Versioned, CalculatorError> {
+        if let Some(mut span) = span {
+            span.set_attribute("calculator.attempts", attempts);
+            span.finish();
+        }
+
+        let reply = reply.map_err(CalculatorError::Communication)?;
+
+        let seq_no = reply.seq_no();
+
+        {
+            let mut last_seen = self.last_seen.lock().unwrap();
+
+            *last_seen = (*last_seen).max(seq_no);
+        }
+
+        let digest = reply.digest().copied();
+
+        let value = reply.into_result()?;
+
+        Ok(Versioned { value, seq_no, digest })
+    }
+
+    /// Wait for every span emitted by this client to be exported
+    pub fn flush(&self) {
+        if let Some(tracer) = &self.tracer {
+            tracer.flush();
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(request_timeout: Duration, max_attempts: u32) -> Self {
+        Self {
+            request_timeout: request_timeout.as_millis() as u64,
+            max_attempts,
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.request_timeout)
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts.max(1)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            request_timeout: default_request_timeout(),
+            max_attempts: default_max_attempts(),
+        }
+    }
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: default_max_concurrent_requests(),
+            unordered_replies: UnorderedReplies::default(),
+        }
+    }
+}
+
+impl From<UnorderedReplies> for UnorderedClientMode {
+    fn from(value: UnorderedReplies) -> Self {
+        match value {
+            UnorderedReplies::Quorum => UnorderedClientMode::BFT,
+            UnorderedReplies::First => UnorderedClientMode::BestEffort,
+        }
+    }
+}
+
+fn default_max_concurrent_requests() -> usize {
+    DEFAULT_MAX_CONCURRENT_REQUESTS
+}
+
+fn default_request_timeout() -> u64 {
+    5000
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+/// Trace ids only have to be unlikely to repeat across runs of the same client
+fn initial_trace_id() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
@@ -0,0 +1,37 @@
+use atlas_client::client::Client;
+use atlas_comm_mio::{ByteStubType, MIOTCPNode};
+use atlas_communication::{NodeInputStub, NodeStubController};
+use atlas_core::serialize::NoProtocol;
+use atlas_reconfiguration::message::ReconfData;
+use atlas_reconfiguration::network_reconfig::NetworkInfo;
+use atlas_reconfiguration::ReconfigurableNodeProtocolHandle;
+use atlas_smr_core::networking::client::{CLINodeWrapper, SMRClientNetworkNode};
+use atlas_smr_core::serialize::SMRSysMsg;
+use example_app::app::messages::AppData;
+use example_app::app::registers::RegisterAppData;
+
+pub mod calculator;
+pub mod registers;
+pub mod script;
+pub mod settings;
+pub mod watch;
+pub mod workload;
+
+pub type ReconfigurationMessage = ReconfData;
+
+/// The networking types are generic over the application data `D`, which is
+/// [RegisterAppData] for clients of the divisible state replica
+pub type CLIIncomingStub<D = AppData> = NodeInputStub<ReconfigurationMessage, NoProtocol, NoProtocol, SMRSysMsg<D>>;
+pub type CLIStubController<D = AppData> = NodeStubController<NetworkInfo, ByteStubType, ReconfigurationMessage, NoProtocol, NoProtocol, SMRSysMsg<D>>;
+
+pub type CLIByteNetworkLayer<D = AppData> = MIOTCPNode<NetworkInfo, CLIIncomingStub<D>, CLIStubController<D>>;
+
+pub type ClientNode<D = AppData> = CLINodeWrapper<ByteStubType, CLIByteNetworkLayer<D>, NetworkInfo, ReconfigurationMessage, D>;
+
+pub type ClientNetwork<D = AppData> = <ClientNode<D> as SMRClientNetworkNode<NetworkInfo, ReconfigurationMessage, D>>::AppNode;
+
+
+/// Set up the protocols with the types that have been built up to here
+pub type ReconfProtocol = ReconfigurableNodeProtocolHandle;
+pub type ExampleClient = Client<ReconfProtocol, AppData, ClientNetwork>;
+pub type RegisterClientHandle = Client<ReconfProtocol, RegisterAppData, ClientNetwork<RegisterAppData>>;
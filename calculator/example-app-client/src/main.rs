@@ -1,66 +1,169 @@
-use atlas_client::client;
-use atlas_client::client::{Client, ClientConfig};
-use atlas_client::client::unordered_client::UnorderedClientMode;
-use atlas_client::concurrent_client::ConcurrentClient;
-use atlas_comm_mio::{ByteStubType, MIOTCPNode};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use clap::Parser;
 use atlas_common::async_runtime;
-use atlas_communication::{NodeInputStub, NodeStubController};
-use atlas_core::ordering_protocol::OrderProtocolTolerance;
-use atlas_core::serialize::NoProtocol;
-use atlas_default_configs::{get_network_configurations, get_reconfig_config};
-use atlas_default_configs::crypto::{FlattenedPathConstructor, FolderPathConstructor};
-use atlas_reconfiguration::message::{ReconfData};
-use atlas_reconfiguration::network_reconfig::NetworkInfo;
-use atlas_reconfiguration::ReconfigurableNodeProtocolHandle;
-use atlas_smr_core::networking::client::{CLINodeWrapper, SMRClientNetworkNode};
-use atlas_smr_core::serialize::SMRSysMsg;
-use example_app::app::messages::AppData;
+use atlas_common::ordering::SeqNo;
+use atlas_default_configs::get_reconfig_config;
+use atlas_default_configs::crypto::FolderPathConstructor;
+use futures::StreamExt;
+use example_app::app::messages::{Operation, ReadConsistency, Request};
+use example_app::app::wire;
+use example_app::logging::{init_logging, NodeRole};
+use example_app::tolerance::{Quorum, Tolerance};
+use example_app::trace::Tracer;
+use example_app_client::calculator::{CalculatorClient, ReplyOrder};
+use example_app_client::script::{parse_script, run_script, write_results, ExecutionMode, ScriptError, ScriptLine};
+use example_app_client::settings;
+use example_app_client::settings::ClientArgs;
+use config::File;
+use config::FileFormat::Toml;
+use log::{error, info};
 
-pub type ReconfigurationMessage = ReconfData;
-pub type CLIIncomingStub = NodeInputStub<ReconfigurationMessage, NoProtocol, NoProtocol, SMRSysMsg<AppData>>;
-pub type CLIStubController = NodeStubController<NetworkInfo, ByteStubType, ReconfigurationMessage, NoProtocol, NoProtocol, SMRSysMsg<AppData>>;
+/// How many requests to submit through the pipeline, after the individual ones
+const PIPELINED_REQUESTS: usize = 1000;
 
-pub type CLIByteNetworkLayer = MIOTCPNode<NetworkInfo, CLIIncomingStub, CLIStubController>;
+fn main() {
+    let client_args = ClientArgs::parse();
+
+    let reconfig_config = get_reconfig_config::<FolderPathConstructor>(None).unwrap();
+
+    let node_id = reconfig_config.node_id;
+
+    let logging_cfg = settings::parse_logging_conf(File::new("config/logging.toml", Toml).required(false)).unwrap();
 
-pub type ClientNode = CLINodeWrapper<ByteStubType, CLIByteNetworkLayer, NetworkInfo, ReconfigurationMessage, AppData>;
+    let _log_handle = init_logging(node_id, NodeRole::Client, &logging_cfg).unwrap();
+
+    info!("Starting client {:?}", node_id);
+
+    let nodes_cfg = settings::parse_nodes_conf(File::new("config/nodes.toml", Toml)).unwrap();
+
+    let deployment_cfg = settings::parse_deployment_conf(File::new("config/deployment.toml", Toml)).unwrap();
+
+    let quorum = match Quorum::<Tolerance>::check(nodes_cfg.replica_count(), &deployment_cfg) {
+        Ok(quorum) => quorum,
+        Err(err) => {
+            error!("Refusing to start client: {}", err);
+
+            std::process::exit(1);
+        }
+    };
 
-pub type ClientNetwork = <ClientNode as SMRClientNetworkNode<NetworkInfo, ReconfigurationMessage, AppData>>::AppNode;
+    info!("Deployment of {} replicas, tolerating {} faults with quorums of {}", quorum.n(), quorum.f(), quorum.quorum());
 
+    let codec_cfg = settings::parse_codec_conf(File::new("config/codec.toml", Toml).required(false)).unwrap();
 
-/// Set up the protocols with the types that have been built up to here
-pub type ReconfProtocol = ReconfigurableNodeProtocolHandle;
-pub type ExampleClient = Client<ReconfProtocol, AppData, ClientNetwork>;
+    wire::set_codec(codec_cfg.codec).unwrap();
 
-pub struct BFT;
+    wire::set_limits(codec_cfg.limits).unwrap();
 
-impl OrderProtocolTolerance for BFT {
-    fn get_n_for_f(f: usize) -> usize {
-        return 3 * f + 1;
+    // Parse the script before connecting, so mistakes in it are reported right away
+    let script = client_args.script.as_ref().map(|path| {
+        match read_script(path) {
+            Ok(script) => script,
+            Err(err) => {
+                error!("Failed to load script {:?}: {}", path, err);
+
+                std::process::exit(2);
+            }
+        }
+    });
+
+    let tracing_cfg = settings::parse_tracing_conf(File::new("config/tracing.toml", Toml).required(false)).unwrap();
+
+    let tracer = Tracer::init(node_id, NodeRole::Client, &tracing_cfg).unwrap();
+
+    let retry_policy = settings::parse_retry_conf(File::new("config/requests.toml", Toml).required(false)).unwrap();
+
+    let connection_cfg = settings::parse_connection_conf(File::new("config/requests.toml", Toml).required(false)).unwrap();
+
+    let calculator = async_runtime::block_on(CalculatorClient::connect_with(connection_cfg))
+        .unwrap()
+        .with_tracer(tracer)
+        .with_retry_policy(retry_policy);
+
+    if let Err(err) = async_runtime::block_on(calculator.check_tolerance(quorum.f())) {
+        error!("Refusing to start client: {}", err);
+
+        std::process::exit(1);
     }
 
-    fn get_quorum_for_n(n: usize) -> usize {
-        return Self::get_f_for_n(n) * 2 + 1;
+    let Some(script) = script else {
+        async_runtime::block_on(run_demo(&calculator));
+
+        calculator.flush();
+
+        return;
+    };
+
+    let mode = if client_args.concurrent { ExecutionMode::Concurrent } else { ExecutionMode::Sequential };
+
+    let results = async_runtime::block_on(run_script(&calculator, script, mode));
+
+    calculator.flush();
+
+    let written = if client_args.results.as_os_str() == "-" {
+        write_results(std::io::stdout().lock(), &results)
+    } else {
+        std::fs::File::create(&client_args.results)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| write_results(BufWriter::new(file), &results))
+    };
+
+    if let Err(err) = written {
+        error!("Failed to write the results to {:?}: {:?}", client_args.results, err);
+
+        std::process::exit(2);
     }
 
-    fn get_f_for_n(n: usize) -> usize {
-        return (n - 1) / 3;
+    let failed = results.iter().filter(|result| !result.passed).count();
+
+    if failed > 0 {
+        error!("{} of {} operations did not match their expectation", failed, results.len());
+
+        std::process::exit(1);
     }
+
+    info!("Executed {} operations, all expectations matched", results.len());
 }
-fn main() {
-    let reconfig_config = get_reconfig_config::<FolderPathConstructor>(None).unwrap();
 
-    let node_id = reconfig_config.node_id;
+fn read_script(path: &Path) -> Result<Vec<ScriptLine>, ScriptError> {
+    if path.as_os_str() == "-" {
+        parse_script(std::io::stdin().lock())
+    } else {
+        parse_script(BufReader::new(std::fs::File::open(path)?))
+    }
+}
 
-    let (network_conf, pool_config) = get_network_configurations(node_id).unwrap();
+async fn run_demo(calculator: &CalculatorClient) {
+    let results = [
+        ("add 10", calculator.add(10).await),
+        ("mul 4", calculator.mul(4).await),
+        ("sub 2", calculator.sub(2).await),
+        ("div 2", calculator.div(2).await),
+        ("get", calculator.get().await),
+        ("read your writes", calculator.read(ReadConsistency::ReadYourWrites { after: SeqNo::ZERO }).await.map(|reply| reply.value)),
+    ];
 
-    let client_cfg = ClientConfig {
-        unordered_rq_mode: UnorderedClientMode::BFT,
-        node: network_conf,
-        reconfiguration: reconfig_config,
-    };
+    for (operation, result) in results {
+        match result {
+            Ok(value) => info!("Executed {}, the value is now {}", operation, value),
+            Err(err) => error!("Failed to execute {}: {}", operation, err),
+        }
+    }
 
-    let client = async_runtime::block_on(client::bootstrap_client::<ReconfProtocol, AppData, ClientNode, BFT>(node_id, client_cfg)).unwrap();
+    let batch = vec![Request::new(Operation::Add, 1); PIPELINED_REQUESTS];
+
+    let mut replies = calculator.submit_batch(batch, ReplyOrder::Completion);
+
+    let mut failed = 0;
+
+    while let Some((index, result)) = replies.next().await {
+        if let Err(err) = result {
+            error!("Failed to execute pipelined request {}: {}", index, err);
+
+            failed += 1;
+        }
+    }
 
-    // Initialize a concurrent client from the existing client
-    let concurrent_client = ConcurrentClient::from_client(client, 10).unwrap();
+    info!("Executed {} pipelined requests, {} failed", PIPELINED_REQUESTS, failed);
 }
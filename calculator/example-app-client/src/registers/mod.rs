@@ -0,0 +1,168 @@
+use std::future::Future;
+use std::pin::pin;
+use std::time::Duration;
+use futures::future::{select, Either};
+use futures_timer::Delay;
+use log::warn;
+use thiserror::Error;
+use atlas_client::client;
+use atlas_client::client::ClientConfig;
+use atlas_client::client::ordered_client::Ordered;
+use atlas_client::client::unordered_client::Unordered;
+use atlas_client::concurrent_client::ConcurrentClient;
+use atlas_common::crypto::hash::Digest;
+use atlas_common::node_id::NodeId;
+use atlas_default_configs::{get_network_configurations, get_reconfig_config};
+use atlas_default_configs::crypto::FolderPathConstructor;
+use example_app::app::messages::{Operation, OperationError, ReadConsistency, Request};
+use example_app::app::registers::{RegisterAppData, RegisterReply, RegisterRequest};
+use example_app::tolerance::Tolerance;
+use crate::calculator::{ConnectionConfig, RetryPolicy};
+use crate::{ClientNetwork, ClientNode, ReconfProtocol, RegisterClientHandle};
+
+#[derive(Error, Debug)]
+pub enum RegisterError {
+    #[error(transparent)]
+    Operation(#[from] OperationError),
+    #[error("Register {0} does not exist")]
+    UnknownRegister(u32),
+    #[error("The replicas replied with {0:?}, which does not answer the request")]
+    UnexpectedReply(RegisterReply),
+    #[error("Failed to bootstrap the client: {0:#}")]
+    Bootstrap(anyhow::Error),
+    #[error("Failed to get a reply from the replicas: {0:#}")]
+    Communication(anyhow::Error),
+    #[error("Fewer than a quorum of replicas replied within {timeout:?}, in each of {attempts} attempts")]
+    QuorumTimeout { timeout: Duration, attempts: u32 },
+    #[error("This node was deployed to tolerate f = {expected}, but the replicas tolerate f = {replicas}")]
+    ToleranceMismatch { expected: usize, replicas: i32 },
+}
+
+/// A client for the divisible state replica, whose state is made of registers
+/// instead of the single value of the calculator.
+///
+/// The replica keeps no sessions, so its requests carry no id: a request that is
+/// resubmitted after a timeout may be executed again
+pub struct RegisterClient {
+    node_id: NodeId,
+    client: ConcurrentClient<ReconfProtocol, RegisterAppData, ClientNetwork<RegisterAppData>>,
+    retry_policy: RetryPolicy,
+}
+
+impl RegisterClient {
+    /// Connect to the replicas, reading this node's identity and the network
+    /// configuration from the `config` folder
+    pub async fn connect(connection: ConnectionConfig) -> Result<Self, RegisterError> {
+        let reconfig_config = get_reconfig_config::<FolderPathConstructor>(None)
+            .map_err(RegisterError::Bootstrap)?;
+
+        let node_id = reconfig_config.node_id;
+
+        let (network_conf, _pool_config) = get_network_configurations(node_id)
+            .map_err(RegisterError::Bootstrap)?;
+
+        let client_cfg = ClientConfig {
+            unordered_rq_mode: connection.unordered_replies.into(),
+            node: network_conf,
+            reconfiguration: reconfig_config,
+        };
+
+        let client = client::bootstrap_client::<ReconfProtocol, RegisterAppData, ClientNode<RegisterAppData>, Tolerance>(node_id, client_cfg).await
+            .map_err(RegisterError::Bootstrap)?;
+
+        Self::from_client(client, connection)
+    }
+
+    pub fn from_client(client: RegisterClientHandle, connection: ConnectionConfig) -> Result<Self, RegisterError> {
+        let node_id = client.id();
+
+        let client = ConcurrentClient::from_client(client, connection.max_concurrent_requests.max(1))
+            .map_err(RegisterError::Bootstrap)?;
+
+        Ok(Self {
+            node_id,
+            client,
+            retry_policy: RetryPolicy::default(),
+        })
+    }
+
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy,
+            ..self
+        }
+    }
+
+    /// Apply an operation to a register, returning its new value
+    pub async fn apply(&self, register: u32, operation: Operation, value: i32) -> Result<i32, RegisterError> {
+        let request = RegisterRequest::new(register, Request::new(operation, value));
+
+        let reply = self.submit(request, |request| self.client.update::<Ordered>(request)).await?;
+
+        value_of(reply)
+    }
+
+    /// Read a register, which at least f+1 replicas agree on
+    pub async fn get(&self, register: u32) -> Result<i32, RegisterError> {
+        let request = RegisterRequest::new(register, Request::new(Operation::Get { consistency: ReadConsistency::BFT }, 0));
+
+        let reply = self.submit(request, |request| self.client.update::<Unordered>(request)).await?;
+
+        value_of(reply)
+    }
+
+    /// The digest of every register. The request is ordered, so that every replica
+    /// computes the digest at the same sequence number and f+1 of their replies can match
+    pub async fn digest(&self) -> Result<Digest, RegisterError> {
+        let request = RegisterRequest::new(0, Request::new(Operation::Digest, 0));
+
+        match self.submit(request, |request| self.client.update::<Ordered>(request)).await? {
+            RegisterReply::Digest(digest) => Ok(digest),
+            reply => Err(RegisterError::UnexpectedReply(reply)),
+        }
+    }
+
+    /// Check that the replicas were deployed to tolerate `f` faults, like this client was
+    pub async fn check_tolerance(&self, f: usize) -> Result<(), RegisterError> {
+        let request = RegisterRequest::new(0, Request::new(Operation::Tolerance, 0));
+
+        let replicas = value_of(self.submit(request, |request| self.client.update::<Unordered>(request)).await?)?;
+
+        if usize::try_from(replicas).ok() != Some(f) {
+            return Err(RegisterError::ToleranceMismatch { expected: f, replicas });
+        }
+
+        Ok(())
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// Submit the request until a quorum replies within the timeout, or we run out of attempts
+    async fn submit<F, Fut>(&self, request: RegisterRequest, send: F) -> Result<RegisterReply, RegisterError>
+        where F: Fn(RegisterRequest) -> Fut,
+              Fut: Future<Output=atlas_common::error::Result<RegisterReply>> {
+        let timeout = self.retry_policy.timeout();
+
+        let max_attempts = self.retry_policy.max_attempts();
+
+        for attempt in 1..=max_attempts {
+            match select(pin!(send(request.clone())), Delay::new(timeout)).await {
+                Either::Left((reply, _)) => return reply.map_err(RegisterError::Communication),
+                Either::Right(_) => warn!("No quorum replied to {:?} within {:?} (attempt {} of {})", request, timeout, attempt, max_attempts),
+            }
+        }
+
+        Err(RegisterError::QuorumTimeout { timeout, attempts: max_attempts })
+    }
+}
+
+fn value_of(reply: RegisterReply) -> Result<i32, RegisterError> {
+    match reply {
+        RegisterReply::Value(value) => Ok(value),
+        RegisterReply::Error(error) => Err(error.into()),
+        RegisterReply::UnknownRegister(register) => Err(RegisterError::UnknownRegister(register)),
+        reply => Err(RegisterError::UnexpectedReply(reply)),
+    }
+}
@@ -0,0 +1,321 @@
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+use futures::stream::{self, StreamExt};
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+use atlas_common::ordering::SeqNo;
+use example_app::app::messages::{Operation, ReadConsistency, Request};
+use crate::calculator::{CalculatorClient, CalculatorError, Versioned};
+
+/// A line of a script, such as `add 5`, `cas 10 20`, `get linearizable` or `mul 3 expect 45`.
+///
+/// Empty lines and everything after a `#` are ignored
+#[derive(Clone, Debug)]
+pub struct ScriptLine {
+    /// Starting at 1
+    pub line: usize,
+    /// The operation, as written in the script
+    pub text: String,
+    step: Step,
+    expectation: Option<Expectation>,
+}
+
+#[derive(Clone, Debug)]
+enum Step {
+    Execute(Request),
+    Read(ReadConsistency),
+}
+
+/// What a line expects its operation to result in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expectation {
+    Value(i32),
+    /// Any error, be it from the operation or from reaching the replicas
+    Error,
+}
+
+/// Whether the lines of a script run one after the other, or are all submitted
+/// up to the client's maximum concurrent requests
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionMode {
+    Sequential,
+    Concurrent,
+}
+
+#[derive(Error, Debug)]
+pub enum ScriptError {
+    #[error("Line {line}: unknown operation {operation}")]
+    UnknownOperation { line: usize, operation: String },
+    #[error("Line {line}: {operation} takes {expected} argument(s)")]
+    WrongArguments { line: usize, operation: String, expected: usize },
+    #[error("Line {line}: invalid argument {argument}: {reason}")]
+    InvalidArgument { line: usize, argument: String, reason: String },
+    #[error("Failed to read the script")]
+    Io(#[from] std::io::Error),
+}
+
+/// The outcome of a line, written as a line of JSON
+#[derive(Serialize, Debug)]
+pub struct ScriptResult {
+    pub line: usize,
+    pub operation: String,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<Expectation>,
+    pub passed: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum Outcome {
+    Value { value: i32, seq_no: SeqNo },
+    Error { error: String },
+}
+
+pub fn parse_script<R>(script: R) -> Result<Vec<ScriptLine>, ScriptError> where R: BufRead {
+    let mut lines = Vec::new();
+
+    for (index, text) in script.lines().enumerate() {
+        let text = text?;
+
+        let text = text.split('#').next().unwrap_or_default().trim();
+
+        if text.is_empty() {
+            continue;
+        }
+
+        lines.push(parse_line(index + 1, text)?);
+    }
+
+    Ok(lines)
+}
+
+fn parse_line(line: usize, text: &str) -> Result<ScriptLine, ScriptError> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+
+    let (words, expectation) = match words.iter().position(|word| *word == "expect") {
+        Some(position) => {
+            let expectation = match &words[position + 1..] {
+                [expected] => parse_argument(line, expected)?,
+                _ => return Err(ScriptError::WrongArguments { line, operation: "expect".to_string(), expected: 1 }),
+            };
+
+            (&words[..position], Some(expectation))
+        }
+        None => (&words[..], None),
+    };
+
+    let (operation, arguments) = words.split_first()
+        .ok_or_else(|| ScriptError::UnknownOperation { line, operation: String::new() })?;
+
+    let arguments = arguments.iter()
+        .map(|argument| parse_argument(line, argument))
+        .collect::<Result<Vec<i32>, _>>();
+
+    let wrong_arguments = |expected| ScriptError::WrongArguments { line, operation: operation.to_string(), expected };
+
+    let step = match operation.to_ascii_lowercase().as_str() {
+        "get" => {
+            let consistency = match words.get(1..) {
+                Some([]) | None => ReadConsistency::default(),
+                Some([consistency]) => consistency.parse()
+                    .map_err(|err: anyhow::Error| ScriptError::InvalidArgument { line, argument: consistency.to_string(), reason: err.to_string() })?,
+                Some(_) => return Err(wrong_arguments(1)),
+            };
+
+            Step::Read(consistency)
+        }
+        "cas" => match arguments?.as_slice() {
+            [expected, new] => Step::Execute(Request::new(Operation::CompareAndSwap { expected: *expected }, *new)),
+            _ => return Err(wrong_arguments(2)),
+        },
+        name => {
+            let operation = match name {
+                "add" => Operation::Add,
+                "sub" => Operation::Sub,
+                "mul" => Operation::Mult,
+                "div" => Operation::Divide,
+                "rem" => Operation::Remainder,
+                "pow" => Operation::Exponent,
+                _ => return Err(ScriptError::UnknownOperation { line, operation: operation.to_string() }),
+            };
+
+            match arguments?.as_slice() {
+                [value] => Step::Execute(Request::new(operation, *value)),
+                _ => return Err(wrong_arguments(1)),
+            }
+        }
+    };
+
+    Ok(ScriptLine {
+        line,
+        text: words.join(" "),
+        step,
+        expectation,
+    })
+}
+
+fn parse_argument<T>(line: usize, argument: &str) -> Result<T, ScriptError> where T: FromStr, T::Err: Display {
+    argument.parse()
+        .map_err(|err: T::Err| ScriptError::InvalidArgument { line, argument: argument.to_string(), reason: err.to_string() })
+}
+
+impl FromStr for Expectation {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("error") {
+            return Ok(Expectation::Error);
+        }
+
+        s.parse().map(Expectation::Value)
+    }
+}
+
+impl Display for Expectation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expectation::Value(value) => write!(f, "{}", value),
+            Expectation::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl Serialize for Expectation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        match self {
+            Expectation::Value(value) => serializer.serialize_i32(*value),
+            Expectation::Error => serializer.serialize_str("error"),
+        }
+    }
+}
+
+/// Run the script, returning the result of each line in the order of the script
+pub async fn run_script(client: &CalculatorClient, lines: Vec<ScriptLine>, mode: ExecutionMode) -> Vec<ScriptResult> {
+    let concurrency = match mode {
+        ExecutionMode::Sequential => 1,
+        ExecutionMode::Concurrent => client.max_concurrent_requests(),
+    };
+
+    stream::iter(lines)
+        .map(|line| run_line(client, line))
+        .buffered(concurrency)
+        .collect()
+        .await
+}
+
+async fn run_line(client: &CalculatorClient, line: ScriptLine) -> ScriptResult {
+    let result = match line.step {
+        Step::Execute(request) => client.execute_versioned(request).await,
+        Step::Read(consistency) => client.read(consistency).await,
+    };
+
+    let passed = match (line.expectation, &result) {
+        (None, _) => true,
+        (Some(Expectation::Value(expected)), Ok(Versioned { value, .. })) => expected == *value,
+        (Some(Expectation::Error), Err(_)) => true,
+        _ => false,
+    };
+
+    ScriptResult {
+        line: line.line,
+        operation: line.text,
+        outcome: result.into(),
+        expected: line.expectation,
+        passed,
+    }
+}
+
+impl From<Result<Versioned, CalculatorError>> for Outcome {
+    fn from(result: Result<Versioned, CalculatorError>) -> Self {
+        match result {
+            Ok(Versioned { value, seq_no, .. }) => Outcome::Value { value, seq_no },
+            Err(err) => Outcome::Error { error: err.to_string() },
+        }
+    }
+}
+
+/// Write each result as a line of JSON
+pub fn write_results<W>(mut w: W, results: &[ScriptResult]) -> atlas_common::error::Result<()> where W: Write {
+    for result in results {
+        serde_json::to_writer(&mut w, result)?;
+
+        writeln!(w)?;
+    }
+
+    w.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execute(text: &str) -> (Request, Option<Expectation>) {
+        match parse_line(1, text).unwrap() {
+            ScriptLine { step: Step::Execute(request), expectation, .. } => (request, expectation),
+            line => panic!("{} parsed as {:?}", text, line),
+        }
+    }
+
+    fn read(text: &str) -> (ReadConsistency, Option<Expectation>) {
+        match parse_line(1, text).unwrap() {
+            ScriptLine { step: Step::Read(consistency), expectation, .. } => (consistency, expectation),
+            line => panic!("{} parsed as {:?}", text, line),
+        }
+    }
+
+    #[test]
+    fn operations() {
+        assert_eq!(execute("add 5"), (Request::new(Operation::Add, 5), None));
+        assert_eq!(execute("SUB -3"), (Request::new(Operation::Sub, -3), None));
+        assert_eq!(execute("pow 2"), (Request::new(Operation::Exponent, 2), None));
+        assert_eq!(execute("cas 10 20"), (Request::new(Operation::CompareAndSwap { expected: 10 }, 20), None));
+    }
+
+    #[test]
+    fn reads() {
+        assert_eq!(read("get"), (ReadConsistency::BFT, None));
+        assert_eq!(read("get linearizable"), (ReadConsistency::Linearizable, None));
+        assert_eq!(read("get read_your_writes"), (ReadConsistency::ReadYourWrites { after: SeqNo::ZERO }, None));
+    }
+
+    #[test]
+    fn expectations() {
+        assert_eq!(execute("mul 3 expect 45"), (Request::new(Operation::Mult, 3), Some(Expectation::Value(45))));
+        assert_eq!(execute("div 0 expect error"), (Request::new(Operation::Divide, 0), Some(Expectation::Error)));
+        assert_eq!(read("get any expect 7"), (ReadConsistency::Any, Some(Expectation::Value(7))));
+
+        let line = parse_line(1, "cas 1  2   expect 2").unwrap();
+
+        assert_eq!(line.text, "cas 1 2");
+    }
+
+    #[test]
+    fn invalid_lines() {
+        assert!(matches!(parse_line(3, "foo 1"), Err(ScriptError::UnknownOperation { line: 3, operation }) if operation == "foo"));
+        assert!(matches!(parse_line(3, "expect 5"), Err(ScriptError::UnknownOperation { line: 3, .. })));
+        assert!(matches!(parse_line(3, "add"), Err(ScriptError::WrongArguments { expected: 1, .. })));
+        assert!(matches!(parse_line(3, "add 1 2"), Err(ScriptError::WrongArguments { expected: 1, .. })));
+        assert!(matches!(parse_line(3, "cas 1"), Err(ScriptError::WrongArguments { expected: 2, .. })));
+        assert!(matches!(parse_line(3, "get bft any"), Err(ScriptError::WrongArguments { expected: 1, .. })));
+        assert!(matches!(parse_line(3, "add 1 expect"), Err(ScriptError::WrongArguments { operation, .. }) if operation == "expect"));
+        assert!(matches!(parse_line(3, "add 1 expect 2 3"), Err(ScriptError::WrongArguments { operation, .. }) if operation == "expect"));
+        assert!(matches!(parse_line(3, "add x"), Err(ScriptError::InvalidArgument { argument, .. }) if argument == "x"));
+        assert!(matches!(parse_line(3, "get weird"), Err(ScriptError::InvalidArgument { argument, .. }) if argument == "weird"));
+        assert!(matches!(parse_line(3, "add 1 expect maybe"), Err(ScriptError::InvalidArgument { argument, .. }) if argument == "maybe"));
+    }
+
+    #[test]
+    fn comments_and_empty_lines_are_skipped() {
+        let script = "# A comment\n\nadd 5 # Five\n   \nget\n";
+
+        let lines = parse_script(script.as_bytes()).unwrap();
+
+        assert_eq!(lines.iter().map(|line| (line.line, line.text.as_str())).collect::<Vec<_>>(), [(3, "add 5"), (5, "get")]);
+        assert!(matches!(parse_script("add 1\nfoo".as_bytes()), Err(ScriptError::UnknownOperation { line: 2, .. })));
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+use clap::Parser;
+use config::{Config, Source};
+use atlas_common::error::*;
+use crate::calculator::{ConnectionConfig, RetryPolicy};
+use crate::workload::WorkloadConfig;
+
+pub use example_app::settings::{parse_codec_conf, parse_deployment_conf, parse_logging_conf, parse_nodes_conf, parse_tracing_conf, parse_watch_conf};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "An example client of the calculator, which runs a short demo or a script of operations")]
+pub struct ClientArgs {
+    /// Run the operations in this file, one per line, instead of the demo. Use - for stdin
+    #[arg(short, long, value_name = "SCRIPT", value_hint = clap::ValueHint::FilePath)]
+    pub script: Option<PathBuf>,
+    /// Submit all the operations of the script at once, instead of one after the other
+    #[arg(short, long, requires = "script")]
+    pub concurrent: bool,
+    /// Where to write the result of each operation of the script, as lines of JSON.
+    /// `-` is stdout, which only carries the results since the logs go to stderr,
+    /// so they can be piped to another tool
+    #[arg(short, long, value_name = "RESULTS", default_value = "-", requires = "script")]
+    pub results: PathBuf,
+}
+
+pub fn parse_retry_conf<T>(source: T) -> Result<RetryPolicy>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let retry_policy: RetryPolicy = settings.try_deserialize()?;
+
+    Ok(retry_policy)
+}
+
+pub fn parse_connection_conf<T>(source: T) -> Result<ConnectionConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let connection_config: ConnectionConfig = settings.try_deserialize()?;
+
+    Ok(connection_config)
+}
+
+pub fn parse_workload_conf<T>(source: T) -> Result<WorkloadConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let workload_config: WorkloadConfig = settings.try_deserialize()?;
+
+    Ok(workload_config)
+}
@@ -0,0 +1,258 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::BufReader;
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::anyhow;
+use log::{debug, warn};
+use atlas_common::error::*;
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use example_app::app::subscriptions::{read_message, watch_address, write_message, Notification, WatchConfig, WatchFilter, WatchMessage};
+use example_app::tolerance::NodesConfig;
+
+/// How many sequence numbers we keep the votes of a replica for, before giving up on its oldest
+/// one. Bounding each replica on its own keeps a faulty one from evicting the votes of the others
+const MAX_PENDING_VOTES_PER_REPLICA: usize = 1024;
+
+/// Notifications of the changes to the value, pushed by the replicas.
+///
+/// A notification is only delivered once `f+1` replicas sent the same one, so
+/// at least one correct replica vouches for it. Notifications are delivered in
+/// increasing sequence number order, and ones older than the last delivered are dropped.
+///
+/// Dropping the watcher closes its connections to the replicas
+pub struct Watcher {
+    rx: Receiver<(NodeId, Notification)>,
+    /// The connections to the replicas, each also held by the thread reading from it
+    connections: Vec<TcpStream>,
+    /// How many replicas must agree on a notification
+    votes_needed: usize,
+    votes: BTreeMap<SeqNo, HashMap<i32, HashSet<NodeId>>>,
+    /// The sequence numbers each replica has a pending vote at
+    pending: HashMap<NodeId, BTreeSet<SeqNo>>,
+    last_delivered: Option<SeqNo>,
+}
+
+impl Watcher {
+    /// Subscribe to every replica in `nodes`, with the given filter
+    pub fn connect(nodes: &NodesConfig, config: &WatchConfig, filter: WatchFilter, f: usize) -> Result<Self> {
+        let (tx, rx) = mpsc::channel();
+
+        let mut connections = Vec::new();
+
+        for node in nodes.replicas() {
+            let node_id = NodeId::from(node.node_id);
+
+            let stream = watch_address(node, config)
+                .and_then(|address| Ok(TcpStream::connect(address)?));
+
+            match stream.and_then(|stream| subscribe(node_id, stream, filter, f, tx.clone())) {
+                Ok(connection) => connections.push(connection),
+                Err(err) => warn!("Failed to subscribe to replica {:?}: {:?}", node_id, err),
+            }
+        }
+
+        if connections.len() <= f {
+            return Err(anyhow!("Only subscribed to {} replicas, but {} must agree on each notification", connections.len(), f + 1));
+        }
+
+        Ok(Self {
+            rx,
+            connections,
+            votes_needed: f + 1,
+            votes: BTreeMap::new(),
+            pending: HashMap::new(),
+            last_delivered: None,
+        })
+    }
+
+    /// Like [Iterator::next], giving up with [RecvTimeoutError::Timeout] when no notification
+    /// gets enough votes within `timeout`, so the caller can check whether it still wants them
+    pub fn next_timeout(&mut self, timeout: Duration) -> std::result::Result<Notification, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            let (replica, notification) = self.rx.recv_timeout(remaining)?;
+
+            if let Some(notification) = self.vote(replica, notification) {
+                return Ok(notification);
+            }
+        }
+    }
+
+    /// A replica only gets one vote per sequence number, for the first value it sent
+    fn vote(&mut self, replica: NodeId, notification: Notification) -> Option<Notification> {
+        if self.last_delivered.is_some_and(|delivered| notification.seq_no <= delivered) {
+            return None;
+        }
+
+        let pending = self.pending.entry(replica).or_default();
+
+        if !pending.insert(notification.seq_no) {
+            return None;
+        }
+
+        if pending.len() > MAX_PENDING_VOTES_PER_REPLICA {
+            match pending.pop_first() {
+                Some(oldest) if oldest == notification.seq_no => return None,
+                Some(oldest) => self.withdraw(replica, oldest),
+                None => {}
+            }
+        }
+
+        let voters = self.votes.entry(notification.seq_no).or_default()
+            .entry(notification.value).or_default();
+
+        voters.insert(replica);
+
+        if voters.len() >= self.votes_needed {
+            self.last_delivered = Some(notification.seq_no);
+
+            self.votes = self.votes.split_off(&notification.seq_no.next());
+
+            for pending in self.pending.values_mut() {
+                *pending = pending.split_off(&notification.seq_no.next());
+            }
+
+            return Some(notification);
+        }
+
+        None
+    }
+
+    fn withdraw(&mut self, replica: NodeId, seq_no: SeqNo) {
+        let Some(values) = self.votes.get_mut(&seq_no) else {
+            return;
+        };
+
+        values.retain(|_, voters| {
+            voters.remove(&replica);
+
+            !voters.is_empty()
+        });
+
+        if values.is_empty() {
+            self.votes.remove(&seq_no);
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        for connection in &self.connections {
+            let _ = connection.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl Iterator for Watcher {
+    type Item = Notification;
+
+    /// Block until the next notification enough replicas agree on,
+    /// or return `None` once every replica has disconnected
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (replica, notification) = self.rx.recv().ok()?;
+
+            if let Some(notification) = self.vote(replica, notification) {
+                return Some(notification);
+            }
+        }
+    }
+}
+
+/// Subscribe to a replica, which must tolerate `f` faults, like the watcher expects.
+/// Returns the connection, for the watcher to close
+fn subscribe(replica: NodeId, stream: TcpStream, filter: WatchFilter, f: usize, tx: Sender<(NodeId, Notification)>) -> Result<TcpStream> {
+    write_message(&stream, &WatchMessage::Subscribe(filter))?;
+
+    let connection = stream.try_clone()?;
+
+    let mut reader = BufReader::new(stream);
+
+    match read_message(&mut reader)? {
+        WatchMessage::Subscribed { f: replica_f } if replica_f == f => {}
+        WatchMessage::Subscribed { f: replica_f } => return Err(anyhow!("Replica {:?} tolerates f = {}, but the watcher expects f = {}", replica, replica_f, f)),
+        other => return Err(anyhow!("Expected the replica to acknowledge the subscription, got {:?}", other)),
+    }
+
+    thread::Builder::new()
+        .name(format!("Watch {:?}", replica))
+        .spawn(move || {
+
+            loop {
+                match read_message(&mut reader) {
+                    Ok(WatchMessage::Notification(notification)) => {
+                        if tx.send((replica, notification)).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(other) => {
+                        warn!("Replica {:?} sent an unexpected watch message {:?}", replica, other);
+
+                        break;
+                    }
+                    Err(err) => {
+                        debug!("Watch connection to replica {:?} closed: {:?}", replica, err);
+
+                        break;
+                    }
+                }
+            }
+        })?;
+
+    Ok(connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watcher(f: usize) -> Watcher {
+        Watcher {
+            rx: mpsc::channel().1,
+            connections: Vec::new(),
+            votes_needed: f + 1,
+            votes: BTreeMap::new(),
+            pending: HashMap::new(),
+            last_delivered: None,
+        }
+    }
+
+    fn notification(value: i32, seq_no: u32) -> Notification {
+        Notification { value, seq_no: SeqNo::from(seq_no) }
+    }
+
+    #[test]
+    fn notifications_need_f_plus_one_votes() {
+        let mut watcher = watcher(1);
+
+        assert_eq!(watcher.vote(NodeId(0), notification(5, 1)), None);
+        // A replica voting twice does not count twice
+        assert_eq!(watcher.vote(NodeId(0), notification(6, 1)), None);
+        assert_eq!(watcher.vote(NodeId(1), notification(6, 1)), None);
+        assert_eq!(watcher.vote(NodeId(2), notification(5, 1)), Some(notification(5, 1)));
+
+        // Older notifications than the delivered one are dropped
+        assert_eq!(watcher.vote(NodeId(3), notification(4, 1)), None);
+        assert!(watcher.votes.is_empty());
+    }
+
+    #[test]
+    fn a_flooding_replica_only_evicts_its_own_votes() {
+        let mut watcher = watcher(1);
+
+        assert_eq!(watcher.vote(NodeId(0), notification(5, 1)), None);
+
+        for seq_no in 2..2 + 2 * MAX_PENDING_VOTES_PER_REPLICA as u32 {
+            assert_eq!(watcher.vote(NodeId(3), notification(0, seq_no)), None);
+        }
+
+        assert_eq!(watcher.pending[&NodeId(3)].len(), MAX_PENDING_VOTES_PER_REPLICA);
+        assert_eq!(watcher.vote(NodeId(1), notification(5, 1)), Some(notification(5, 1)));
+    }
+}
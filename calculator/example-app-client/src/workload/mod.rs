@@ -0,0 +1,315 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use atlas_smr_application::app::Application;
+use example_app::app::App;
+use example_app::app::messages::{Operation, ReadConsistency, Reply, Request};
+use example_app::app::wire::CodecStats;
+use example_app::state::CalculatorState;
+use crate::calculator::{CalculatorClient, CalculatorError, ConnectionConfig, RetryPolicy};
+
+/// The logical clients to run, split in groups that share an operation mix.
+///
+/// Clients get consecutive node ids starting at `first_client_id`, in the order of the groups
+#[derive(Deserialize, Clone, Debug)]
+pub struct WorkloadConfig {
+    pub first_client_id: u32,
+    pub groups: Vec<ClientGroup>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ClientGroup {
+    pub clients: usize,
+    /// How many operations each client of the group submits
+    pub operations: usize,
+    /// How many requests each client of the group keeps outstanding
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// Operands are picked uniformly from `1..=max_operand`
+    #[serde(default = "default_max_operand")]
+    pub max_operand: i32,
+    pub mix: OperationMix,
+}
+
+/// The relative weight of each operation, operations left out are never picked
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct OperationMix {
+    #[serde(default)]
+    pub add: u32,
+    #[serde(default)]
+    pub sub: u32,
+    #[serde(default)]
+    pub mul: u32,
+    #[serde(default)]
+    pub div: u32,
+    #[serde(default)]
+    pub rem: u32,
+    #[serde(default)]
+    pub pow: u32,
+    /// Unordered reads, with f+1 matching replies
+    #[serde(default)]
+    pub get: u32,
+}
+
+/// How the operations of a client went
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ClientReport {
+    pub node_id: u32,
+    pub completed: usize,
+    /// The replicas replied, but the operation could not be applied (e.g. an overflow)
+    pub operation_errors: usize,
+    /// No reply from a quorum of replicas
+    pub failed: usize,
+    pub mean_latency_us: u64,
+    pub max_latency_us: u64,
+    pub throughput_ops: f64,
+}
+
+/// A client of the workload, with its own identity and session
+struct LogicalClient {
+    node_id: NodeId,
+    client: CalculatorClient,
+    group: ClientGroup,
+}
+
+/// A small xorshift generator, so a workload is the same every time it runs
+struct Rng(u64);
+
+impl OperationMix {
+    fn weighted(&self) -> [(Option<Operation>, u32); 7] {
+        [
+            (Some(Operation::Add), self.add),
+            (Some(Operation::Sub), self.sub),
+            (Some(Operation::Mult), self.mul),
+            (Some(Operation::Divide), self.div),
+            (Some(Operation::Remainder), self.rem),
+            (Some(Operation::Exponent), self.pow),
+            (None, self.get),
+        ]
+    }
+
+    fn total(&self) -> u64 {
+        self.weighted().iter().map(|(_, weight)| *weight as u64).sum()
+    }
+
+    fn pick(&self, rng: &mut Rng, max_operand: i32) -> Request {
+        let mut roll = rng.next() % self.total();
+
+        for (operation, weight) in self.weighted() {
+            if roll < weight as u64 {
+                return match operation {
+                    Some(operation) => Request::new(operation, rng.operand(max_operand)),
+                    None => Request::new(Operation::Get { consistency: ReadConsistency::BFT }, 0),
+                };
+            }
+
+            roll -= weight as u64;
+        }
+
+        unreachable!("The roll is always below the total weight")
+    }
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on 0
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+
+        self.0
+    }
+
+    fn operand(&mut self, max_operand: i32) -> i32 {
+        (self.next() % max_operand.max(1) as u64) as i32 + 1
+    }
+}
+
+/// Bootstrap every client of the workload, each with its own identity, and run them all at once.
+///
+/// Nothing runs unless the replicas tolerate `f` faults, like the workload expects
+pub async fn run_workload(config: &WorkloadConfig, retry_policy: &RetryPolicy, f: usize) -> Result<Vec<ClientReport>, CalculatorError> {
+    let mut clients = Vec::new();
+
+    let mut next_id = config.first_client_id;
+
+    for group in &config.groups {
+        if group.mix.total() == 0 {
+            error!("Skipping a group of {} clients, as its operation mix is empty", group.clients);
+
+            next_id += group.clients as u32;
+
+            continue;
+        }
+
+        for _ in 0..group.clients {
+            let node_id = NodeId(next_id);
+
+            next_id += 1;
+
+            let credentials = format!("ca-root/cli{}", node_id.0);
+
+            if !Path::new(&credentials).is_dir() {
+                error!("Skipping client {:?}, as it has no credentials in {}. Issue them with gen-client-certs.sh", node_id, credentials);
+
+                continue;
+            }
+
+            let connection = ConnectionConfig {
+                max_concurrent_requests: group.max_concurrent_requests,
+                ..ConnectionConfig::default()
+            };
+
+            match CalculatorClient::connect_as(node_id, connection).await {
+                Ok(client) => clients.push(LogicalClient {
+                    node_id,
+                    client: client.with_retry_policy(retry_policy.clone()),
+                    group: group.clone(),
+                }),
+                Err(err) => error!("Failed to bootstrap client {:?}: {}", node_id, err),
+            }
+        }
+    }
+
+    if let Some(first) = clients.first() {
+        first.client.check_tolerance(f).await?;
+    }
+
+    info!("Running the workload with {} clients", clients.len());
+
+    Ok(join_all(clients.iter().map(run_client)).await)
+}
+
+/// The messages of a workload, to compare how each codec encodes them
+pub struct WorkloadMessages {
+    /// Every request the workload submits, in the order each client submits them
+    pub requests: Vec<Request>,
+    /// The reply of a replica to each request
+    pub replies: Vec<Reply>,
+    /// The state of a replica once it executed every request
+    pub state: CalculatorState,
+}
+
+/// The statistics of a workload run, as a single JSON document
+#[derive(Serialize, Clone, Debug)]
+pub struct WorkloadReport {
+    pub clients: Vec<ClientReport>,
+    /// The size and encoding time of the workload's requests, replies and state with each codec
+    pub codecs: Vec<CodecStats>,
+}
+
+/// Execute every request of the workload locally, client after client, as a replica would
+pub fn workload_messages(config: &WorkloadConfig) -> WorkloadMessages {
+    let app = App::init();
+
+    let mut state = CalculatorState::default();
+
+    let mut requests = Vec::new();
+    let mut replies = Vec::new();
+
+    let mut next_id = config.first_client_id;
+
+    for group in &config.groups {
+        for _ in 0..group.clients {
+            let node_id = NodeId(next_id);
+
+            next_id += 1;
+
+            if group.mix.total() == 0 {
+                continue;
+            }
+
+            for (request_id, request) in client_requests(group, node_id).into_iter().enumerate() {
+                let ordered = !matches!(request.operation(), Operation::Get { .. });
+
+                if ordered {
+                    state.set_executed(SeqNo::from(replies.len() as u32));
+                }
+
+                let reply = app.update(&mut state, request.clone());
+
+                if ordered {
+                    state.record_reply(node_id, request_id as u64, reply.clone());
+                }
+
+                requests.push(request.with_id(request_id as u64));
+                replies.push(reply);
+            }
+        }
+    }
+
+    WorkloadMessages { requests, replies, state }
+}
+
+fn client_requests(group: &ClientGroup, node_id: NodeId) -> Vec<Request> {
+    let mut rng = Rng::new(node_id.0 as u64);
+
+    (0..group.operations)
+        .map(|_| group.mix.pick(&mut rng, group.max_operand))
+        .collect()
+}
+
+async fn run_client(client: &LogicalClient) -> ClientReport {
+    let requests = client_requests(&client.group, client.node_id);
+
+    let started = Instant::now();
+
+    let outcomes: Vec<(Duration, Result<i32, CalculatorError>)> = stream::iter(requests)
+        .map(|request| async move {
+            let sent = Instant::now();
+
+            let result = client.client.execute(request).await;
+
+            (sent.elapsed(), result)
+        })
+        .buffer_unordered(client.client.max_concurrent_requests())
+        .collect()
+        .await;
+
+    let elapsed = started.elapsed();
+
+    let mut report = ClientReport {
+        node_id: client.node_id.0,
+        ..ClientReport::default()
+    };
+
+    let mut total_latency = Duration::ZERO;
+
+    for (latency, result) in outcomes {
+        match result {
+            Ok(_) => report.completed += 1,
+            Err(CalculatorError::Operation(_)) => report.operation_errors += 1,
+            Err(_) => report.failed += 1,
+        }
+
+        total_latency += latency;
+        report.max_latency_us = report.max_latency_us.max(latency.as_micros() as u64);
+    }
+
+    let answered = report.completed + report.operation_errors;
+
+    if answered + report.failed > 0 {
+        report.mean_latency_us = (total_latency.as_micros() / (answered + report.failed) as u128) as u64;
+    }
+
+    report.throughput_ops = answered as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+
+    report
+}
+
+fn default_max_concurrent_requests() -> usize {
+    crate::calculator::DEFAULT_MAX_CONCURRENT_REQUESTS
+}
+
+fn default_max_operand() -> i32 {
+    10
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use axum::{Json, Router};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use serde::{Deserialize, Serialize};
+use atlas_common::ordering::SeqNo;
+use example_app::app::messages::{Operation, ReadConsistency, Request};
+use example_app_client::calculator::{CalculatorClient, CalculatorError, Versioned};
+use crate::history::{History, HistoryEntry};
+
+/// The operations that can be submitted through `POST /ops`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OpKind {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    /// Sets the value to `value` if it is currently `expected`
+    Cas,
+}
+
+/// The body of `POST /ops`, e.g. `{"op": "add", "value": 5}`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OpRequest {
+    pub op: OpKind,
+    pub value: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<i32>,
+}
+
+/// What the cluster replied, or why it could not be reached
+#[derive(Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum OpResponse {
+    Value { value: i32, seq_no: SeqNo },
+    Error { error: String },
+}
+
+pub struct GatewayState {
+    client: CalculatorClient,
+    history: History,
+}
+
+#[derive(Deserialize)]
+struct ValueQuery {
+    consistency: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    limit: Option<usize>,
+}
+
+impl GatewayState {
+    pub fn new(client: CalculatorClient, history: History) -> Self {
+        Self { client, history }
+    }
+}
+
+impl TryFrom<&OpRequest> for Request {
+    type Error = String;
+
+    fn try_from(value: &OpRequest) -> Result<Self, Self::Error> {
+        let operation = match value.op {
+            OpKind::Add => Operation::Add,
+            OpKind::Sub => Operation::Sub,
+            OpKind::Mul => Operation::Mult,
+            OpKind::Div => Operation::Divide,
+            OpKind::Rem => Operation::Remainder,
+            OpKind::Pow => Operation::Exponent,
+            OpKind::Cas => {
+                let expected = value.expected.ok_or("cas requires the expected value")?;
+
+                Operation::CompareAndSwap { expected }
+            }
+        };
+
+        Ok(Request::new(operation, value.value))
+    }
+}
+
+pub fn router(state: Arc<GatewayState>) -> Router {
+    Router::new()
+        .route("/ops", post(execute))
+        .route("/value", get(value))
+        .route("/history", get(history))
+        .with_state(state)
+}
+
+async fn execute(State(state): State<Arc<GatewayState>>, Json(op): Json<OpRequest>) -> (StatusCode, Json<OpResponse>) {
+    let request = match Request::try_from(&op) {
+        Ok(request) => request,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(OpResponse::Error { error })),
+    };
+
+    let (status, response) = respond(state.client.execute_versioned(request).await);
+
+    state.history.record(op, response.clone());
+
+    (status, Json(response))
+}
+
+/// `GET /value?consistency=bft`, where the consistency is one of any, bft, linearizable or read_your_writes.
+///
+/// Read your writes is relative to the replies this gateway has seen, from any of its users
+async fn value(State(state): State<Arc<GatewayState>>, Query(query): Query<ValueQuery>) -> (StatusCode, Json<OpResponse>) {
+    let consistency = match query.consistency.as_deref().map(str::parse::<ReadConsistency>).transpose() {
+        Ok(consistency) => consistency.unwrap_or_default(),
+        Err(err) => return (StatusCode::BAD_REQUEST, Json(OpResponse::Error { error: err.to_string() })),
+    };
+
+    let (status, response) = respond(state.client.read(consistency).await);
+
+    (status, Json(response))
+}
+
+async fn history(State(state): State<Arc<GatewayState>>, Query(query): Query<HistoryQuery>) -> Json<Vec<HistoryEntry>> {
+    Json(state.history.latest(query.limit))
+}
+
+fn respond(result: Result<Versioned, CalculatorError>) -> (StatusCode, OpResponse) {
+    match result {
+        Ok(Versioned { value, seq_no, .. }) => (StatusCode::OK, OpResponse::Value { value, seq_no }),
+        Err(err) => {
+            let status = match &err {
+                CalculatorError::Operation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+                CalculatorError::QuorumTimeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+                CalculatorError::Bootstrap(_) | CalculatorError::Communication(_) | CalculatorError::ToleranceMismatch { .. } => StatusCode::BAD_GATEWAY,
+            };
+
+            (status, OpResponse::Error { error: err.to_string() })
+        }
+    }
+}
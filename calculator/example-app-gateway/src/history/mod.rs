@@ -0,0 +1,60 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use serde::Serialize;
+use crate::api::{OpRequest, OpResponse};
+
+/// An operation the gateway proxied, and how it went
+#[derive(Serialize, Clone, Debug)]
+pub struct HistoryEntry {
+    /// When the reply arrived, in RFC 3339
+    pub timestamp: String,
+    #[serde(flatten)]
+    pub request: OpRequest,
+    pub response: OpResponse,
+}
+
+/// The latest operations proxied by this gateway, oldest first.
+///
+/// This is only what went through this gateway, not the history of the value
+pub struct History {
+    capacity: usize,
+    entries: Mutex<VecDeque<HistoryEntry>>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn record(&self, request: OpRequest, response: OpResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let entry = HistoryEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            request,
+            response,
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+
+        entries.push_back(entry);
+    }
+
+    /// The last `limit` entries, or all of them
+    pub fn latest(&self, limit: Option<usize>) -> Vec<HistoryEntry> {
+        let entries = self.entries.lock().unwrap();
+
+        let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+
+        entries.iter().skip(skip).cloned().collect()
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use atlas_default_configs::get_reconfig_config;
+use atlas_default_configs::crypto::FolderPathConstructor;
+use config::File;
+use config::FileFormat::Toml;
+use example_app::logging::{init_logging, NodeRole};
+use example_app::tolerance::{Quorum, Tolerance};
+use example_app::trace::Tracer;
+use example_app_client::calculator::CalculatorClient;
+use log::{error, info};
+use crate::api::GatewayState;
+use crate::history::History;
+
+mod api;
+mod history;
+mod settings;
+
+#[tokio::main]
+async fn main() {
+    let reconfig_config = get_reconfig_config::<FolderPathConstructor>(None).unwrap();
+
+    let node_id = reconfig_config.node_id;
+
+    let logging_cfg = example_app_client::settings::parse_logging_conf(File::new("config/logging.toml", Toml).required(false)).unwrap();
+
+    let _log_handle = init_logging(node_id, NodeRole::Client, &logging_cfg).unwrap();
+
+    info!("Starting gateway {:?}", node_id);
+
+    let nodes_cfg = example_app_client::settings::parse_nodes_conf(File::new("config/nodes.toml", Toml)).unwrap();
+
+    let deployment_cfg = example_app_client::settings::parse_deployment_conf(File::new("config/deployment.toml", Toml)).unwrap();
+
+    let quorum = match Quorum::<Tolerance>::check(nodes_cfg.replica_count(), &deployment_cfg) {
+        Ok(quorum) => quorum,
+        Err(err) => {
+            error!("Refusing to start gateway: {}", err);
+
+            std::process::exit(1);
+        }
+    };
+
+    let gateway_cfg = settings::parse_gateway_conf(File::new("config/gateway.toml", Toml)).unwrap();
+
+    let tracing_cfg = example_app_client::settings::parse_tracing_conf(File::new("config/tracing.toml", Toml).required(false)).unwrap();
+
+    let tracer = Tracer::init(node_id, NodeRole::Client, &tracing_cfg).unwrap();
+
+    let retry_policy = example_app_client::settings::parse_retry_conf(File::new("config/requests.toml", Toml).required(false)).unwrap();
+
+    let connection_cfg = example_app_client::settings::parse_connection_conf(File::new("config/requests.toml", Toml).required(false)).unwrap();
+
+    let client = CalculatorClient::connect_with(connection_cfg).await
+        .unwrap()
+        .with_tracer(tracer)
+        .with_retry_policy(retry_policy);
+
+    if let Err(err) = client.check_tolerance(quorum.f()).await {
+        error!("Refusing to start gateway: {}", err);
+
+        std::process::exit(1);
+    }
+
+    let state = Arc::new(GatewayState::new(client, History::new(gateway_cfg.history_capacity)));
+
+    let listener = tokio::net::TcpListener::bind(gateway_cfg.bind).await.unwrap();
+
+    info!("Serving the calculator on http://{}", gateway_cfg.bind);
+
+    if let Err(err) = axum::serve(listener, api::router(state)).await {
+        error!("Gateway stopped: {:?}", err);
+    }
+}
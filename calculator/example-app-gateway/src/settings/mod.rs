@@ -0,0 +1,26 @@
+use std::net::SocketAddr;
+use config::{Config, Source};
+use serde::Deserialize;
+use atlas_common::error::*;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GatewayConfig {
+    pub bind: SocketAddr,
+    #[serde(default = "default_history_capacity")]
+    pub history_capacity: usize,
+}
+
+pub fn parse_gateway_conf<T>(source: T) -> Result<GatewayConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let gateway_config: GatewayConfig = settings.try_deserialize()?;
+
+    Ok(gateway_config)
+}
+
+fn default_history_capacity() -> usize {
+    1000
+}
@@ -0,0 +1,100 @@
+use tonic::Status;
+use atlas_common::ordering::SeqNo;
+use example_app::app::messages;
+use example_app::app::subscriptions::Notification;
+use example_app_client::calculator::{CalculatorError, Versioned};
+use crate::proto;
+use crate::proto::operation_error::Kind;
+
+impl TryFrom<proto::Request> for messages::Request {
+    type Error = Status;
+
+    fn try_from(request: proto::Request) -> Result<Self, Self::Error> {
+        let operation = proto::Operation::try_from(request.operation)
+            .map_err(|_| Status::invalid_argument(format!("Unknown operation {}", request.operation)))?;
+
+        let operation = match operation {
+            proto::Operation::Add => messages::Operation::Add,
+            proto::Operation::Sub => messages::Operation::Sub,
+            proto::Operation::Mult => messages::Operation::Mult,
+            proto::Operation::Divide => messages::Operation::Divide,
+            proto::Operation::Remainder => messages::Operation::Remainder,
+            proto::Operation::Exponent => messages::Operation::Exponent,
+            proto::Operation::Get => {
+                let consistency = proto::ReadConsistency::try_from(request.consistency)
+                    .map_err(|_| Status::invalid_argument(format!("Unknown read consistency {}", request.consistency)))?;
+
+                let consistency = match consistency {
+                    proto::ReadConsistency::Bft => messages::ReadConsistency::BFT,
+                    proto::ReadConsistency::Any => messages::ReadConsistency::Any,
+                    proto::ReadConsistency::Linearizable => messages::ReadConsistency::Linearizable,
+                    // The client raises this to the latest sequence number it has seen
+                    proto::ReadConsistency::ReadYourWrites => messages::ReadConsistency::ReadYourWrites { after: SeqNo::ZERO },
+                };
+
+                messages::Operation::Get { consistency }
+            }
+            proto::Operation::CompareAndSwap => {
+                let expected = request.expected
+                    .ok_or_else(|| Status::invalid_argument("Compare and swap requires the expected value"))?;
+
+                messages::Operation::CompareAndSwap { expected }
+            }
+        };
+
+        Ok(messages::Request::new(operation, request.value))
+    }
+}
+
+impl From<messages::OperationError> for proto::OperationError {
+    fn from(error: messages::OperationError) -> Self {
+        let message = error.to_string();
+
+        let kind = match error {
+            messages::OperationError::DivisionByZero => Kind::DivisionByZero(proto::Empty {}),
+            messages::OperationError::NegativeExponent(exponent) => Kind::NegativeExponent(exponent),
+            messages::OperationError::Overflow => Kind::Overflow(proto::Empty {}),
+            messages::OperationError::CompareAndSwapFailed { current } => Kind::CompareAndSwapFailed(current),
+            messages::OperationError::ExpiredRequest(request) => Kind::ExpiredRequest(request),
+            messages::OperationError::NotExecutedYet { executed, required } => Kind::NotExecutedYet(proto::NotExecutedYet {
+                executed: u32::from(executed),
+                required: u32::from(required),
+            }),
+        };
+
+        Self {
+            kind: Some(kind),
+            message,
+        }
+    }
+}
+
+impl From<Notification> for proto::Notification {
+    fn from(notification: Notification) -> Self {
+        Self {
+            value: notification.value,
+            seq_no: u32::from(notification.seq_no),
+        }
+    }
+}
+
+/// The reply to send for the result of a request.
+///
+/// Errors applying the operation are part of the reply, as the replicas did reply,
+/// while failing to reach them is a [Status]. The replicas' sequence number is not
+/// kept along with operation errors, so those replies leave it unset
+pub fn reply(result: Result<Versioned, CalculatorError>) -> Result<proto::Reply, Status> {
+    match result {
+        Ok(Versioned { value, seq_no, .. }) => Ok(proto::Reply {
+            result: Some(proto::reply::Result::Value(value)),
+            seq_no: Some(u32::from(seq_no)),
+        }),
+        Err(CalculatorError::Operation(error)) => Ok(proto::Reply {
+            result: Some(proto::reply::Result::Error(error.into())),
+            seq_no: None,
+        }),
+        Err(err @ CalculatorError::QuorumTimeout { .. }) => Err(Status::deadline_exceeded(err.to_string())),
+        Err(err @ CalculatorError::Communication(_)) => Err(Status::unavailable(err.to_string())),
+        Err(err @ (CalculatorError::Bootstrap(_) | CalculatorError::ToleranceMismatch { .. })) => Err(Status::internal(err.to_string())),
+    }
+}
@@ -0,0 +1,8 @@
+/// The messages and the client and server stubs generated from `proto/calculator.proto`
+pub mod proto {
+    tonic::include_proto!("calculator");
+}
+
+pub mod convert;
+pub mod service;
+pub mod settings;
@@ -0,0 +1,74 @@
+use atlas_default_configs::get_reconfig_config;
+use atlas_default_configs::crypto::FolderPathConstructor;
+use config::File;
+use config::FileFormat::Toml;
+use log::{error, info};
+use tonic::transport::Server;
+use example_app::logging::{init_logging, NodeRole};
+use example_app::tolerance::{Quorum, Tolerance};
+use example_app::trace::Tracer;
+use example_app_client::calculator::CalculatorClient;
+use example_app_client::settings as client_settings;
+use example_app_grpc::proto::calculator_server::CalculatorServer;
+use example_app_grpc::service::CalculatorService;
+use example_app_grpc::settings;
+
+#[tokio::main]
+async fn main() {
+    let reconfig_config = get_reconfig_config::<FolderPathConstructor>(None).unwrap();
+
+    let node_id = reconfig_config.node_id;
+
+    let logging_cfg = client_settings::parse_logging_conf(File::new("config/logging.toml", Toml).required(false)).unwrap();
+
+    let _log_handle = init_logging(node_id, NodeRole::Client, &logging_cfg).unwrap();
+
+    info!("Starting gRPC server {:?}", node_id);
+
+    let nodes_cfg = client_settings::parse_nodes_conf(File::new("config/nodes.toml", Toml)).unwrap();
+
+    let deployment_cfg = client_settings::parse_deployment_conf(File::new("config/deployment.toml", Toml)).unwrap();
+
+    let quorum = match Quorum::<Tolerance>::check(nodes_cfg.replica_count(), &deployment_cfg) {
+        Ok(quorum) => quorum,
+        Err(err) => {
+            error!("Refusing to start gRPC server: {}", err);
+
+            std::process::exit(1);
+        }
+    };
+
+    let grpc_cfg = settings::parse_grpc_conf(File::new("config/grpc.toml", Toml)).unwrap();
+
+    let watch_cfg = client_settings::parse_watch_conf(File::new("config/watch.toml", Toml).required(false)).unwrap();
+
+    let tracing_cfg = client_settings::parse_tracing_conf(File::new("config/tracing.toml", Toml).required(false)).unwrap();
+
+    let tracer = Tracer::init(node_id, NodeRole::Client, &tracing_cfg).unwrap();
+
+    let retry_policy = client_settings::parse_retry_conf(File::new("config/requests.toml", Toml).required(false)).unwrap();
+
+    let connection_cfg = client_settings::parse_connection_conf(File::new("config/requests.toml", Toml).required(false)).unwrap();
+
+    let client = CalculatorClient::connect_with(connection_cfg).await
+        .unwrap()
+        .with_tracer(tracer)
+        .with_retry_policy(retry_policy);
+
+    if let Err(err) = client.check_tolerance(quorum.f()).await {
+        error!("Refusing to start gRPC server: {}", err);
+
+        std::process::exit(1);
+    }
+
+    let service = CalculatorService::new(client, nodes_cfg, watch_cfg, quorum.f());
+
+    info!("Serving the calculator over gRPC on {}", grpc_cfg.bind);
+
+    if let Err(err) = Server::builder()
+        .add_service(CalculatorServer::new(service))
+        .serve(grpc_cfg.bind)
+        .await {
+        error!("gRPC server stopped: {:?}", err);
+    }
+}
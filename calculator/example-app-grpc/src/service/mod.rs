@@ -0,0 +1,125 @@
+use std::sync::Arc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+use futures::stream::{BoxStream, StreamExt};
+use log::warn;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+use example_app::app::messages;
+use example_app::app::subscriptions::{WatchConfig, WatchFilter};
+use example_app::tolerance::NodesConfig;
+use example_app_client::calculator::CalculatorClient;
+use example_app_client::watch::Watcher;
+use crate::convert;
+use crate::proto;
+use crate::proto::calculator_server::Calculator;
+
+/// How many notifications a watch buffers while its gRPC client is slow to take them
+const WATCH_BUFFER: usize = 64;
+
+/// How often a watch without notifications checks whether its gRPC client went away
+const WATCH_CLOSED_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct CalculatorService {
+    client: Arc<CalculatorClient>,
+    nodes: NodesConfig,
+    watch: WatchConfig,
+    /// How many faulty replicas the deployment tolerates
+    f: usize,
+}
+
+impl CalculatorService {
+    pub fn new(client: CalculatorClient, nodes: NodesConfig, watch: WatchConfig, f: usize) -> Self {
+        Self {
+            client: Arc::new(client),
+            nodes,
+            watch,
+            f,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Calculator for CalculatorService {
+    async fn execute(&self, request: Request<proto::Request>) -> Result<Response<proto::Reply>, Status> {
+        let request = messages::Request::try_from(request.into_inner())?;
+
+        let reply = convert::reply(self.client.execute_versioned(request).await)?;
+
+        Ok(Response::new(reply))
+    }
+
+    type ExecuteBatchStream = BoxStream<'static, Result<proto::BatchReply, Status>>;
+
+    /// Requests are pipelined, keeping up to the client's maximum concurrent requests outstanding
+    async fn execute_batch(&self, request: Request<Streaming<proto::Request>>) -> Result<Response<Self::ExecuteBatchStream>, Status> {
+        let client = self.client.clone();
+
+        let max_concurrent_requests = client.max_concurrent_requests();
+
+        let replies = request.into_inner()
+            .enumerate()
+            .map(move |(index, request)| {
+                let client = client.clone();
+
+                async move {
+                    let request = messages::Request::try_from(request?)?;
+
+                    let reply = convert::reply(client.execute_versioned(request).await)?;
+
+                    Ok(proto::BatchReply {
+                        index: index as u64,
+                        reply: Some(reply),
+                    })
+                }
+            })
+            .buffer_unordered(max_concurrent_requests);
+
+        Ok(Response::new(replies.boxed()))
+    }
+
+    type WatchStream = ReceiverStream<Result<proto::Notification, Status>>;
+
+    /// Every watch subscribes to the replicas on its own, from a blocking thread, which
+    /// unsubscribes within [WATCH_CLOSED_CHECK_INTERVAL] of the gRPC client going away
+    async fn watch(&self, request: Request<proto::WatchRequest>) -> Result<Response<Self::WatchStream>, Status> {
+        let filter = match request.into_inner().threshold {
+            Some(threshold) => WatchFilter::Crossing { threshold },
+            None => WatchFilter::All,
+        };
+
+        let (tx, rx) = mpsc::channel(WATCH_BUFFER);
+
+        let nodes = self.nodes.clone();
+        let watch = self.watch.clone();
+        let f = self.f;
+
+        tokio::task::spawn_blocking(move || {
+            let mut watcher = match Watcher::connect(&nodes, &watch, filter, f) {
+                Ok(watcher) => watcher,
+                Err(err) => {
+                    warn!("Failed to subscribe to the replicas: {:?}", err);
+
+                    let _ = tx.blocking_send(Err(Status::unavailable(format!("Failed to subscribe to the replicas: {}", err))));
+
+                    return;
+                }
+            };
+
+            while !tx.is_closed() {
+                match watcher.next_timeout(WATCH_CLOSED_CHECK_INTERVAL) {
+                    Ok(notification) => {
+                        if tx.blocking_send(Ok(notification.into())).is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
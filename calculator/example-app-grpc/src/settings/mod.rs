@@ -0,0 +1,20 @@
+use std::net::SocketAddr;
+use config::{Config, Source};
+use serde::Deserialize;
+use atlas_common::error::*;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GrpcConfig {
+    pub bind: SocketAddr,
+}
+
+pub fn parse_grpc_conf<T>(source: T) -> Result<GrpcConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let grpc_config: GrpcConfig = settings.try_deserialize()?;
+
+    Ok(grpc_config)
+}
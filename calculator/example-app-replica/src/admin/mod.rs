@@ -0,0 +1,128 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use anyhow::{anyhow, Context};
+use log::{debug, info, warn};
+use atlas_common::error::*;
+use atlas_common::ordering::SeqNo;
+use example_app::app::admin::{AdminCommand, AdminConfig, AdminReply, CheckpointEntry};
+use example_app::app::checkpoints::{CheckpointDigest, CheckpointDigests};
+use example_app::app::wire::limits::rejected_payloads;
+use example_app::state::digest_hex;
+
+/// The longest command line we read, commands are a word and at most a sequence number
+const MAX_LINE: u64 = 256;
+
+/// Answer admin commands on the given address, in the background.
+///
+/// Commands are sent one per line of at most [MAX_LINE] bytes, see [AdminCommand], and each gets a line of json back.
+/// Every connection gets its own thread, and is closed once it stays idle for `read_timeout`
+pub fn start_admin_server(address: SocketAddr, config: &AdminConfig, f: usize, checkpoints: Arc<CheckpointDigests>) -> Result<()> {
+    let listener = TcpListener::bind(address)
+        .with_context(|| format!("Failed to bind the admin server to {}", address))?;
+
+    info!("Accepting admin commands on {}", address);
+
+    let (read_timeout, serve_snapshots) = (config.read_timeout(), config.serve_snapshots);
+
+    thread::Builder::new()
+        .name("Admin server".to_string())
+        .spawn(move || {
+            for connection in listener.incoming() {
+                match connection {
+                    Ok(stream) => {
+                        let checkpoints = checkpoints.clone();
+
+                        let spawned = thread::Builder::new()
+                            .name("Admin session".to_string())
+                            .spawn(move || {
+                                let peer = stream.peer_addr().ok();
+
+                                if let Err(err) = serve_admin(stream, read_timeout, serve_snapshots, f, &checkpoints) {
+                                    debug!("Admin session {:?} ended: {:?}", peer, err);
+                                }
+                            });
+
+                        if let Err(err) = spawned {
+                            warn!("Failed to spawn a thread for an admin session: {:?}", err);
+                        }
+                    }
+                    Err(err) => warn!("Failed to accept an admin connection: {:?}", err),
+                }
+            }
+        })
+        .context("Failed to spawn the admin server thread")?;
+
+    Ok(())
+}
+
+fn serve_admin(stream: TcpStream, read_timeout: Duration, serve_snapshots: bool, f: usize, checkpoints: &CheckpointDigests) -> Result<()> {
+    // A zero timeout would make reads block forever
+    stream.set_read_timeout(Some(read_timeout.max(Duration::from_millis(1))))?;
+
+    let mut writer = &stream;
+
+    let mut reader = BufReader::new(&stream);
+
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        // Bounded, so a peer that never sends a newline cannot make us buffer without end
+        if (&mut reader).take(MAX_LINE).read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        if !line.ends_with('\n') && line.len() as u64 == MAX_LINE {
+            return Err(anyhow!("Command longer than {} bytes", MAX_LINE));
+        }
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = match line.trim().parse() {
+            Ok(AdminCommand::Status) => AdminReply::Status { executed: u32::from(checkpoints.executed()), f },
+            Ok(AdminCommand::Checkpoints) => AdminReply::Checkpoints(checkpoints.recent().iter().map(checkpoint_entry).collect()),
+            Ok(AdminCommand::Rejected) => AdminReply::RejectedPayloads(rejected_payloads()),
+            Ok(AdminCommand::Snapshot) if !serve_snapshots => AdminReply::Error("This replica does not serve snapshots, see serve_snapshots in admin.toml".to_string()),
+            Ok(AdminCommand::Snapshot) => match checkpoints.next_snapshot(read_timeout) {
+                Some(snapshot) => {
+                    write_reply(&mut writer, &AdminReply::Snapshot)?;
+
+                    // The snapshot file is self delimiting, so it goes out as is right after the reply
+                    snapshot.write(&mut writer)?;
+
+                    continue;
+                }
+                None => AdminReply::Error(format!("No checkpoint was reached within {:?}", read_timeout)),
+            },
+            Ok(AdminCommand::CheckpointAfter(seq_no)) => AdminReply::Checkpoints(checkpoints.checkpoint_after(SeqNo::from(seq_no), read_timeout)
+                .iter()
+                .map(checkpoint_entry)
+                .collect()),
+            Err(err) => AdminReply::Error(err.to_string()),
+        };
+
+        write_reply(&mut writer, &reply)?;
+    }
+}
+
+fn checkpoint_entry(checkpoint: &CheckpointDigest) -> CheckpointEntry {
+    CheckpointEntry {
+        seq_no: u32::from(checkpoint.seq_no),
+        digest: digest_hex(&checkpoint.digest),
+    }
+}
+
+fn write_reply<W>(mut writer: W, reply: &AdminReply) -> Result<()> where W: Write {
+    serde_json::to_writer(&mut writer, reply)?;
+
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+
+    Ok(())
+}
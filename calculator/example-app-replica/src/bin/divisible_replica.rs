@@ -0,0 +1,38 @@
+use clap::Parser;
+use config::File;
+use config::FileFormat::Toml;
+use atlas_common::async_runtime;
+use atlas_smr_replica::server::divisible_state_server::DivStReplica;
+use log::error;
+use example_app_replica::{settings, startup};
+use example_app_replica::divisible::{init_div_replica_conf, init_replica_config, Application, SMRReplica};
+use example_app_replica::settings::ReplicaArgs;
+
+fn main() {
+    let replica_args = ReplicaArgs::parse();
+
+    let setup = startup::setup(&replica_args, "divisible state replica").unwrap();
+
+    if replica_args.snapshot.is_some() {
+        error!("Snapshots hold a monolithic state, so only the monolithic replica can start from one");
+
+        std::process::exit(1);
+    }
+
+    let state_transfer = settings::parse_div_state_transfer_conf(File::new("config/state_transfer.toml", Toml)).unwrap();
+
+    let replica_config = init_replica_config(setup.protocols);
+
+    let application = Application::init()
+        .with_tolerated_faults(setup.quorum.f());
+
+    let div_config = init_div_replica_conf(replica_config, state_transfer, application).unwrap();
+
+    let mut replica: SMRReplica = async_runtime::block_on(DivStReplica::bootstrap(div_config)).unwrap();
+
+    loop {
+        if let Err(err) = replica.run(None) {
+            error!("Error while executing replica {}", err);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! The calculator with its state split into shards of registers, which are checkpointed
+//! and transferred independently. Only the state, its transfer protocol, the persistent log,
+//! the executor and the replica itself differ from [crate::monolithic]
+
+use atlas_common::error::*;
+use atlas_common::ordering::SeqNo;
+use atlas_div_state_transfer::SimpleStateTransferProtocol;
+use atlas_div_state_transfer::config::StateTransferConfig;
+use atlas_div_state_transfer::message::serialize::STMsg;
+use atlas_persistent_log::stateful_logs::divisible_state::DivisibleStatePersistentLog;
+use atlas_smr_execution::SingleThreadedDivStExecutor;
+use atlas_smr_replica::config::DivisibleStateReplicaConfig;
+use atlas_smr_replica::server::divisible_state_server::DivStReplica;
+use example_app::app::registers::{RegisterApp, RegisterAppData};
+use example_app::state::registers::RegisterState;
+use crate::startup::ProtocolConfigs;
+use crate::types;
+
+pub type State = RegisterState;
+pub type ApplicationData = RegisterAppData;
+pub type Application = RegisterApp;
+
+/// The state transfer messages carry [example_app::state::registers::RegisterShard]s
+/// instead of the entire state
+pub type StateTransferMessage = STMsg<State>;
+
+pub type OrderProtocolMessage = types::OrderProtocolMessage<ApplicationData>;
+pub type DecLogMsg = types::DecLogMsg<ApplicationData>;
+pub type ReplicaNode = types::ReplicaNode<ApplicationData, StateTransferMessage>;
+pub type StateTransferNetwork = types::StateTransferNetwork<ApplicationData, StateTransferMessage>;
+
+/// The persistent log stores each part of the state separately, so a checkpoint only
+/// has to write the parts that were modified since the previous one
+pub type Logging = DivisibleStatePersistentLog<State, ApplicationData, OrderProtocolMessage, OrderProtocolMessage, DecLogMsg, StateTransferMessage>;
+
+pub type OrderProtocol = types::OrderProtocol<ApplicationData, StateTransferMessage>;
+pub type DecisionLog = types::DecisionLog<ApplicationData, StateTransferMessage, Logging>;
+pub type LogTransferProtocol = types::LogTransferProtocol<ApplicationData, StateTransferMessage, Logging>;
+pub type ViewTransferProt = types::ViewTransferProt<ApplicationData, StateTransferMessage>;
+pub type StateTransferProtocol = SimpleStateTransferProtocol<State, StateTransferNetwork, Logging>;
+
+pub type SMRReplica = DivStReplica<types::ReconfProtocol, SingleThreadedDivStExecutor, State, Application,
+    OrderProtocol, DecisionLog, StateTransferProtocol, LogTransferProtocol,
+    ViewTransferProt, ReplicaNode, Logging>;
+
+pub type ReplicaConf = types::ReplicaConf<State, ApplicationData, StateTransferMessage, StateTransferProtocol, Logging>;
+
+pub type DivConfig = DivisibleStateReplicaConfig::<types::ReconfProtocol, State, Application, OrderProtocol, DecisionLog,
+    StateTransferProtocol, LogTransferProtocol, ViewTransferProt, ReplicaNode, Logging>;
+
+pub fn init_replica_config(protocols: ProtocolConfigs) -> ReplicaConf {
+    ReplicaConf {
+        node: protocols.network,
+        next_consensus_seq: SeqNo::ZERO,
+        op_config: protocols.order_protocol,
+        dl_config: protocols.dec_log,
+        lt_config: protocols.log_transfer,
+        db_path: protocols.db_path,
+        pl_config: (),
+        reconfig_node: protocols.reconfiguration,
+        vt_config: protocols.view_transfer,
+        p: Default::default(),
+        preprocessor_threads: 1,
+    }
+}
+
+pub fn init_div_replica_conf(replica_conf: ReplicaConf,
+                             state_transfer_config: StateTransferConfig,
+                             service: Application) -> Result<DivConfig> {
+    Ok(DivConfig {
+        service,
+        replica_config: replica_conf,
+        st_config: state_transfer_config,
+    })
+}
@@ -0,0 +1,8 @@
+pub mod admin;
+pub mod divisible;
+pub mod monolithic;
+pub mod protocol;
+pub mod settings;
+pub mod startup;
+pub mod types;
+pub mod watch;
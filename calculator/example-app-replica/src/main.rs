@@ -1,209 +1,127 @@
-use std::ffi::OsString;
-use std::path::PathBuf;
+use std::sync::Arc;
 use anyhow::anyhow;
 use clap::Parser;
 use config::File;
 use config::FileFormat::Toml;
 use atlas_common::async_runtime;
-use atlas_common::ordering::SeqNo;
-use atlas_common::error::*;
-use atlas_decision_log::config::DecLogConfig;
-use atlas_decision_log::Log;
-use atlas_decision_log::serialize::LogSerialization;
-use atlas_log_transfer::CollabLogTransfer;
-use atlas_log_transfer::config::LogTransferConfig;
-use atlas_log_transfer::messages::serialize::LTMsg;
-use atlas_persistent_log::stateful_logs::monolithic_state::MonStatePersistentLog;
-use atlas_reconfiguration::config::ReconfigurableNetworkConfig;
-use atlas_reconfiguration::message::ReconfData;
-use atlas_reconfiguration::network_reconfig::NetworkInfo;
-use atlas_smr_execution::SingleThreadedMonExecutor;
-use atlas_smr_replica::config::{MonolithicStateReplicaConfig, ReplicaConfig};
+use atlas_common::node_id::NodeId;
+use atlas_smr_execution::{MultiThreadedMonExecutor, SingleThreadedMonExecutor};
 use atlas_smr_replica::server::monolithic_server::MonReplica;
-use atlas_view_transfer::config::ViewTransferConfig;
-use atlas_view_transfer::message::serialize::ViewTransfer;
-use atlas_view_transfer::SimpleViewTransferProtocol;
-use example_app::app::App;
-use example_app::app::messages::AppData;
-use example_app::state::CalculatorState;
-use febft_pbft_consensus::bft::config::PBFTConfig;
-use febft_pbft_consensus::bft::message::serialize::PBFTConsensus;
-use febft_pbft_consensus::bft::PBFTOrderProtocol;
-use febft_state_transfer::CollabStateTransfer;
-use febft_state_transfer::config::StateTransferConfig;
-use febft_state_transfer::message::serialize::CSTMsg;
-use log::error;
-use atlas_comm_mio::{ByteStubType, MIOTCPNode};
-use atlas_comm_mio::config::MIOConfig;
-use atlas_communication::{NodeInputStub, NodeStubController};
-use atlas_default_configs::{get_network_configurations, get_reconfig_config};
-use atlas_default_configs::crypto::FolderPathConstructor;
-use atlas_reconfiguration::ReconfigurableNodeProtocolHandle;
-use atlas_smr_core::networking::{ReplicaNodeWrapper, SMRReplicaNetworkNode};
-use atlas_smr_core::request_pre_processing::RequestPreProcessor;
-use atlas_smr_core::serialize::{Service, SMRSysMsg, StateSys};
-use atlas_smr_core::SMRReq;
-use atlas_smr_replica::server::Exec;
-use crate::settings::ReplicaArgs;
-
-mod settings;
-
-
-/// If you want to use the default configurations,
-/// just change these types to whichever types you want to use
-pub type State = CalculatorState;
-pub type ApplicationData = AppData;
-pub type Application = App;
-
-/// Set up the data handles so we initialize the networking layer
-pub type ReconfigurationMessage = ReconfData;
-
-/// In the case of SMR messages, we want the type that is going to be ordered to include just the actual
-/// SMR Ordered Request Type, so we can use the same type for the ordering protocol
-/// This type, for SMR is [atlas_smr_core::serialize::SMRReq]
-///
-/// These protocols are only going to be used for the ordered requests, so they only have to know about the ordered requests
-/// In further parts, we can utilize [MicrobenchmarkData] directly as it requires a [D: ApplicationData], instead of just [SerType]
-pub type OrderProtocolMessage = PBFTConsensus<SMRReq<ApplicationData>>;
-pub type DecLogMsg = LogSerialization<SMRReq<ApplicationData>, OrderProtocolMessage, OrderProtocolMessage>;
-pub type LogTransferMessage = LTMsg<SMRReq<ApplicationData>, OrderProtocolMessage, OrderProtocolMessage, DecLogMsg>;
-pub type ViewTransferMessage = ViewTransfer<OrderProtocolMessage>;
-
-/// The state transfer also requires wrapping in order to keep the [atlas_communication::serialization::Serializable] type
-/// out of the state transfer protocol (and all others for that matter) for further flexibility
-/// Therefore, we have to wrap the [atlas_smr_core::serialize::StateSys] type in order to get the [atlas_communication::serialization::Serializable] trait
-///
-pub type StateTransferMessage = CSTMsg<State>;
-pub type SerStateTransferMessage = StateSys<StateTransferMessage>;
-
-
-/// This type is the protocol type responsible for all SMR messages including unordered ones, so it already knows about [atlas_smr_application::ApplicationData]
-pub type ProtocolDataType = Service<ApplicationData, OrderProtocolMessage, LogTransferMessage, ViewTransferMessage>;
-
-/// Set up the networking layer with the data handles we have
-///
-/// In the networking level, we utilize the type which wraps [atlas_smr_application::ApplicationData]
-/// and provides the [atlas_communication::serialization::Serializable] type required
-/// for the network layer.
-///
-/// For that, we use [atlas_smr_core::serialize::SMRSysMsg]
-
-/// Replica stub things
-pub type IncomingStub = NodeInputStub<ReconfigurationMessage, ProtocolDataType, SerStateTransferMessage, SMRSysMsg<ApplicationData>>;
-pub type StubController = NodeStubController<NetworkInfo, ByteStubType, ReconfigurationMessage, ProtocolDataType, SerStateTransferMessage, SMRSysMsg<ApplicationData>>;
-
-pub type ByteNetworkLayer = MIOTCPNode<NetworkInfo, IncomingStub, StubController>;
-
-pub type ReplicaNode = ReplicaNodeWrapper<ByteStubType, ByteNetworkLayer, NetworkInfo, ReconfigurationMessage, ApplicationData, OrderProtocolMessage,
-    LogTransferMessage, ViewTransferMessage, StateTransferMessage>;
-
-pub type ProtocolNetwork = <ReplicaNode as SMRReplicaNetworkNode<NetworkInfo, ReconfigurationMessage, ApplicationData, OrderProtocolMessage,
-    LogTransferMessage, ViewTransferMessage, StateTransferMessage>>::ProtocolNode;
-
-pub type StateTransferNetwork = <ReplicaNode as SMRReplicaNetworkNode<NetworkInfo, ReconfigurationMessage, ApplicationData, OrderProtocolMessage,
-    LogTransferMessage, ViewTransferMessage, StateTransferMessage>>::StateTransferNode;
-
-pub type AppNetwork = <ReplicaNode as SMRReplicaNetworkNode<NetworkInfo, ReconfigurationMessage, ApplicationData, OrderProtocolMessage,
-    LogTransferMessage, ViewTransferMessage, StateTransferMessage>>::ApplicationNode;
-
-pub type ReconfigurationNode = <ReplicaNode as SMRReplicaNetworkNode<NetworkInfo, ReconfigurationMessage, ApplicationData, OrderProtocolMessage,
-    LogTransferMessage, ViewTransferMessage, StateTransferMessage>>::ReconfigurationNode;
-
-/// Set up the persistent logging type with the existing data handles
-pub type Logging = MonStatePersistentLog<State, ApplicationData, OrderProtocolMessage, OrderProtocolMessage, DecLogMsg, StateTransferMessage>;
-
-/// Set up the protocols with the types that have been built up to here
-pub type ReconfProtocol = ReconfigurableNodeProtocolHandle;
-pub type OrderProtocol = PBFTOrderProtocol<SMRReq<ApplicationData>, RequestPreProcessor<SMRReq<ApplicationData>>, ProtocolNetwork>;
-pub type DecisionLog = Log<SMRReq<ApplicationData>, OrderProtocol, Logging, Exec<ApplicationData>>;
-pub type LogTransferProtocol = CollabLogTransfer<SMRReq<ApplicationData>, OrderProtocol, DecisionLog, ProtocolNetwork, Logging, Exec<ApplicationData>>;
-pub type ViewTransferProt = SimpleViewTransferProtocol<OrderProtocol, ProtocolNetwork>;
-pub type StateTransferProtocol = CollabStateTransfer<State, StateTransferNetwork, Logging>;
-
-
-pub type SMRReplica = MonReplica<ReconfProtocol, SingleThreadedMonExecutor, State, Application,
-    OrderProtocol, DecisionLog, StateTransferProtocol, LogTransferProtocol,
-    ViewTransferProt, ReplicaNode, Logging>;
-
-pub type ReplicaConf = ReplicaConfig::<ReconfProtocol, State, ApplicationData, OrderProtocol, DecisionLog,
-    StateTransferProtocol, LogTransferProtocol, ViewTransferProt, ReplicaNode, Logging>;
-
-pub type MonConfig = MonolithicStateReplicaConfig::<ReconfProtocol, State, Application, OrderProtocol, DecisionLog,
-    StateTransferProtocol, LogTransferProtocol, ViewTransferProt, ReplicaNode, Logging>;
-
-pub fn init_replica_config(reconf: ReconfigurableNetworkConfig, network: MIOConfig,
-                           order_protocol_config: PBFTConfig, log_transfer_config: LogTransferConfig,
-                           dec_log_config: DecLogConfig, view_transfer_config: ViewTransferConfig,
-                           db_path: PathBuf)
-                           -> Result<ReplicaConf> {
-    let db_path = db_path.into_os_string().into_string();
-
-    let db_path = match db_path {
-        Ok(db) => db,
-        Err(_) => {
-            return Err(anyhow!("Failed to parse persistent log folder"));
-        }
-    };
+use example_app::app::admin::{admin_bind_address, only_reachable_locally};
+use example_app::app::checkpoints::CheckpointDigests;
+use example_app::app::subscriptions::{watch_address, SubscriptionRegistry};
+use example_app::app::wire;
+use example_app::logging::NodeRole;
+use example_app::snapshot::Snapshot;
+use example_app::state::digest_hex;
+use example_app::trace::Tracer;
+use log::{error, info, warn};
+use example_app_replica::{admin, settings, startup, watch};
+use example_app_replica::monolithic::{init_mon_replica_conf, init_replica_config, Application, SMRReplica};
+use example_app_replica::settings::{ExecutorKind, ReplicaArgs};
 
-    let conf = ReplicaConf {
-        node: network,
-        next_consensus_seq: SeqNo::ZERO,
-        op_config: order_protocol_config,
-        dl_config: dec_log_config,
-        lt_config: log_transfer_config,
-        db_path,
-        pl_config: (),
-        reconfig_node: reconf,
-        vt_config: view_transfer_config,
-        p: Default::default(),
-        preprocessor_threads: 1,
-    };
+fn main() {
+    let replica_args = ReplicaArgs::parse();
 
-    Ok(conf)
-}
+    let setup = startup::setup(&replica_args, "replica").unwrap();
 
+    let codec_cfg = settings::parse_codec_conf(File::new("config/codec.toml", Toml).required(false)).unwrap();
 
-pub fn init_mon_replica_conf(replica_conf: ReplicaConf,
-                             state_transfer_config: StateTransferConfig,
-                             service: Application) -> Result<MonConfig> {
-    Ok(MonConfig {
-        service,
-        replica_config: replica_conf,
-        st_config: state_transfer_config,
-    })
-}
+    wire::set_codec(codec_cfg.codec).unwrap();
 
+    wire::set_limits(codec_cfg.limits).unwrap();
 
-fn main() {
-    let replica_args = ReplicaArgs::parse();
-
-    let reconfiguration_cfg = get_reconfig_config::<FolderPathConstructor>(None).unwrap();
+    if let Some(path) = &replica_args.snapshot {
+        let mut snapshot = Snapshot::load(path).unwrap();
 
-    let (network_cfg, pool_config) = get_network_configurations(reconfiguration_cfg.node_id).unwrap();
+        info!("Starting from the snapshot of sequence number {:?}, with value {} and digest {}",
+            snapshot.seq_no, snapshot.state.value(), digest_hex(&snapshot.state.digest()));
 
-    // Read all configs from the corresponding files, then create the replica config, then create the MonConfig
-    let config = settings::parse_febft_conf(File::new("config/febft.toml", Toml)).unwrap();
+        // The new cluster orders from sequence number 0 again, so the state must not claim to
+        // have executed further than that. Sequence numbers clients got from the old cluster,
+        // such as the ones read your writes waits for, mean nothing to the new one
+        warn!("Sequence numbers and sessions from before the snapshot ({:?}) do not carry over, clients have to reconnect", snapshot.seq_no);
 
-    let dec_log_config = settings::parse_dec_log_conf(File::new("config/dec_log.toml", Toml)).unwrap();
+        snapshot.state.restart();
 
-    let log_transfer = settings::parse_log_transfer_conf(File::new("config/log_transfer.toml", Toml)).unwrap();
+        example_app::app::set_initial_state(snapshot.state).unwrap();
+    }
 
     let state_transfer = settings::parse_state_transfer_conf(File::new("config/state_transfer.toml", Toml)).unwrap();
 
-    let view_transfer = settings::parse_view_transfer_conf(File::new("config/view_transfer.toml", Toml)).unwrap();
+    let tracing_cfg = settings::parse_tracing_conf(File::new("config/tracing.toml", Toml).required(false)).unwrap();
+
+    let tracer = Tracer::init(setup.node_id, NodeRole::Replica, &tracing_cfg).unwrap();
+
+    let executor_cfg = settings::parse_executor_conf(File::new("config/executor.toml", Toml).required(false)).unwrap();
+
+    let watch_cfg = settings::parse_watch_conf(File::new("config/watch.toml", Toml).required(false)).unwrap();
+
+    let subscriptions = if watch_cfg.enabled {
+        let own_node = setup.nodes.replicas()
+            .find(|node| NodeId::from(node.node_id) == setup.node_id)
+            .ok_or_else(|| anyhow!("Replica {:?} is not listed in nodes.toml", setup.node_id)).unwrap();
 
-    let replica_config = init_replica_config(reconfiguration_cfg, network_cfg, config,
-                                             log_transfer, dec_log_config, view_transfer,
-                                             replica_args.db_path).unwrap();
+        let registry = Arc::new(SubscriptionRegistry::new(watch_cfg.max_pending_notifications));
 
-    let mon_config = init_mon_replica_conf(replica_config, state_transfer,
-                                           Application::init()).unwrap();
+        watch::start_watch_server(watch_address(own_node, &watch_cfg).unwrap(), &watch_cfg, setup.quorum.f(), registry.clone()).unwrap();
 
-    let mut replica: SMRReplica = async_runtime::block_on(MonReplica::bootstrap(mon_config)).unwrap();
+        Some(registry)
+    } else {
+        None
+    };
+
+    let admin_cfg = settings::parse_admin_conf(File::new("config/admin.toml", Toml).required(false)).unwrap();
+
+    let checkpoints = if admin_cfg.enabled {
+        let own_node = setup.nodes.replicas()
+            .find(|node| NodeId::from(node.node_id) == setup.node_id)
+            .ok_or_else(|| anyhow!("Replica {:?} is not listed in nodes.toml", setup.node_id)).unwrap();
+
+        if only_reachable_locally(own_node, &admin_cfg) {
+            warn!("The admin server only listens on {}, so the divergence detector and the snapshot tool can only reach it from this host. \
+                Set bind_ip in admin.toml to reach it at {}", admin_cfg.bind_ip, own_node.ip);
+        }
+
+        let checkpoints = Arc::new(CheckpointDigests::new(&admin_cfg.checkpoints));
 
-    loop {
-        if let Err(err) = replica.run(None) {
-            error!("Error while executing replica {}", err);
+        admin::start_admin_server(admin_bind_address(own_node, &admin_cfg).unwrap(), &admin_cfg, setup.quorum.f(), checkpoints.clone()).unwrap();
+
+        Some(checkpoints)
+    } else {
+        None
+    };
+
+    let application = Application::init()
+        .with_tracer(tracer)
+        .with_subscriptions(subscriptions)
+        .with_checkpoint_digests(checkpoints)
+        .with_tolerated_faults(setup.quorum.f());
+
+    let replica_config = init_replica_config(setup.protocols);
+
+    let mon_config = init_mon_replica_conf(replica_config, state_transfer, application).unwrap();
+
+    info!("Running replica with the {:?} executor", executor_cfg.executor);
+
+    match executor_cfg.executor {
+        ExecutorKind::SingleThreaded => {
+            let mut replica: SMRReplica<SingleThreadedMonExecutor> = async_runtime::block_on(MonReplica::bootstrap(mon_config)).unwrap();
+
+            loop {
+                if let Err(err) = replica.run(None) {
+                    error!("Error while executing replica {}", err);
+                }
+            }
+        }
+        ExecutorKind::MultiThreaded => {
+            let mut replica: SMRReplica<MultiThreadedMonExecutor> = async_runtime::block_on(MonReplica::bootstrap(mon_config)).unwrap();
+
+            loop {
+                if let Err(err) = replica.run(None) {
+                    error!("Error while executing replica {}", err);
+                }
+            }
         }
     }
 }
@@ -0,0 +1,74 @@
+//! The calculator replica, whose whole state is checkpointed and transferred at once
+
+use atlas_common::error::*;
+use atlas_common::ordering::SeqNo;
+use atlas_persistent_log::stateful_logs::monolithic_state::MonStatePersistentLog;
+use atlas_smr_replica::config::MonolithicStateReplicaConfig;
+use atlas_smr_replica::server::monolithic_server::MonReplica;
+use example_app::app::App;
+use example_app::app::messages::AppData;
+use example_app::state::CalculatorState;
+use febft_state_transfer::CollabStateTransfer;
+use febft_state_transfer::config::StateTransferConfig;
+use febft_state_transfer::message::serialize::CSTMsg;
+use crate::startup::ProtocolConfigs;
+use crate::types;
+
+/// If you want to use the default configurations,
+/// just change these types to whichever types you want to use
+pub type State = CalculatorState;
+pub type ApplicationData = AppData;
+pub type Application = App;
+
+pub type StateTransferMessage = CSTMsg<State>;
+
+pub type OrderProtocolMessage = types::OrderProtocolMessage<ApplicationData>;
+pub type DecLogMsg = types::DecLogMsg<ApplicationData>;
+pub type ReplicaNode = types::ReplicaNode<ApplicationData, StateTransferMessage>;
+pub type StateTransferNetwork = types::StateTransferNetwork<ApplicationData, StateTransferMessage>;
+
+/// Set up the persistent logging type with the existing data handles.
+/// Tools that read the log of a replica open it as this same type
+pub type Logging = MonStatePersistentLog<State, ApplicationData, OrderProtocolMessage, OrderProtocolMessage, DecLogMsg, StateTransferMessage>;
+
+pub type OrderProtocol = types::OrderProtocol<ApplicationData, StateTransferMessage>;
+pub type DecisionLog = types::DecisionLog<ApplicationData, StateTransferMessage, Logging>;
+pub type LogTransferProtocol = types::LogTransferProtocol<ApplicationData, StateTransferMessage, Logging>;
+pub type ViewTransferProt = types::ViewTransferProt<ApplicationData, StateTransferMessage>;
+pub type StateTransferProtocol = CollabStateTransfer<State, StateTransferNetwork, Logging>;
+
+/// The replica is generic over the executor, which is picked at startup (see [crate::settings::ExecutorKind])
+pub type SMRReplica<Executor> = MonReplica<types::ReconfProtocol, Executor, State, Application,
+    OrderProtocol, DecisionLog, StateTransferProtocol, LogTransferProtocol,
+    ViewTransferProt, ReplicaNode, Logging>;
+
+pub type ReplicaConf = types::ReplicaConf<State, ApplicationData, StateTransferMessage, StateTransferProtocol, Logging>;
+
+pub type MonConfig = MonolithicStateReplicaConfig::<types::ReconfProtocol, State, Application, OrderProtocol, DecisionLog,
+    StateTransferProtocol, LogTransferProtocol, ViewTransferProt, ReplicaNode, Logging>;
+
+pub fn init_replica_config(protocols: ProtocolConfigs) -> ReplicaConf {
+    ReplicaConf {
+        node: protocols.network,
+        next_consensus_seq: SeqNo::ZERO,
+        op_config: protocols.order_protocol,
+        dl_config: protocols.dec_log,
+        lt_config: protocols.log_transfer,
+        db_path: protocols.db_path,
+        pl_config: (),
+        reconfig_node: protocols.reconfiguration,
+        vt_config: protocols.view_transfer,
+        p: Default::default(),
+        preprocessor_threads: 1,
+    }
+}
+
+pub fn init_mon_replica_conf(replica_conf: ReplicaConf,
+                             state_transfer_config: StateTransferConfig,
+                             service: Application) -> Result<MonConfig> {
+    Ok(MonConfig {
+        service,
+        replica_config: replica_conf,
+        st_config: state_transfer_config,
+    })
+}
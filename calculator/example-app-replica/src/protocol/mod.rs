@@ -0,0 +1,24 @@
+//! The ordering protocol the replica runs, picked at compile time through cargo features.
+//!
+//! Each protocol lives in its own module, which exposes the same set of items:
+//! - `OrderProtocolMessage<RQ>`, the messages exchanged by the protocol
+//! - `OrderProtocol<RQ, NT>`, the protocol itself
+//! - `OrderProtocolConfig` and `parse_order_protocol_conf`, to read its configuration
+//! - `Tolerance`, the [atlas_core::ordering_protocol::OrderProtocolTolerance] that matches
+//!   the protocol's fault model (see [example_app::tolerance])
+//!
+//! Adding a new protocol means adding a module with those items, gated by a new feature.
+//!
+//! PBFT is the only protocol available so far.
+
+#[cfg(feature = "pbft")]
+mod pbft;
+
+#[cfg(feature = "pbft")]
+pub use pbft::*;
+
+// Clients size their quorums with the shared tolerance, so the protocol has to use the same one
+const _: std::marker::PhantomData<example_app::tolerance::Tolerance> = std::marker::PhantomData::<Tolerance>;
+
+#[cfg(not(feature = "pbft"))]
+compile_error!("No ordering protocol selected, enable one of the following features: pbft");
@@ -0,0 +1,61 @@
+use std::time::Duration;
+use config::{File, Source};
+use config::FileFormat::Toml;
+use serde::Deserialize;
+use atlas_common::error::*;
+use atlas_smr_core::request_pre_processing::RequestPreProcessor;
+use febft_pbft_consensus::bft::config::{PBFTConfig, ProposerConfig};
+use febft_pbft_consensus::bft::message::serialize::PBFTConsensus;
+use febft_pbft_consensus::bft::PBFTOrderProtocol;
+
+pub type OrderProtocolMessage<RQ> = PBFTConsensus<RQ>;
+pub type OrderProtocol<RQ, NT> = PBFTOrderProtocol<RQ, RequestPreProcessor<RQ>, NT>;
+pub type OrderProtocolConfig = PBFTConfig;
+
+/// PBFT tolerates `f` byzantine faults with `3f+1` replicas
+pub type Tolerance = example_app::tolerance::BFT;
+
+pub fn parse_order_protocol_conf() -> Result<OrderProtocolConfig> {
+    parse_febft_conf(File::new("config/febft.toml", Toml))
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct FeBFTConfig {
+    timeout_duration: u64,
+    proposer_config: FeBFTProposerConfig,
+    watermark: u32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct FeBFTProposerConfig {
+    target_batch_size: u64,
+    max_batch_size: u64,
+    batch_timeout: u64,
+    processing_threads: u32,
+}
+
+fn parse_febft_conf<T>(source: T) -> Result<PBFTConfig>
+    where T: Source + Send + Sync + 'static {
+    let mut settings = config::Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let febft_config: FeBFTConfig = settings.try_deserialize()?;
+
+    Ok(febft_config.into())
+}
+
+impl From<FeBFTConfig> for PBFTConfig {
+    fn from(value: FeBFTConfig) -> Self {
+        PBFTConfig {
+            timeout_dur: Duration::from_micros(value.timeout_duration),
+            proposer_config: ProposerConfig {
+                target_batch_size: value.proposer_config.target_batch_size,
+                max_batch_size: value.proposer_config.max_batch_size,
+                batch_timeout: value.proposer_config.batch_timeout,
+                processing_threads: value.proposer_config.processing_threads,
+            },
+            watermark: value.watermark,
+        }
+    }
+}
@@ -5,28 +5,70 @@ use atlas_common::error::*;
 use config::{Config, Source};
 use serde::Deserialize;
 use atlas_decision_log::config::DecLogConfig;
-use febft_pbft_consensus::bft::config::{PBFTConfig, ProposerConfig};
+use log::LevelFilter;
+use example_app::logging::{FileLoggingConfig, LogFormat, LoggingConfig};
+
+pub use example_app::settings::{parse_admin_conf, parse_codec_conf, parse_deployment_conf, parse_logging_conf, parse_nodes_conf, parse_tracing_conf, parse_watch_conf};
 
 #[derive(Parser, Debug)]
 #[command(author = "Nuno Neto", version, about = "An example application utilizing Atlas's SMR replica (with monolithic state)")]
 pub struct ReplicaArgs {
     #[arg(short, long, value_name = "DB_DIR", value_hint = clap::ValueHint::AnyPath, default_value = "./persistent_db")]
-    pub db_path: PathBuf
+    pub db_path: PathBuf,
+    /// Overrides the root log level set in the logging configuration
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<LevelFilter>,
+    /// Overrides the log format (text or json) set in the logging configuration
+    #[arg(long, value_name = "FORMAT")]
+    pub log_format: Option<LogFormat>,
+    /// Also write the logs to rotating files in the given folder
+    #[arg(long, value_name = "LOG_DIR", value_hint = clap::ValueHint::DirPath)]
+    pub log_dir: Option<PathBuf>,
+    /// Start from the state of this snapshot instead of an empty one, when the persistent
+    /// log is empty. Every replica of the cluster must start from the same snapshot.
+    /// Ordering starts again at sequence number 0, whatever the snapshot was taken at
+    #[arg(long, value_name = "SNAPSHOT", value_hint = clap::ValueHint::FilePath)]
+    pub snapshot: Option<PathBuf>,
+}
+
+impl ReplicaArgs {
+    /// Apply the logging related command line flags on top of the configuration read from file
+    pub fn apply_logging_overrides(&self, mut config: LoggingConfig) -> LoggingConfig {
+        if let Some(level) = self.log_level {
+            config.level = level;
+        }
+
+        if let Some(format) = self.log_format {
+            config.format = format;
+        }
+
+        if let Some(log_dir) = &self.log_dir {
+            let file_config = config.file.get_or_insert_with(FileLoggingConfig::default);
+
+            file_config.directory = log_dir.clone();
+        }
+
+        config
+    }
 }
 
-#[derive(Deserialize, Clone, Debug)]
-pub struct FeBFTConfig {
-    timeout_duration: u64,
-    proposer_config: FeBFTProposerConfig,
-    watermark: u32,
+/// The executors the replica can run the application with
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutorKind {
+    /// Ordered and unordered requests are executed one at a time, in the same thread
+    #[default]
+    SingleThreaded,
+    /// Unordered batches are executed in parallel with each other, in Atlas' thread pool
+    /// (sized by `threadpool_threads` in runtime_config.toml), while ordered requests
+    /// are still executed serially
+    MultiThreaded,
 }
 
 #[derive(Deserialize, Clone, Debug)]
-pub struct FeBFTProposerConfig {
-    target_batch_size: u64,
-    max_batch_size: u64,
-    batch_timeout: u64,
-    processing_threads: u32,
+pub struct ExecutorConfig {
+    #[serde(default)]
+    pub executor: ExecutorKind,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -49,17 +91,6 @@ pub struct StateTransferConfig {
     timeout_duration: u64,
 }
 
-pub fn parse_febft_conf<T>(source: T) -> Result<PBFTConfig>
-    where T: Source + Send + Sync + 'static {
-    let mut settings = config::Config::builder()
-        .add_source(source)
-        .build()?;
-
-    let febft_config: FeBFTConfig = settings.try_deserialize()?;
-
-    Ok(febft_config.into())
-}
-
 pub fn parse_dec_log_conf<T>(source: T) -> Result<DecLogConfig>
     where T: Source + Send + Sync + 'static {
     let mut settings = config::Config::builder()
@@ -93,6 +124,17 @@ pub fn parse_state_transfer_conf<T>(source: T) -> Result<febft_state_transfer::c
     Ok(st_config.into())
 }
 
+pub fn parse_div_state_transfer_conf<T>(source: T) -> Result<atlas_div_state_transfer::config::StateTransferConfig>
+    where T: Source + Send + Sync + 'static {
+    let mut settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let st_config: StateTransferConfig = settings.try_deserialize()?;
+
+    Ok(st_config.into())
+}
+
 pub fn parse_view_transfer_conf<T>(source: T) -> Result<atlas_view_transfer::config::ViewTransferConfig>
     where T: Source + Send + Sync + 'static {
     let mut settings = Config::builder()
@@ -104,6 +146,17 @@ pub fn parse_view_transfer_conf<T>(source: T) -> Result<atlas_view_transfer::con
     Ok(vt_config.into())
 }
 
+pub fn parse_executor_conf<T>(source: T) -> Result<ExecutorConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let executor_config: ExecutorConfig = settings.try_deserialize()?;
+
+    Ok(executor_config)
+}
+
 impl From<DecisionLogConfig> for DecLogConfig {
     fn from(value: DecisionLogConfig) -> Self {
         Self {
@@ -128,25 +181,18 @@ impl From<StateTransferConfig> for febft_state_transfer::config::StateTransferCo
     }
 }
 
-impl From<ViewTransferConfig> for atlas_view_transfer::config::ViewTransferConfig {
-    fn from(value: ViewTransferConfig) -> Self {
+impl From<StateTransferConfig> for atlas_div_state_transfer::config::StateTransferConfig {
+    fn from(value: StateTransferConfig) -> Self {
         Self {
             timeout_duration: Duration::from_micros(value.timeout_duration),
         }
     }
 }
 
-impl From<FeBFTConfig> for PBFTConfig {
-    fn from(value: FeBFTConfig) -> Self {
-        PBFTConfig {
-            timeout_dur: Duration::from_micros(value.timeout_duration),
-            proposer_config: ProposerConfig {
-                target_batch_size: value.proposer_config.target_batch_size,
-                max_batch_size: value.proposer_config.max_batch_size,
-                batch_timeout: value.proposer_config.batch_timeout,
-                processing_threads: value.proposer_config.processing_threads,
-            },
-            watermark: value.watermark,
+impl From<ViewTransferConfig> for atlas_view_transfer::config::ViewTransferConfig {
+    fn from(value: ViewTransferConfig) -> Self {
+        Self {
+            timeout_duration: Duration::from_micros(value.timeout_duration),
         }
     }
-}
\ No newline at end of file
+}
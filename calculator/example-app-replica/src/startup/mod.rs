@@ -0,0 +1,89 @@
+use anyhow::anyhow;
+use config::File;
+use config::FileFormat::Toml;
+use log::{error, info};
+use log4rs::Handle;
+use atlas_comm_mio::config::MIOConfig;
+use atlas_common::error::*;
+use atlas_common::node_id::NodeId;
+use atlas_decision_log::config::DecLogConfig;
+use atlas_default_configs::{get_network_configurations, get_reconfig_config};
+use atlas_default_configs::crypto::FolderPathConstructor;
+use atlas_log_transfer::config::LogTransferConfig;
+use atlas_reconfiguration::config::ReconfigurableNetworkConfig;
+use atlas_view_transfer::config::ViewTransferConfig;
+use example_app::logging::{init_logging, NodeRole};
+use example_app::tolerance::{NodesConfig, Quorum};
+use crate::protocol;
+use crate::settings;
+use crate::settings::ReplicaArgs;
+
+/// Everything a replica reads at startup, whatever its state.
+/// Only the state transfer configuration is left to each replica
+pub struct ReplicaSetup {
+    pub node_id: NodeId,
+    pub nodes: NodesConfig,
+    pub quorum: Quorum<protocol::Tolerance>,
+    pub protocols: ProtocolConfigs,
+    /// Must be kept alive for as long as the replica logs
+    pub log_handle: Handle,
+}
+
+/// What the replica config is built from, see [crate::monolithic::init_replica_config]
+pub struct ProtocolConfigs {
+    pub reconfiguration: ReconfigurableNetworkConfig,
+    pub network: MIOConfig,
+    pub order_protocol: protocol::OrderProtocolConfig,
+    pub dec_log: DecLogConfig,
+    pub log_transfer: LogTransferConfig,
+    pub view_transfer: ViewTransferConfig,
+    /// Where the persistent log is kept
+    pub db_path: String,
+}
+
+/// Start logging and read the configuration shared by every replica.
+///
+/// Exits the process when the deployment cannot tolerate the configured number of faults
+pub fn setup(args: &ReplicaArgs, description: &str) -> Result<ReplicaSetup> {
+    let reconfiguration = get_reconfig_config::<FolderPathConstructor>(None)?;
+
+    let node_id = reconfiguration.node_id;
+
+    let logging_cfg = settings::parse_logging_conf(File::new("config/logging.toml", Toml).required(false))?;
+
+    let log_handle = init_logging(node_id, NodeRole::Replica, &args.apply_logging_overrides(logging_cfg))?;
+
+    info!("Starting {} {:?}", description, node_id);
+
+    let nodes = settings::parse_nodes_conf(File::new("config/nodes.toml", Toml))?;
+
+    let deployment_cfg = settings::parse_deployment_conf(File::new("config/deployment.toml", Toml))?;
+
+    let quorum = match Quorum::<protocol::Tolerance>::check(nodes.replica_count(), &deployment_cfg) {
+        Ok(quorum) => quorum,
+        Err(err) => {
+            error!("Refusing to start replica: {}", err);
+
+            std::process::exit(1);
+        }
+    };
+
+    info!("Deployment of {} replicas, tolerating {} faults with quorums of {}", quorum.n(), quorum.f(), quorum.quorum());
+
+    let (network, _pool_config) = get_network_configurations(node_id)?;
+
+    let db_path = args.db_path.clone().into_os_string().into_string()
+        .map_err(|_| anyhow!("Failed to parse persistent log folder"))?;
+
+    let protocols = ProtocolConfigs {
+        reconfiguration,
+        network,
+        order_protocol: protocol::parse_order_protocol_conf()?,
+        dec_log: settings::parse_dec_log_conf(File::new("config/dec_log.toml", Toml))?,
+        log_transfer: settings::parse_log_transfer_conf(File::new("config/log_transfer.toml", Toml))?,
+        view_transfer: settings::parse_view_transfer_conf(File::new("config/view_transfer.toml", Toml))?,
+        db_path,
+    };
+
+    Ok(ReplicaSetup { node_id, nodes, quorum, protocols, log_handle })
+}
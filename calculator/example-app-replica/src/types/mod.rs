@@ -0,0 +1,76 @@
+//! The types both replicas are wired up with, generic over what differs between them:
+//! the application data `D`, the state transfer messages `STM` and the persistent log `PL`.
+//!
+//! See [crate::monolithic] and [crate::divisible] for the concrete replicas
+
+use atlas_comm_mio::{ByteStubType, MIOTCPNode};
+use atlas_communication::{NodeInputStub, NodeStubController};
+use atlas_decision_log::Log;
+use atlas_decision_log::serialize::LogSerialization;
+use atlas_log_transfer::CollabLogTransfer;
+use atlas_log_transfer::messages::serialize::LTMsg;
+use atlas_reconfiguration::message::ReconfData;
+use atlas_reconfiguration::network_reconfig::NetworkInfo;
+use atlas_reconfiguration::ReconfigurableNodeProtocolHandle;
+use atlas_smr_core::networking::{ReplicaNodeWrapper, SMRReplicaNetworkNode};
+use atlas_smr_core::serialize::{Service, SMRSysMsg, StateSys};
+use atlas_smr_core::SMRReq;
+use atlas_smr_replica::config::ReplicaConfig;
+use atlas_smr_replica::server::Exec;
+use atlas_view_transfer::message::serialize::ViewTransfer;
+use atlas_view_transfer::SimpleViewTransferProtocol;
+use crate::protocol;
+
+/// Set up the data handles so we initialize the networking layer
+pub type ReconfigurationMessage = ReconfData;
+
+/// In the case of SMR messages, we want the type that is going to be ordered to include just the actual
+/// SMR Ordered Request Type, so we can use the same type for the ordering protocol
+/// This type, for SMR is [atlas_smr_core::serialize::SMRReq]
+///
+/// These protocols are only going to be used for the ordered requests, so they only have to know about the ordered requests
+pub type OrderProtocolMessage<D> = protocol::OrderProtocolMessage<SMRReq<D>>;
+pub type DecLogMsg<D> = LogSerialization<SMRReq<D>, OrderProtocolMessage<D>, OrderProtocolMessage<D>>;
+pub type LogTransferMessage<D> = LTMsg<SMRReq<D>, OrderProtocolMessage<D>, OrderProtocolMessage<D>, DecLogMsg<D>>;
+pub type ViewTransferMessage<D> = ViewTransfer<OrderProtocolMessage<D>>;
+
+/// The state transfer also requires wrapping in order to keep the [atlas_communication::serialization::Serializable] type
+/// out of the state transfer protocol (and all others for that matter) for further flexibility
+/// Therefore, we have to wrap the [atlas_smr_core::serialize::StateSys] type in order to get the [atlas_communication::serialization::Serializable] trait
+pub type SerStateTransferMessage<STM> = StateSys<STM>;
+
+/// This type is the protocol type responsible for all SMR messages including unordered ones, so it already knows about [atlas_smr_application::ApplicationData]
+pub type ProtocolDataType<D> = Service<D, OrderProtocolMessage<D>, LogTransferMessage<D>, ViewTransferMessage<D>>;
+
+/// Set up the networking layer with the data handles we have
+///
+/// In the networking level, we utilize the type which wraps [atlas_smr_application::ApplicationData]
+/// and provides the [atlas_communication::serialization::Serializable] type required
+/// for the network layer.
+///
+/// For that, we use [atlas_smr_core::serialize::SMRSysMsg]
+pub type IncomingStub<D, STM> = NodeInputStub<ReconfigurationMessage, ProtocolDataType<D>, SerStateTransferMessage<STM>, SMRSysMsg<D>>;
+pub type StubController<D, STM> = NodeStubController<NetworkInfo, ByteStubType, ReconfigurationMessage, ProtocolDataType<D>, SerStateTransferMessage<STM>, SMRSysMsg<D>>;
+
+pub type ByteNetworkLayer<D, STM> = MIOTCPNode<NetworkInfo, IncomingStub<D, STM>, StubController<D, STM>>;
+
+pub type ReplicaNode<D, STM> = ReplicaNodeWrapper<ByteStubType, ByteNetworkLayer<D, STM>, NetworkInfo, ReconfigurationMessage, D, OrderProtocolMessage<D>,
+    LogTransferMessage<D>, ViewTransferMessage<D>, STM>;
+
+pub type ProtocolNetwork<D, STM> = <ReplicaNode<D, STM> as SMRReplicaNetworkNode<NetworkInfo, ReconfigurationMessage, D, OrderProtocolMessage<D>,
+    LogTransferMessage<D>, ViewTransferMessage<D>, STM>>::ProtocolNode;
+
+pub type StateTransferNetwork<D, STM> = <ReplicaNode<D, STM> as SMRReplicaNetworkNode<NetworkInfo, ReconfigurationMessage, D, OrderProtocolMessage<D>,
+    LogTransferMessage<D>, ViewTransferMessage<D>, STM>>::StateTransferNode;
+
+/// Set up the protocols with the types that have been built up to here
+pub type ReconfProtocol = ReconfigurableNodeProtocolHandle;
+/// The ordering protocol is selected through cargo features, see [crate::protocol]
+pub type OrderProtocol<D, STM> = protocol::OrderProtocol<SMRReq<D>, ProtocolNetwork<D, STM>>;
+pub type DecisionLog<D, STM, PL> = Log<SMRReq<D>, OrderProtocol<D, STM>, PL, Exec<D>>;
+pub type LogTransferProtocol<D, STM, PL> = CollabLogTransfer<SMRReq<D>, OrderProtocol<D, STM>, DecisionLog<D, STM, PL>, ProtocolNetwork<D, STM>, PL, Exec<D>>;
+pub type ViewTransferProt<D, STM> = SimpleViewTransferProtocol<OrderProtocol<D, STM>, ProtocolNetwork<D, STM>>;
+
+/// `S` is the state and `ST` its transfer protocol
+pub type ReplicaConf<S, D, STM, ST, PL> = ReplicaConfig::<ReconfProtocol, S, D, OrderProtocol<D, STM>, DecisionLog<D, STM, PL>,
+    ST, LogTransferProtocol<D, STM, PL>, ViewTransferProt<D, STM>, ReplicaNode<D, STM>, PL>;
@@ -0,0 +1,122 @@
+use std::io::{BufReader, ErrorKind};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+use anyhow::{anyhow, Context};
+use log::{debug, info, warn};
+use atlas_common::error::*;
+use example_app::app::subscriptions::{read_message, write_message, SubscriptionRegistry, WatchConfig, WatchMessage};
+
+/// How long a new connection has to send its subscription
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long writing a notification may block before the subscriber is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a subscriber that gets no notifications is checked for having gone away
+const LIVENESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Accept watch subscriptions on the given address, in the background.
+///
+/// Every connection gets its own thread, which forwards the notifications of its
+/// subscription until the subscriber goes away, up to `max_subscribers` of them at once.
+/// Subscribers are told the replica tolerates `f` faults, so they can check they wait
+/// for as many replicas as it expects
+pub fn start_watch_server(address: SocketAddr, config: &WatchConfig, f: usize, registry: Arc<SubscriptionRegistry>) -> Result<()> {
+    let listener = TcpListener::bind(address)
+        .with_context(|| format!("Failed to bind the watch server to {}", address))?;
+
+    info!("Accepting watch subscriptions on {}", address);
+
+    let max_subscribers = config.max_subscribers;
+
+    let connected = Arc::new(AtomicUsize::new(0));
+
+    thread::Builder::new()
+        .name("Watch server".to_string())
+        .spawn(move || {
+            for connection in listener.incoming() {
+                match connection {
+                    Ok(stream) => {
+                        if connected.fetch_add(1, Ordering::Relaxed) >= max_subscribers {
+                            connected.fetch_sub(1, Ordering::Relaxed);
+
+                            warn!("Turning away watch subscriber {:?}, {} are already connected", stream.peer_addr().ok(), max_subscribers);
+
+                            continue;
+                        }
+
+                        let (registry, subscriber_slot) = (registry.clone(), connected.clone());
+
+                        let spawned = thread::Builder::new()
+                            .name("Watch subscriber".to_string())
+                            .spawn(move || {
+                                let peer = stream.peer_addr().ok();
+
+                                if let Err(err) = serve_subscriber(stream, f, &registry) {
+                                    debug!("Watch subscriber {:?} disconnected: {:?}", peer, err);
+                                }
+
+                                subscriber_slot.fetch_sub(1, Ordering::Relaxed);
+                            });
+
+                        if let Err(err) = spawned {
+                            connected.fetch_sub(1, Ordering::Relaxed);
+
+                            warn!("Failed to spawn a thread for a watch subscriber: {:?}", err);
+                        }
+                    }
+                    Err(err) => warn!("Failed to accept a watch subscriber: {:?}", err),
+                }
+            }
+        })
+        .context("Failed to spawn the watch server thread")?;
+
+    Ok(())
+}
+
+/// The subscription is unregistered when this returns, whether the subscriber went away or failed
+fn serve_subscriber(stream: TcpStream, f: usize, registry: &SubscriptionRegistry) -> Result<()> {
+    stream.set_read_timeout(Some(SUBSCRIBE_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+    let filter = match read_message(BufReader::new(&stream))? {
+        WatchMessage::Subscribe(filter) => filter,
+        other => return Err(anyhow!("Expected a subscription, got {:?}", other)),
+    };
+
+    debug!("New watch subscription from {:?} with filter {:?}", stream.peer_addr(), filter);
+
+    let notifications = registry.subscribe(filter);
+
+    write_message(&stream, &WatchMessage::Subscribed { f })?;
+
+    loop {
+        match notifications.recv_timeout(LIVENESS_INTERVAL) {
+            Ok(notification) => write_message(&stream, &WatchMessage::Notification(notification))?,
+            Err(RecvTimeoutError::Timeout) => check_connected(&stream)?,
+            // The registry dropped the subscriber for falling behind
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+/// Subscribers send nothing once subscribed, so the connection having anything
+/// to read means it was closed, or the subscriber is not one of ours
+fn check_connected(stream: &TcpStream) -> Result<()> {
+    stream.set_nonblocking(true)?;
+
+    let peeked = stream.peek(&mut [0; 1]);
+
+    stream.set_nonblocking(false)?;
+
+    match peeked {
+        Ok(0) => Err(anyhow!("The subscriber closed the connection")),
+        Ok(_) => Err(anyhow!("The subscriber sent data after subscribing")),
+        Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
@@ -0,0 +1,24 @@
+use std::net::TcpStream;
+use std::time::Duration;
+use anyhow::Context;
+use atlas_common::error::*;
+use example_app::app::admin::{admin_address, AdminConfig};
+use example_app::tolerance::BootstrapNode;
+
+pub const ADMIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Connect to the admin server of a replica, giving up on replicas that take too long to answer
+pub fn connect(node: &BootstrapNode, config: &AdminConfig) -> Result<TcpStream> {
+    let address = admin_address(node, config)?;
+
+    let stream = TcpStream::connect_timeout(&address, ADMIN_TIMEOUT)
+        .with_context(|| match address.ip().is_loopback() {
+            true => format!("Failed to connect to the admin server at {}", address),
+            false => format!("Failed to connect to the admin server at {}, which only listens on localhost unless bind_ip is set in the replica's admin.toml", address),
+        })?;
+
+    stream.set_read_timeout(Some(ADMIN_TIMEOUT))?;
+    stream.set_write_timeout(Some(ADMIN_TIMEOUT))?;
+
+    Ok(stream)
+}
@@ -0,0 +1,70 @@
+use std::fmt::Display;
+use clap::Parser;
+use serde::Serialize;
+use example_app_tools::db::PersistentDb;
+use example_app_tools::inspect::{self, BatchSummary, CheckpointSummary};
+use example_app_tools::settings::{InspectArgs, InspectCommand};
+
+/// Print what the persistent log of a replica holds. The log is opened as the
+/// replica opens it, so the replica has to be stopped first
+fn main() {
+    let args = InspectArgs::parse();
+
+    let db = match PersistentDb::open(&args.db_path) {
+        Ok(db) => db,
+        Err(err) => {
+            eprintln!("{:#}", err);
+
+            std::process::exit(2);
+        }
+    };
+
+    match args.command {
+        InspectCommand::Summary => {
+            let checkpoint = db.checkpoint().unwrap();
+            let decisions = db.decisions().unwrap();
+
+            print(&inspect::summary(checkpoint.as_ref(), &decisions), args.json);
+        }
+        InspectCommand::Checkpoint => {
+            match db.checkpoint().unwrap() {
+                Some(checkpoint) => print(&CheckpointSummary::from(&checkpoint), args.json),
+                None => eprintln!("The persistent log holds no checkpoint"),
+            }
+        }
+        InspectCommand::Batches { from, to } => {
+            let batches = db.decisions().unwrap().into_iter()
+                .filter(|batch| (from..=to).contains(&u32::from(batch.seq_no)));
+
+            for batch in batches {
+                print(&BatchSummary::from(batch), args.json);
+            }
+        }
+        InspectCommand::Verify => {
+            let checkpoint = db.checkpoint().unwrap();
+            let decisions = db.decisions().unwrap();
+
+            let inconsistencies = inspect::verify(checkpoint.as_ref(), &decisions);
+
+            for inconsistency in &inconsistencies {
+                print(inconsistency, args.json);
+            }
+
+            if !inconsistencies.is_empty() {
+                std::process::exit(1);
+            }
+
+            if !args.json {
+                println!("The persistent log is consistent");
+            }
+        }
+    }
+}
+
+fn print<T>(item: &T, json: bool) where T: Serialize + Display {
+    if json {
+        println!("{}", serde_json::to_string(item).unwrap());
+    } else {
+        println!("{}", item);
+    }
+}
@@ -0,0 +1,51 @@
+use atlas_common::node_id::NodeId;
+use clap::Parser;
+use config::File;
+use config::FileFormat::Toml;
+use example_app::logging::{init_logging, NodeRole};
+use example_app::tolerance::{Quorum, Tolerance};
+use example_app_tools::divergence::Detector;
+use example_app_tools::settings::{self, DivergenceArgs};
+use log::{error, info};
+
+/// Compare the checkpoint digests of every replica each time they record a checkpoint,
+/// writing the outcome of each round to stdout as a line of json
+fn main() {
+    let args = DivergenceArgs::parse();
+
+    let logging_cfg = settings::parse_logging_conf(File::new("config/logging.toml", Toml).required(false)).unwrap();
+
+    // The detector is not a member of the deployment, so it has no node id of its own
+    let _log_handle = init_logging(NodeId(0), NodeRole::Tool, &logging_cfg).unwrap();
+
+    let nodes_cfg = settings::parse_nodes_conf(File::new("config/nodes.toml", Toml)).unwrap();
+
+    let deployment_cfg = settings::parse_deployment_conf(File::new("config/deployment.toml", Toml)).unwrap();
+
+    let quorum = match Quorum::<Tolerance>::check(nodes_cfg.replica_count(), &deployment_cfg) {
+        Ok(quorum) => quorum,
+        Err(err) => {
+            error!("Refusing to start the divergence detector: {}", err);
+
+            std::process::exit(1);
+        }
+    };
+
+    let admin_cfg = settings::parse_admin_conf(File::new("config/admin.toml", Toml).required(false)).unwrap();
+
+    let mut detector = Detector::new(&nodes_cfg, admin_cfg.clone(), quorum.f());
+
+    info!("Comparing the digests of {} replicas every {} sequence numbers", quorum.n(), admin_cfg.checkpoints.period);
+
+    loop {
+        let report = detector.round();
+
+        println!("{}", serde_json::to_string(&report).unwrap());
+
+        if args.once {
+            std::process::exit(if report.divergence.is_some() { 1 } else { 0 });
+        }
+
+        detector.wait_for_checkpoint();
+    }
+}
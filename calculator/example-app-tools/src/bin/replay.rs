@@ -0,0 +1,53 @@
+use clap::Parser;
+use atlas_common::error::*;
+use example_app::snapshot::Snapshot;
+use example_app_tools::db::PersistentDb;
+use example_app_tools::replay;
+use example_app_tools::replay::ReplayReport;
+use example_app_tools::settings::ReplayArgs;
+
+/// Replay the log of a replica with the `App` of this build. Running the replay
+/// of a new build against the log of a production replica shows whether it
+/// still reaches the same states, before rolling it out
+fn main() {
+    let args = ReplayArgs::parse();
+
+    let report = match run(&args) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("{:#}", err);
+
+            std::process::exit(2);
+        }
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string(&report).unwrap());
+    } else {
+        println!("Replayed from checkpoint {} to {}, verified the checkpoints {:?}", report.from, report.replayed_to, report.verified);
+
+        if let Some(seq_no) = report.out_of_order {
+            println!("Stopped as the batch {} was stored out of order", seq_no);
+        }
+
+        if let Some((from, to)) = report.missing {
+            println!("Stopped as no batch was stored from {} to {}", from, to);
+        }
+
+        if let Some(mismatch) = &report.mismatch {
+            println!("The state diverges at checkpoint {}, the last checkpoint that matched was {}", mismatch.seq_no, mismatch.last_match);
+            println!("  stored:   value {}, digest {}", mismatch.stored_value, mismatch.stored_digest);
+            println!("  replayed: value {}, digest {}", mismatch.replayed_value, mismatch.replayed_digest);
+        }
+    }
+
+    if report.mismatch.is_some() || report.out_of_order.is_some() {
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &ReplayArgs) -> Result<ReplayReport> {
+    let snapshot = args.snapshot.as_deref().map(Snapshot::load).transpose()?;
+
+    replay::replay_db(&PersistentDb::open(&args.db_path)?, snapshot)
+}
@@ -0,0 +1,57 @@
+use anyhow::anyhow;
+use clap::Parser;
+use config::File;
+use config::FileFormat::Toml;
+use atlas_common::error::*;
+use example_app::snapshot::Snapshot;
+use example_app::state::digest_hex;
+use example_app_tools::db::PersistentDb;
+use example_app_tools::settings::{self, SnapshotArgs, SnapshotCommand};
+use example_app_tools::snapshot;
+
+/// Export the state of a replica, from its persistent log or from the replica
+/// while it runs. Replicas of a fresh cluster start from it with --snapshot
+fn main() {
+    let args = SnapshotArgs::parse();
+
+    if let Err(err) = run(args.command) {
+        eprintln!("{:#}", err);
+
+        std::process::exit(1);
+    }
+}
+
+fn run(command: SnapshotCommand) -> Result<()> {
+    match command {
+        SnapshotCommand::FromDb { db_path, output } => {
+            let snapshot = snapshot::from_db(&PersistentDb::open(&db_path)?)?;
+
+            snapshot.save(&output)?;
+
+            describe(&snapshot);
+        }
+        SnapshotCommand::FromReplica { node_id, output } => {
+            let nodes_cfg = settings::parse_nodes_conf(File::new("config/nodes.toml", Toml))?;
+
+            let admin_cfg = settings::parse_admin_conf(File::new("config/admin.toml", Toml).required(false))?;
+
+            let node = nodes_cfg.replicas()
+                .find(|node| node.node_id == node_id)
+                .ok_or_else(|| anyhow!("Replica {} is not listed in nodes.toml", node_id))?;
+
+            let snapshot = snapshot::from_replica(node, &admin_cfg)?;
+
+            snapshot.save(&output)?;
+
+            describe(&snapshot);
+        }
+        SnapshotCommand::Show { snapshot } => describe(&Snapshot::load(&snapshot)?),
+    }
+
+    Ok(())
+}
+
+fn describe(snapshot: &Snapshot) {
+    println!("Snapshot of sequence number {:?}: value {}, {} sessions, digest {}",
+             snapshot.seq_no, snapshot.state.value(), snapshot.state.session_count(), digest_hex(&snapshot.state.digest()));
+}
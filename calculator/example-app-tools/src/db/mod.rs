@@ -0,0 +1,88 @@
+use std::path::Path;
+use anyhow::{anyhow, Context};
+use atlas_common::error::*;
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use atlas_core::persistent_log::{MonolithicStateLog, OperationMode, PersistentDecisionLog};
+use example_app::app::messages::Request;
+use example_app::state::CalculatorState;
+use example_app_replica::monolithic::{DecisionLog, Logging, OrderProtocol, State, StateTransferProtocol};
+
+/// The persistent log of a replica, opened as the same [Logging] type the replica
+/// writes it with. Every entry is read through the log's own read API, so the tools
+/// follow whatever layout and encoding the log uses
+pub struct PersistentDb {
+    log: Logging,
+}
+
+#[derive(Clone, Debug)]
+pub struct StoredCheckpoint {
+    pub seq_no: SeqNo,
+    pub state: CalculatorState,
+}
+
+pub struct DecidedBatch {
+    pub seq_no: SeqNo,
+    pub requests: Vec<DecidedRequest>,
+}
+
+pub struct DecidedRequest {
+    pub from: NodeId,
+    pub session: SeqNo,
+    pub operation_id: SeqNo,
+    pub request: Request,
+}
+
+impl PersistentDb {
+    pub fn open(path: &Path) -> Result<Self> {
+        if !path.is_dir() {
+            return Err(anyhow!("No persistent log at {}", path.display()));
+        }
+
+        let db_path = path.to_str()
+            .ok_or_else(|| anyhow!("Failed to parse persistent log folder {}", path.display()))?;
+
+        let log = Logging::init_log::<String, OrderProtocol, StateTransferProtocol, DecisionLog>(db_path.to_string())
+            .with_context(|| format!("Failed to open the persistent log at {}", path.display()))?;
+
+        Ok(Self { log })
+    }
+
+    /// The latest checkpoint of the replica. The log only keeps the latest one,
+    /// along with the batches decided after it
+    pub fn checkpoint(&self) -> Result<Option<StoredCheckpoint>> {
+        let checkpoint = MonolithicStateLog::<State>::read_checkpoint(&self.log)
+            .context("Failed to read the checkpoint")?;
+
+        Ok(checkpoint.map(|checkpoint| StoredCheckpoint {
+            seq_no: checkpoint.sequence_number(),
+            state: checkpoint.state().clone(),
+        }))
+    }
+
+    /// The decided batches, in the order of their sequence numbers
+    pub fn decisions(&self) -> Result<Vec<DecidedBatch>> {
+        let decision_log = PersistentDecisionLog::read_decision_log(&self.log, OperationMode::BlockingSync)
+            .context("Failed to read the decision log")?;
+
+        let Some(decision_log) = decision_log else {
+            return Ok(Vec::new());
+        };
+
+        let batches = decision_log.proofs().iter()
+            .map(|proof| DecidedBatch {
+                seq_no: proof.sequence_number(),
+                requests: OrderProtocol::get_requests(proof).into_iter()
+                    .map(|stored| DecidedRequest {
+                        from: stored.header().from(),
+                        session: stored.message().session_number(),
+                        operation_id: stored.message().sequence_number(),
+                        request: stored.message().operation().clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(batches)
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::io::BufReader;
+use std::thread;
+use anyhow::anyhow;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use atlas_common::error::*;
+use example_app::app::admin::{send_command, AdminCommand, AdminConfig, AdminReply, CheckpointEntry};
+use example_app::tolerance::{BootstrapNode, NodesConfig};
+use crate::admin;
+
+/// What a replica told us about its execution
+#[derive(Serialize, Clone, Debug)]
+pub struct ReplicaReport {
+    pub node_id: u32,
+    /// The last sequence number the replica executed
+    pub executed: u32,
+    pub checkpoints: Vec<CheckpointEntry>,
+}
+
+/// The first checkpoint at which the replicas disagree on the digest of the state
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub seq_no: u32,
+    /// The digest of the largest group of replicas, if at least `f + 1` of them agree on it
+    pub majority_digest: Option<String>,
+    pub majority: Vec<u32>,
+    /// The replicas that do not have the majority's digest
+    pub minority: Vec<u32>,
+}
+
+/// The outcome of a round, written as a line of json
+#[derive(Serialize, Debug)]
+pub struct RoundReport {
+    pub round: u64,
+    /// The last sequence number executed by each replica that answered
+    pub executed: BTreeMap<u32, u32>,
+    /// Replicas more than a checkpoint period behind the most advanced one
+    pub lagging: Vec<u32>,
+    pub unreachable: Vec<u32>,
+    pub divergence: Option<Divergence>,
+}
+
+/// Polls the admin server of every replica, comparing the digests of their checkpoints
+pub struct Detector {
+    replicas: Vec<BootstrapNode>,
+    config: AdminConfig,
+    f: usize,
+    rounds: u64,
+    /// The latest checkpoint any replica reported in the last round
+    latest_checkpoint: u32,
+    last_divergence: Option<Divergence>,
+}
+
+impl Detector {
+    pub fn new(nodes: &NodesConfig, config: AdminConfig, f: usize) -> Self {
+        Self {
+            replicas: nodes.replicas().cloned().collect(),
+            config,
+            f,
+            rounds: 0,
+            latest_checkpoint: 0,
+            last_divergence: None,
+        }
+    }
+
+    /// Poll every replica once.
+    ///
+    /// A divergence is logged as an error the first time it is seen, so a detector
+    /// left running does not repeat the same alert every round
+    pub fn round(&mut self) -> RoundReport {
+        self.rounds += 1;
+
+        let mut reports = Vec::with_capacity(self.replicas.len());
+        let mut unreachable = Vec::new();
+
+        for replica in &self.replicas {
+            match probe(replica, &self.config, self.f) {
+                Ok(report) => reports.push(report),
+                Err(err) => {
+                    warn!("Replica {} did not answer: {:?}", replica.node_id, err);
+
+                    unreachable.push(replica.node_id);
+                }
+            }
+        }
+
+        let divergence = find_divergence(&reports, self.f);
+
+        match &divergence {
+            Some(divergence) if self.last_divergence.as_ref() != Some(divergence) => {
+                error!("Replicas {:?} diverge from {:?} at sequence number {} (majority digest {:?})",
+                    divergence.minority, divergence.majority, divergence.seq_no, divergence.majority_digest);
+            }
+            Some(_) => {}
+            None if self.last_divergence.is_some() => info!("The replicas no longer diverge"),
+            None => {}
+        }
+
+        self.last_divergence.clone_from(&divergence);
+
+        // Not kept across rounds, as replicas restarted from a snapshot start again from 0
+        self.latest_checkpoint = reports.iter()
+            .flat_map(|report| report.checkpoints.iter().map(|checkpoint| checkpoint.seq_no))
+            .max()
+            .unwrap_or(0);
+
+        RoundReport {
+            round: self.rounds,
+            lagging: lagging(&reports, self.config.checkpoints.period),
+            executed: reports.iter().map(|report| (report.node_id, report.executed)).collect(),
+            unreachable,
+            divergence,
+        }
+    }
+
+    /// Block until a replica records a checkpoint past the latest one of the last round,
+    /// so that rounds run once every checkpoint period of ordered operations
+    pub fn wait_for_checkpoint(&self) {
+        loop {
+            let mut answered = false;
+
+            for replica in &self.replicas {
+                match next_checkpoint(replica, &self.config, self.latest_checkpoint) {
+                    Ok(Some(seq_no)) => {
+                        debug!("Replica {} recorded the checkpoint at {}", replica.node_id, seq_no);
+
+                        return;
+                    }
+                    Ok(None) => answered = true,
+                    Err(err) => debug!("Replica {} did not answer: {:?}", replica.node_id, err),
+                }
+            }
+
+            // Every replica refused straight away, so there is nothing to wait on
+            if !answered {
+                thread::sleep(admin::ADMIN_TIMEOUT);
+            }
+        }
+    }
+}
+
+/// Wait for the replica to record a checkpoint past `after`, returning its sequence number,
+/// or [None] when it does not within the read timeout of its admin server
+pub fn next_checkpoint(node: &BootstrapNode, config: &AdminConfig, after: u32) -> Result<Option<u32>> {
+    let stream = admin::connect(node, config)?;
+
+    // The replica only replies once it records the checkpoint, or its read timeout expires
+    stream.set_read_timeout(Some(config.read_timeout() + admin::ADMIN_TIMEOUT))?;
+
+    let mut reader = BufReader::new(&stream);
+
+    match send_command(&mut reader, &stream, AdminCommand::CheckpointAfter(after))? {
+        AdminReply::Checkpoints(checkpoints) => Ok(checkpoints.last().map(|checkpoint| checkpoint.seq_no)),
+        AdminReply::Error(err) => Err(anyhow!("Replica {} could not wait for a checkpoint: {}", node.node_id, err)),
+        other => Err(anyhow!("Unexpected reply to checkpoint_after: {:?}", other)),
+    }
+}
+
+/// Ask the admin server of the replica for its last executed sequence number and its checkpoints.
+///
+/// Fails when the replica does not tolerate the `f` faults the majority is counted with
+pub fn probe(node: &BootstrapNode, config: &AdminConfig, f: usize) -> Result<ReplicaReport> {
+    let stream = admin::connect(node, config)?;
+
+    let mut reader = BufReader::new(&stream);
+
+    let executed = match send_command(&mut reader, &stream, AdminCommand::Status)? {
+        AdminReply::Status { executed, f: replica_f } if replica_f == f => executed,
+        AdminReply::Status { f: replica_f, .. } => return Err(anyhow!("Replica {} tolerates f = {}, but the detector expects f = {}", node.node_id, replica_f, f)),
+        other => return Err(anyhow!("Unexpected reply to status: {:?}", other)),
+    };
+
+    let checkpoints = match send_command(&mut reader, &stream, AdminCommand::Checkpoints)? {
+        AdminReply::Checkpoints(checkpoints) => checkpoints,
+        other => return Err(anyhow!("Unexpected reply to checkpoints: {:?}", other)),
+    };
+
+    Ok(ReplicaReport {
+        node_id: node.node_id,
+        executed,
+        checkpoints,
+    })
+}
+
+/// Find the first sequence number at which the replicas recorded different digests.
+///
+/// Only the checkpoints a replica still holds can be compared, so a replica that was
+/// behind when the others recorded a checkpoint is compared on the later ones
+pub fn find_divergence(reports: &[ReplicaReport], f: usize) -> Option<Divergence> {
+    let mut by_seq_no: BTreeMap<u32, BTreeMap<&str, Vec<u32>>> = BTreeMap::new();
+
+    for report in reports {
+        for checkpoint in &report.checkpoints {
+            by_seq_no.entry(checkpoint.seq_no).or_default()
+                .entry(checkpoint.digest.as_str()).or_default()
+                .push(report.node_id);
+        }
+    }
+
+    let (seq_no, groups) = by_seq_no.into_iter().find(|(_, groups)| groups.len() > 1)?;
+
+    // Ties go to the first digest, so every round names the same majority
+    let (digest, majority) = groups.iter()
+        .rev()
+        .max_by_key(|(_, nodes)| nodes.len())?;
+
+    let mut minority: Vec<u32> = groups.iter()
+        .filter(|(other, _)| *other != digest)
+        .flat_map(|(_, nodes)| nodes.iter().copied())
+        .collect();
+
+    minority.sort_unstable();
+
+    let mut majority = majority.clone();
+
+    majority.sort_unstable();
+
+    Some(Divergence {
+        seq_no,
+        majority_digest: (majority.len() > f).then(|| digest.to_string()),
+        majority,
+        minority,
+    })
+}
+
+fn lagging(reports: &[ReplicaReport], period: u32) -> Vec<u32> {
+    let Some(latest) = reports.iter().map(|report| report.executed).max() else {
+        return Vec::new();
+    };
+
+    reports.iter()
+        .filter(|report| report.executed.saturating_add(period) < latest)
+        .map(|report| report.node_id)
+        .collect()
+}
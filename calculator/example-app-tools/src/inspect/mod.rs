@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use serde::Serialize;
+use example_app::app::messages::Request;
+use example_app::state::digest_hex;
+use crate::db::{DecidedBatch, StoredCheckpoint};
+
+/// What the log covers
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct LogSummary {
+    /// The sequence number of the latest checkpoint
+    pub checkpoint: Option<u32>,
+    pub batches: usize,
+    pub first_batch: Option<u32>,
+    pub last_batch: Option<u32>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CheckpointSummary {
+    pub seq_no: u32,
+    /// The sequence number of the last batch the state had applied
+    pub executed: u32,
+    pub value: i32,
+    pub sessions: usize,
+    pub digest: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BatchSummary {
+    pub seq_no: u32,
+    pub requests: Vec<RequestSummary>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RequestSummary {
+    pub from: u32,
+    pub session: u32,
+    pub operation_id: u32,
+    pub request: Request,
+}
+
+/// Something in the log that should not be there, or that is missing from it
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Inconsistency {
+    /// The state stored for a checkpoint was not the state after that sequence number
+    CheckpointSeqNo { seq_no: u32, executed: u32 },
+    /// No batch was stored for these sequence numbers, though later ones were
+    MissingDecisions { from: u32, to: u32 },
+    /// A batch stored after one with the same or a later sequence number
+    OutOfOrder { seq_no: u32, after: u32 },
+    /// The same request of a client was decided twice
+    DuplicateRequest { client: u32, request_id: u64, first: u32, second: u32 },
+}
+
+impl From<&StoredCheckpoint> for CheckpointSummary {
+    fn from(checkpoint: &StoredCheckpoint) -> Self {
+        Self {
+            seq_no: checkpoint.seq_no.into(),
+            executed: checkpoint.state.executed().into(),
+            value: checkpoint.state.value(),
+            sessions: checkpoint.state.session_count(),
+            digest: digest_hex(&checkpoint.state.digest()),
+        }
+    }
+}
+
+impl From<DecidedBatch> for BatchSummary {
+    fn from(batch: DecidedBatch) -> Self {
+        Self {
+            seq_no: batch.seq_no.into(),
+            requests: batch.requests.into_iter()
+                .map(|request| RequestSummary {
+                    from: request.from.0,
+                    session: request.session.into(),
+                    operation_id: request.operation_id.into(),
+                    request: request.request,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Display for LogSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.checkpoint {
+            Some(seq_no) => writeln!(f, "checkpoint: {}", seq_no)?,
+            None => writeln!(f, "checkpoint: none")?,
+        }
+
+        match (self.first_batch, self.last_batch) {
+            (Some(first), Some(last)) => write!(f, "batches: {}, from {} to {}", self.batches, first, last),
+            _ => write!(f, "batches: none"),
+        }
+    }
+}
+
+impl Display for CheckpointSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "checkpoint {}: value {}, executed {}, {} sessions, digest {}",
+               self.seq_no, self.value, self.executed, self.sessions, self.digest)
+    }
+}
+
+impl Display for BatchSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "batch {}: {} requests", self.seq_no, self.requests.len())?;
+
+        for request in &self.requests {
+            write!(f, "\n  client {} session {} operation {}: {:?} {}",
+                   request.from, request.session, request.operation_id,
+                   request.request.operation(), request.request.value())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Inconsistency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Inconsistency::CheckpointSeqNo { seq_no, executed } => write!(f, "The checkpoint at {} holds the state after {}", seq_no, executed),
+            Inconsistency::MissingDecisions { from, to } => write!(f, "No batch was stored from {} to {}", from, to),
+            Inconsistency::OutOfOrder { seq_no, after } => write!(f, "The batch {} was stored after the batch {}", seq_no, after),
+            Inconsistency::DuplicateRequest { client, request_id, first, second } =>
+                write!(f, "Request {} of client {} was decided at both {} and {}", request_id, client, first, second),
+        }
+    }
+}
+
+pub fn summary(checkpoint: Option<&StoredCheckpoint>, batches: &[DecidedBatch]) -> LogSummary {
+    LogSummary {
+        checkpoint: checkpoint.map(|checkpoint| checkpoint.seq_no.into()),
+        batches: batches.len(),
+        first_batch: batches.first().map(|batch| batch.seq_no.into()),
+        last_batch: batches.last().map(|batch| batch.seq_no.into()),
+    }
+}
+
+/// Check that the checkpoint holds the state of its sequence number, and that
+/// the batches after it follow one another, with none missing or decided twice
+pub fn verify(checkpoint: Option<&StoredCheckpoint>, batches: &[DecidedBatch]) -> Vec<Inconsistency> {
+    let mut inconsistencies = Vec::new();
+
+    if let Some(checkpoint) = checkpoint {
+        if checkpoint.state.executed() != checkpoint.seq_no {
+            inconsistencies.push(Inconsistency::CheckpointSeqNo {
+                seq_no: checkpoint.seq_no.into(),
+                executed: checkpoint.state.executed().into(),
+            });
+        }
+    }
+
+    // The batches up to the checkpoint are applied to its state, so only
+    // the batches after it have to follow one another
+    let covered = checkpoint.map(|checkpoint| u32::from(checkpoint.seq_no));
+
+    let mut previous: Option<u32> = None;
+
+    let mut decided = BTreeMap::new();
+
+    for batch in batches {
+        let seq_no = u32::from(batch.seq_no);
+
+        if covered.is_none_or(|covered| seq_no > covered) {
+            let expected = previous.or(covered).map(|previous| previous.saturating_add(1));
+
+            match expected {
+                Some(expected) if seq_no > expected => {
+                    inconsistencies.push(Inconsistency::MissingDecisions { from: expected, to: seq_no - 1 });
+                }
+                Some(expected) if seq_no < expected => {
+                    inconsistencies.push(Inconsistency::OutOfOrder { seq_no, after: expected - 1 });
+                }
+                _ => {}
+            }
+
+            previous = Some(previous.map_or(seq_no, |previous| previous.max(seq_no)));
+        }
+
+        for request in &batch.requests {
+            let Some(request_id) = request.request.id() else {
+                continue;
+            };
+
+            if let Some(first) = decided.insert((request.from.0, request_id), seq_no) {
+                inconsistencies.push(Inconsistency::DuplicateRequest {
+                    client: request.from.0,
+                    request_id,
+                    first,
+                    second: seq_no,
+                });
+            }
+        }
+    }
+
+    inconsistencies
+}
@@ -0,0 +1,7 @@
+pub mod admin;
+pub mod db;
+pub mod divergence;
+pub mod inspect;
+pub mod replay;
+pub mod settings;
+pub mod snapshot;
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use anyhow::anyhow;
+use serde::Serialize;
+use atlas_common::error::*;
+use atlas_smr_application::app::{Application, UpdateBatch};
+use example_app::app::App;
+use example_app::snapshot::Snapshot;
+use example_app::state::{digest_hex, CalculatorState};
+use crate::db::{DecidedBatch, PersistentDb, StoredCheckpoint};
+
+/// The outcome of replaying the decided batches on top of a checkpoint
+#[derive(Serialize, Debug, Default)]
+pub struct ReplayReport {
+    /// The sequence number of the checkpoint the replay started from
+    pub from: u32,
+    /// The last sequence number that was replayed
+    pub replayed_to: u32,
+    /// The later checkpoints whose state matched the replayed one
+    pub verified: Vec<u32>,
+    pub mismatch: Option<Mismatch>,
+    /// The batches the replay stopped at, because they were not stored
+    pub missing: Option<(u32, u32)>,
+    /// The batch the replay stopped at, because it came after a batch with the same
+    /// or a later sequence number: it was stored twice, or out of order
+    pub out_of_order: Option<u32>,
+}
+
+/// The first checkpoint whose stored state differs from the replayed one
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub seq_no: u32,
+    /// The last checkpoint that still matched, so the batch that went wrong is between the two
+    pub last_match: u32,
+    pub stored_value: i32,
+    pub replayed_value: i32,
+    pub stored_digest: String,
+    pub replayed_digest: String,
+}
+
+/// Replay the log of a replica, starting from the snapshot the replica started from
+/// or, without one, from the checkpoint stored in the log. When the replay starts
+/// from a snapshot, the stored checkpoint is one of those it checks.
+///
+/// A replay needs one of the two: the state a replica starts from may not be the default one
+pub fn replay_db(db: &PersistentDb, snapshot: Option<Snapshot>) -> Result<ReplayReport> {
+    let checkpoint = db.checkpoint()?;
+
+    let (start, later) = match (snapshot, checkpoint) {
+        (Some(snapshot), checkpoint) => {
+            let start = StoredCheckpoint { seq_no: snapshot.seq_no, state: snapshot.state };
+
+            let later = checkpoint.filter(|checkpoint| checkpoint.seq_no > start.seq_no);
+
+            (start, later)
+        }
+        (None, Some(checkpoint)) => (checkpoint, None),
+        (None, None) => return Err(anyhow!("The persistent log holds no checkpoint to start from, pass the snapshot the replica started from")),
+    };
+
+    let from = start.seq_no;
+
+    let decisions = db.decisions()?.into_iter()
+        .filter(|batch| batch.seq_no > from);
+
+    Ok(replay(start, later, decisions))
+}
+
+/// Apply each batch to the state of the starting checkpoint with [App::update_batch],
+/// comparing the digest of the state with that of every later checkpoint.
+///
+/// The replay stops at the first mismatch, at the first batch that is missing, or at
+/// the first batch that does not follow the previous one
+pub fn replay<C, B>(start: StoredCheckpoint, later: C, batches: B) -> ReplayReport
+    where C: IntoIterator<Item=StoredCheckpoint>, B: IntoIterator<Item=DecidedBatch> {
+    let app = App::init();
+
+    let later: BTreeMap<u32, CalculatorState> = later.into_iter()
+        .map(|checkpoint| (checkpoint.seq_no.into(), checkpoint.state))
+        .collect();
+
+    let from = u32::from(start.seq_no);
+
+    let mut report = ReplayReport {
+        from,
+        replayed_to: from,
+        ..Default::default()
+    };
+
+    let mut state = start.state;
+    let mut last_match = from;
+
+    for batch in batches {
+        let seq_no = u32::from(batch.seq_no);
+        let expected = report.replayed_to.saturating_add(1);
+
+        if seq_no < expected {
+            report.out_of_order = Some(seq_no);
+
+            break;
+        }
+
+        if seq_no > expected {
+            report.missing = Some((expected, seq_no - 1));
+
+            break;
+        }
+
+        let mut update = UpdateBatch::new_with_cap(batch.seq_no, batch.requests.len());
+
+        for request in batch.requests {
+            update.add(request.from, request.session, request.operation_id, request.request);
+        }
+
+        app.update_batch(&mut state, update);
+
+        report.replayed_to = seq_no;
+
+        let Some(stored) = later.get(&seq_no) else {
+            continue;
+        };
+
+        let (stored_digest, replayed_digest) = (stored.digest(), state.digest());
+
+        if stored_digest != replayed_digest {
+            report.mismatch = Some(Mismatch {
+                seq_no,
+                last_match,
+                stored_value: stored.value(),
+                replayed_value: state.value(),
+                stored_digest: digest_hex(&stored_digest),
+                replayed_digest: digest_hex(&replayed_digest),
+            });
+
+            break;
+        }
+
+        report.verified.push(seq_no);
+
+        last_match = seq_no;
+    }
+
+    report
+}
@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+
+pub use example_app::settings::{parse_admin_conf, parse_deployment_conf, parse_logging_conf, parse_nodes_conf};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Compare the state digests of every replica at each checkpoint, and alert when they diverge")]
+pub struct DivergenceArgs {
+    /// Run a single round, exiting with 1 if the replicas diverged
+    #[arg(long)]
+    pub once: bool,
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Look inside the persistent log of a stopped replica, without modifying it")]
+pub struct InspectArgs {
+    /// The persistent log folder of the replica
+    #[arg(value_name = "DB_DIR", value_hint = clap::ValueHint::DirPath, default_value = "./persistent_db")]
+    pub db_path: PathBuf,
+    /// Write one json object per line, instead of text
+    #[arg(long)]
+    pub json: bool,
+    #[command(subcommand)]
+    pub command: InspectCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum InspectCommand {
+    /// Print the sequence numbers of the checkpoint and of the batches the log holds
+    Summary,
+    /// Print the latest checkpoint and its state
+    Checkpoint,
+    /// Print the requests of each decided batch
+    Batches {
+        /// The first sequence number to print
+        #[arg(long, default_value_t = 0)]
+        from: u32,
+        /// The last sequence number to print
+        #[arg(long, default_value_t = u32::MAX)]
+        to: u32,
+    },
+    /// Check that the log is consistent, exiting with 1 if it is not
+    Verify,
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Replay the decided batches of a replica's persistent log, checking the result against its later checkpoints")]
+pub struct ReplayArgs {
+    /// The persistent log folder of the replica
+    #[arg(value_name = "DB_DIR", value_hint = clap::ValueHint::DirPath, default_value = "./persistent_db")]
+    pub db_path: PathBuf,
+    /// The snapshot the replica started from, to replay from it instead of the checkpoint
+    /// stored in the log. Needed when the log holds no checkpoint yet
+    #[arg(long, value_name = "SNAPSHOT", value_hint = clap::ValueHint::FilePath)]
+    pub snapshot: Option<PathBuf>,
+    /// Write the report as json, instead of text
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Export the state of a replica to a checksummed snapshot file, which another cluster can start from")]
+pub struct SnapshotArgs {
+    #[command(subcommand)]
+    pub command: SnapshotCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SnapshotCommand {
+    /// Export the latest checkpoint stored in the persistent log of a stopped replica
+    FromDb {
+        /// The persistent log folder of the replica
+        #[arg(long, value_name = "DB_DIR", value_hint = clap::ValueHint::DirPath, default_value = "./persistent_db")]
+        db_path: PathBuf,
+        #[arg(value_name = "SNAPSHOT", value_hint = clap::ValueHint::FilePath)]
+        output: PathBuf,
+    },
+    /// Export the state at the next checkpoint of a running replica, through its admin server,
+    /// which must have serve_snapshots set in its admin.toml
+    FromReplica {
+        /// The replica to ask, as listed in nodes.toml
+        #[arg(long)]
+        node_id: u32,
+        #[arg(value_name = "SNAPSHOT", value_hint = clap::ValueHint::FilePath)]
+        output: PathBuf,
+    },
+    /// Check the checksum of a snapshot and print what it holds
+    Show {
+        #[arg(value_name = "SNAPSHOT", value_hint = clap::ValueHint::FilePath)]
+        snapshot: PathBuf,
+    },
+}
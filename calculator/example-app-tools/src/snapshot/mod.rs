@@ -0,0 +1,32 @@
+use std::io::BufReader;
+use anyhow::anyhow;
+use atlas_common::error::*;
+use example_app::app::admin::{send_command, AdminCommand, AdminConfig, AdminReply};
+use example_app::snapshot::Snapshot;
+use example_app::tolerance::BootstrapNode;
+use crate::admin;
+use crate::db::PersistentDb;
+
+/// The checkpoint stored in the persistent log, which only keeps the latest one
+pub fn from_db(db: &PersistentDb) -> Result<Snapshot> {
+    let checkpoint = db.checkpoint()?
+        .ok_or_else(|| anyhow!("The persistent log holds no checkpoint"))?;
+
+    Ok(Snapshot::new(checkpoint.seq_no, checkpoint.state))
+}
+
+/// The state at the next checkpoint of a running replica, asked through its admin server
+pub fn from_replica(node: &BootstrapNode, config: &AdminConfig) -> Result<Snapshot> {
+    let stream = admin::connect(node, config)?;
+
+    // The replica only replies once it reaches its next checkpoint
+    stream.set_read_timeout(Some(config.read_timeout() + admin::ADMIN_TIMEOUT))?;
+
+    let mut reader = BufReader::new(&stream);
+
+    match send_command(&mut reader, &stream, AdminCommand::Snapshot)? {
+        AdminReply::Snapshot => Ok(Snapshot::read(reader)?),
+        AdminReply::Error(err) => Err(anyhow!("Replica {} could not export a snapshot: {}", node.node_id, err)),
+        other => Err(anyhow!("Unexpected reply to snapshot: {:?}", other)),
+    }
+}
@@ -0,0 +1,74 @@
+use example_app::app::admin::{AdminCommand, CheckpointEntry};
+use example_app_tools::divergence::{find_divergence, ReplicaReport};
+
+fn report(node_id: u32, checkpoints: &[(u32, &str)]) -> ReplicaReport {
+    ReplicaReport {
+        node_id,
+        executed: checkpoints.last().map(|(seq_no, _)| *seq_no).unwrap_or(0),
+        checkpoints: checkpoints.iter()
+            .map(|(seq_no, digest)| CheckpointEntry { seq_no: *seq_no, digest: digest.to_string() })
+            .collect(),
+    }
+}
+
+#[test]
+fn agreeing_replicas_do_not_diverge() {
+    let reports = [
+        report(0, &[(1000, "aa"), (2000, "bb")]),
+        report(1, &[(1000, "aa"), (2000, "bb")]),
+        report(2, &[(1000, "aa")]),
+        report(3, &[]),
+    ];
+
+    assert_eq!(find_divergence(&reports, 1), None);
+}
+
+#[test]
+fn names_the_minority_at_the_first_divergent_checkpoint() {
+    let reports = [
+        report(0, &[(1000, "aa"), (2000, "bb"), (3000, "cc")]),
+        report(1, &[(1000, "aa"), (2000, "bb"), (3000, "cc")]),
+        report(2, &[(1000, "aa"), (2000, "ee"), (3000, "ff")]),
+        report(3, &[(1000, "aa"), (2000, "bb"), (3000, "dd")]),
+    ];
+
+    let divergence = find_divergence(&reports, 1).unwrap();
+
+    assert_eq!(divergence.seq_no, 2000);
+    assert_eq!(divergence.majority, vec![0, 1, 3]);
+    assert_eq!(divergence.minority, vec![2]);
+    assert_eq!(divergence.majority_digest.as_deref(), Some("bb"));
+}
+
+#[test]
+fn no_majority_digest_without_f_plus_one_replicas() {
+    let reports = [
+        report(0, &[(1000, "aa")]),
+        report(1, &[(1000, "bb")]),
+        report(2, &[(1000, "cc")]),
+    ];
+
+    let divergence = find_divergence(&reports, 1).unwrap();
+
+    assert_eq!(divergence.majority_digest, None);
+    assert_eq!(divergence.majority, vec![0]);
+    assert_eq!(divergence.minority, vec![1, 2]);
+}
+
+#[test]
+fn admin_commands_roundtrip() {
+    let commands = [
+        AdminCommand::Status,
+        AdminCommand::Checkpoints,
+        AdminCommand::Rejected,
+        AdminCommand::Snapshot,
+        AdminCommand::CheckpointAfter(2000),
+    ];
+
+    for command in commands {
+        assert_eq!(command.to_string().parse::<AdminCommand>().unwrap(), command);
+    }
+
+    assert!("checkpoint_after".parse::<AdminCommand>().is_err());
+    assert!("checkpoint_after soon".parse::<AdminCommand>().is_err());
+}
@@ -0,0 +1,84 @@
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use example_app::app::messages::{Operation, Request};
+use example_app::state::CalculatorState;
+use example_app_tools::db::{DecidedBatch, DecidedRequest, StoredCheckpoint};
+use example_app_tools::inspect::{summary, verify, Inconsistency};
+
+fn batch(seq_no: u32, request_id: u64) -> DecidedBatch {
+    DecidedBatch {
+        seq_no: SeqNo::from(seq_no),
+        requests: vec![DecidedRequest {
+            from: NodeId(1000),
+            session: SeqNo::ZERO,
+            operation_id: SeqNo::from(seq_no),
+            request: Request::new(Operation::Add, 1).with_id(request_id),
+        }],
+    }
+}
+
+fn checkpoint(seq_no: u32, executed: u32) -> StoredCheckpoint {
+    let mut state = CalculatorState::default();
+
+    state.set_executed(SeqNo::from(executed));
+
+    StoredCheckpoint { seq_no: SeqNo::from(seq_no), state }
+}
+
+#[test]
+fn a_consistent_log_passes() {
+    let batches = [batch(4, 1), batch(5, 2), batch(6, 3)];
+
+    assert_eq!(verify(Some(&checkpoint(3, 3)), &batches), vec![]);
+    assert_eq!(verify(None, &batches), vec![]);
+}
+
+#[test]
+fn batches_up_to_the_checkpoint_are_not_checked_for_gaps() {
+    let batches = [batch(1, 1), batch(4, 2), batch(5, 3)];
+
+    assert_eq!(verify(Some(&checkpoint(3, 3)), &batches), vec![]);
+}
+
+#[test]
+fn a_checkpoint_of_another_seq_no_is_reported() {
+    assert_eq!(verify(Some(&checkpoint(3, 2)), &[]), vec![Inconsistency::CheckpointSeqNo { seq_no: 3, executed: 2 }]);
+}
+
+#[test]
+fn missing_batches_are_reported() {
+    let batches = [batch(4, 1), batch(7, 2)];
+
+    assert_eq!(verify(Some(&checkpoint(3, 3)), &batches), vec![Inconsistency::MissingDecisions { from: 5, to: 6 }]);
+
+    // The first batch after the checkpoint is missing too
+    assert_eq!(verify(Some(&checkpoint(1, 1)), &batches), vec![
+        Inconsistency::MissingDecisions { from: 2, to: 3 },
+        Inconsistency::MissingDecisions { from: 5, to: 6 },
+    ]);
+}
+
+#[test]
+fn batches_out_of_order_are_reported() {
+    let batches = [batch(0, 1), batch(1, 2), batch(0, 3), batch(2, 4)];
+
+    assert_eq!(verify(None, &batches), vec![Inconsistency::OutOfOrder { seq_no: 0, after: 1 }]);
+}
+
+#[test]
+fn requests_decided_twice_are_reported() {
+    let batches = [batch(4, 1), batch(5, 1)];
+
+    assert_eq!(verify(Some(&checkpoint(3, 3)), &batches), vec![
+        Inconsistency::DuplicateRequest { client: 1000, request_id: 1, first: 4, second: 5 },
+    ]);
+}
+
+#[test]
+fn summary_covers_the_checkpoint_and_batches() {
+    let log = summary(Some(&checkpoint(3, 3)), &[batch(4, 1), batch(5, 2)]);
+
+    assert_eq!(log.checkpoint, Some(3));
+    assert_eq!(log.batches, 2);
+    assert_eq!((log.first_batch, log.last_batch), (Some(4), Some(5)));
+}
@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use atlas_common::globals::ReadOnly;
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use atlas_core::persistent_log::{MonolithicStateLog, OperationMode};
+use atlas_core::state_transfer::Checkpoint;
+use example_app::app::messages::Reply;
+use example_app::snapshot::Snapshot;
+use example_app::state::CalculatorState;
+use example_app_replica::monolithic::{DecisionLog, Logging, OrderProtocol, StateTransferProtocol};
+use example_app_tools::db::PersistentDb;
+use example_app_tools::inspect;
+use example_app_tools::replay::replay_db;
+
+/// A fresh folder for the log of a test
+fn db_path(test: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("example-app-tools-{}-{}", test, std::process::id()));
+
+    let _ = std::fs::remove_dir_all(&path);
+
+    path
+}
+
+fn state(executed: u32) -> CalculatorState {
+    let mut state = CalculatorState::default();
+
+    state.set_value(42);
+    state.set_executed(SeqNo::from(executed));
+    state.record_reply(NodeId(1000), 7, Reply::new(Ok(42), SeqNo::from(executed)));
+
+    state
+}
+
+/// Write a checkpoint with the same log the replica writes with
+fn write_checkpoint(path: &Path, state: CalculatorState) {
+    let log = Logging::init_log::<String, OrderProtocol, StateTransferProtocol, DecisionLog>(path.to_str().unwrap().to_string()).unwrap();
+
+    let checkpoint = Checkpoint::new(state.executed(), state.clone(), state.digest());
+
+    log.write_checkpoint(OperationMode::BlockingSync, Arc::new(ReadOnly::new(checkpoint))).unwrap();
+}
+
+#[test]
+fn inspects_a_checkpoint_written_by_the_log() {
+    let path = db_path("inspect");
+
+    write_checkpoint(&path, state(9));
+
+    let db = PersistentDb::open(&path).unwrap();
+
+    let checkpoint = db.checkpoint().unwrap().unwrap();
+
+    assert_eq!(checkpoint.seq_no, SeqNo::from(9));
+    assert_eq!(checkpoint.state, state(9));
+
+    let decisions = db.decisions().unwrap();
+
+    assert_eq!(inspect::summary(Some(&checkpoint), &decisions).checkpoint, Some(9));
+    assert_eq!(inspect::verify(Some(&checkpoint), &decisions), vec![]);
+}
+
+#[test]
+fn replays_from_a_checkpoint_written_by_the_log() {
+    let path = db_path("replay");
+
+    write_checkpoint(&path, state(9));
+
+    let report = replay_db(&PersistentDb::open(&path).unwrap(), None).unwrap();
+
+    assert_eq!(report.from, 9);
+    assert_eq!(report.replayed_to, 9);
+    assert_eq!(report.mismatch, None);
+}
+
+#[test]
+fn replays_from_a_snapshot_up_to_the_checkpoint_of_the_log() {
+    let path = db_path("replay-snapshot");
+
+    write_checkpoint(&path, state(9));
+
+    let report = replay_db(&PersistentDb::open(&path).unwrap(), Some(Snapshot::new(SeqNo::from(4), state(4)))).unwrap();
+
+    // The batches between the snapshot and the checkpoint were pruned from the log
+    assert_eq!(report.from, 4);
+    assert_eq!(report.replayed_to, 4);
+    assert_eq!(report.verified, Vec::<u32>::new());
+}
+
+#[test]
+fn replay_needs_a_checkpoint_or_a_snapshot() {
+    let path = db_path("replay-empty");
+
+    std::fs::create_dir_all(&path).unwrap();
+
+    assert!(replay_db(&PersistentDb::open(&path).unwrap(), None).is_err());
+}
+
+#[test]
+fn a_missing_log_is_not_created() {
+    let path = db_path("missing");
+
+    assert!(PersistentDb::open(&path).is_err());
+    assert!(!path.exists());
+}
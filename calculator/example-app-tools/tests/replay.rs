@@ -0,0 +1,78 @@
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use example_app::app::messages::{Operation, Request};
+use example_app::state::CalculatorState;
+use example_app_tools::db::{DecidedBatch, DecidedRequest, StoredCheckpoint};
+use example_app_tools::replay::replay;
+
+fn batch(seq_no: u32, operation: Operation, value: i32) -> DecidedBatch {
+    DecidedBatch {
+        seq_no: SeqNo::from(seq_no),
+        requests: vec![DecidedRequest {
+            from: NodeId(1000),
+            session: SeqNo::ZERO,
+            operation_id: SeqNo::from(seq_no),
+            // Without an id the request leaves no reply in the state, so it only holds the value
+            request: Request::new(operation, value),
+        }],
+    }
+}
+
+fn checkpoint(seq_no: u32, value: i32) -> StoredCheckpoint {
+    let mut state = CalculatorState::default();
+
+    state.set_value(value);
+    state.set_executed(SeqNo::from(seq_no));
+
+    StoredCheckpoint { seq_no: SeqNo::from(seq_no), state }
+}
+
+fn batches() -> Vec<DecidedBatch> {
+    vec![batch(1, Operation::Add, 5), batch(2, Operation::Mult, 3), batch(3, Operation::Sub, 1)]
+}
+
+#[test]
+fn replay_reaches_the_stored_checkpoint() {
+    let report = replay(checkpoint(0, 0), [checkpoint(2, 15), checkpoint(3, 14)], batches());
+
+    assert_eq!(report.replayed_to, 3);
+    assert_eq!(report.verified, vec![2, 3]);
+    assert_eq!(report.mismatch, None);
+}
+
+#[test]
+fn replay_names_the_first_mismatching_checkpoint() {
+    let report = replay(checkpoint(0, 0), [checkpoint(2, 15), checkpoint(3, 13)], batches());
+
+    let mismatch = report.mismatch.unwrap();
+
+    assert_eq!(mismatch.seq_no, 3);
+    assert_eq!(mismatch.last_match, 2);
+    assert_eq!(mismatch.stored_value, 13);
+    assert_eq!(mismatch.replayed_value, 14);
+}
+
+#[test]
+fn replay_stops_at_a_missing_batch() {
+    let report = replay(checkpoint(0, 0), [], [batch(1, Operation::Add, 5), batch(3, Operation::Add, 1)]);
+
+    assert_eq!(report.replayed_to, 1);
+    assert_eq!(report.missing, Some((2, 2)));
+}
+
+#[test]
+fn replay_stops_at_a_batch_stored_twice() {
+    let report = replay(checkpoint(0, 0), [], [batch(1, Operation::Add, 5), batch(1, Operation::Add, 5), batch(2, Operation::Add, 1)]);
+
+    assert_eq!(report.replayed_to, 1);
+    assert_eq!(report.out_of_order, Some(1));
+    assert_eq!(report.missing, None);
+}
+
+#[test]
+fn replay_stops_at_a_batch_before_the_checkpoint() {
+    let report = replay(checkpoint(0, 0), [], [batch(0, Operation::Add, 5)]);
+
+    assert_eq!(report.replayed_to, 0);
+    assert_eq!(report.out_of_order, Some(0));
+}
@@ -0,0 +1,95 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use atlas_common::ordering::SeqNo;
+use atlas_smr_application::serialize::ApplicationData;
+use example_app::app::messages::{AppData, Operation, Reply, Request};
+use example_app::app::wire;
+use example_app::app::wire::compact;
+use example_app::app::wire::Codec;
+
+fn request() -> Request {
+    Request::new(Operation::Add, 42).with_id(1_700_000_000_000)
+}
+
+fn reply() -> Reply {
+    Reply::new(Ok(1337), SeqNo::from(4096))
+}
+
+fn encoded<T: wire::WireMessage>(codec: Codec, message: &T) -> Vec<u8> {
+    let mut buf = Vec::new();
+
+    wire::encode_with(codec, &mut buf, message).unwrap();
+
+    buf
+}
+
+fn decode_request(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_request");
+
+    let request = request();
+
+    let legacy = bincode::serde::encode_to_vec(&request, bincode::config::standard()).unwrap();
+
+    group.bench_function("bincode_legacy_reader", |b| {
+        b.iter(|| wire::decode::<Request, _>(black_box(legacy.as_slice())).unwrap())
+    });
+
+    for codec in Codec::ALL {
+        let buf = encoded(codec, &request);
+
+        group.bench_function(format!("{:?}_reader", codec).to_lowercase(), |b| {
+            b.iter(|| wire::decode::<Request, _>(black_box(buf.as_slice())).unwrap())
+        });
+
+        group.bench_function(format!("{:?}_slice", codec).to_lowercase(), |b| {
+            b.iter(|| wire::decode_from_slice::<Request>(black_box(&buf)).unwrap())
+        });
+
+        // What the replica runs for every request it receives
+        group.bench_function(format!("{:?}_app_data", codec).to_lowercase(), |b| {
+            b.iter(|| AppData::deserialize_request(black_box(buf.as_slice())).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn encode_request(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_request");
+
+    let request = request();
+
+    for codec in Codec::ALL {
+        group.bench_function(format!("{:?}", codec).to_lowercase(), |b| {
+            b.iter_batched_ref(|| Vec::with_capacity(64), |buf| {
+                wire::encode_with(codec, buf, black_box(&request)).unwrap()
+            }, BatchSize::SmallInput)
+        });
+    }
+
+    group.bench_function("compact_stack_buffer", |b| {
+        let mut buf = [0u8; compact::REQUEST_SIZE];
+
+        b.iter(|| compact::encode_request(black_box(&request), &mut buf).unwrap())
+    });
+
+    group.finish();
+}
+
+fn decode_reply(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_reply");
+
+    let reply = reply();
+
+    for codec in [Codec::Bincode, Codec::Compact] {
+        let buf = encoded(codec, &reply);
+
+        group.bench_function(format!("{:?}_slice", codec).to_lowercase(), |b| {
+            b.iter(|| wire::decode_from_slice::<Reply>(black_box(&buf)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, decode_request, encode_request, decode_reply);
+criterion_main!(benches);
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use atlas_smr_application::serialize::ApplicationData;
+use example_app::app::messages::{AppData, Request};
+use example_app::app::wire;
+use example_app_fuzz::check_decoders;
+
+fuzz_target!(|data: &[u8]| {
+    check_decoders(data, AppData::deserialize_request, wire::decode_from_slice::<Request>);
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use atlas_smr_application::state::monolithic_state::MonolithicState;
+use example_app::app::wire;
+use example_app::state::CalculatorState;
+use example_app_fuzz::check_decoders;
+
+fuzz_target!(|data: &[u8]| {
+    check_decoders(data, CalculatorState::deserialize_state, wire::decode_from_slice::<CalculatorState>);
+});
@@ -0,0 +1,57 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The most serde preallocates from a length prefix, see `example_app::app::wire::decode_bincode`
+const MAX_PREALLOCATION: usize = 1024 * 1024;
+
+/// Remembers the largest allocation since the last [check_decoders].
+///
+/// libFuzzer's `-malloc_limit_mb` only catches allocations past its limit (the RSS limit
+/// by default, 2 GiB), which is far more than a decoder should ever allocate for the
+/// small inputs it generates, so the targets bound the allocations by the input instead
+struct LargestAllocation;
+
+static LARGEST: AtomicUsize = AtomicUsize::new(0);
+
+#[global_allocator]
+static ALLOCATOR: LargestAllocation = LargestAllocation;
+
+unsafe impl GlobalAlloc for LargestAllocation {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LARGEST.fetch_max(layout.size(), Ordering::Relaxed);
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LARGEST.fetch_max(new_size, Ordering::Relaxed);
+
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Decode the input through the reader and the slice paths, which must agree on whether it
+/// is valid and on what it holds. Neither may allocate more at once than the input justifies,
+/// whatever length prefixes it claims
+pub fn check_decoders<'a, T, R, S>(data: &'a [u8], from_reader: impl FnOnce(&'a [u8]) -> Result<T, R>, from_slice: impl FnOnce(&'a [u8]) -> Result<T, S>)
+    where T: PartialEq + Debug, R: Debug, S: Debug {
+    LARGEST.store(0, Ordering::Relaxed);
+
+    let from_reader = from_reader(data);
+    let from_slice = from_slice(data);
+
+    let largest = LARGEST.load(Ordering::Relaxed);
+
+    // Buffers read to the end may have grown to twice the input
+    assert!(largest <= MAX_PREALLOCATION + 2 * data.len(), "Decoding {} bytes allocated {} bytes at once", data.len(), largest);
+
+    match (&from_reader, &from_slice) {
+        (Ok(from_reader), Ok(from_slice)) => assert_eq!(from_reader, from_slice),
+        _ => assert_eq!(from_reader.is_ok(), from_slice.is_ok(), "{:?} != {:?}", from_reader, from_slice),
+    }
+}
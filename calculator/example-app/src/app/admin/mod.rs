@@ -0,0 +1,172 @@
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use crate::app::checkpoints::CheckpointDigestConfig;
+use crate::app::wire::limits::RejectedPayloads;
+use crate::tolerance::BootstrapNode;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AdminConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Only localhost by default, as the admin server is not authenticated
+    #[serde(default = "default_bind_ip")]
+    pub bind_ip: IpAddr,
+    /// The admin server listens on the replica's port plus this offset
+    #[serde(default = "default_port_offset")]
+    pub port_offset: u16,
+    /// In milliseconds, how long an admin connection may stay idle before it is closed,
+    /// which is also as long as snapshot and checkpoint requests wait for the next checkpoint
+    #[serde(default = "default_read_timeout")]
+    pub read_timeout: u64,
+    /// Whether to answer [AdminCommand::Snapshot], which hands out the whole state,
+    /// client sessions included, to anyone who can reach the admin server
+    #[serde(default)]
+    pub serve_snapshots: bool,
+    #[serde(default)]
+    pub checkpoints: CheckpointDigestConfig,
+}
+
+/// The commands the admin server answers, sent one per line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdminCommand {
+    /// The last sequence number the replica executed, and how many faults it tolerates
+    Status,
+    /// The sequence number and state digest of the latest checkpoints
+    Checkpoints,
+    /// How many payloads were rejected for exceeding their size limit
+    Rejected,
+    /// The snapshot file of the state at the next checkpoint, only answered
+    /// when [AdminConfig::serve_snapshots] is set
+    Snapshot,
+    /// The latest checkpoint past the given sequence number, waiting for the replica to record
+    /// one for up to [AdminConfig::read_timeout]. Answered with no checkpoints when it does not
+    CheckpointAfter(u32),
+}
+
+/// The answer to an admin command, written as a single line of json
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminReply {
+    Status { executed: u32, f: usize },
+    Checkpoints(Vec<CheckpointEntry>),
+    RejectedPayloads(RejectedPayloads),
+    /// Followed by the snapshot file, as written by [crate::snapshot::Snapshot::write]
+    Snapshot,
+    Error(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CheckpointEntry {
+    pub seq_no: u32,
+    /// In hexadecimal
+    pub digest: String,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_ip: default_bind_ip(),
+            port_offset: default_port_offset(),
+            read_timeout: default_read_timeout(),
+            serve_snapshots: false,
+            checkpoints: CheckpointDigestConfig::default(),
+        }
+    }
+}
+
+impl AdminConfig {
+    pub fn read_timeout(&self) -> Duration {
+        Duration::from_millis(self.read_timeout)
+    }
+}
+
+impl Display for AdminCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdminCommand::Status => write!(f, "status"),
+            AdminCommand::Checkpoints => write!(f, "checkpoints"),
+            AdminCommand::Rejected => write!(f, "rejected"),
+            AdminCommand::Snapshot => write!(f, "snapshot"),
+            AdminCommand::CheckpointAfter(seq_no) => write!(f, "checkpoint_after {}", seq_no),
+        }
+    }
+}
+
+impl FromStr for AdminCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["status"] => Ok(AdminCommand::Status),
+            ["checkpoints"] => Ok(AdminCommand::Checkpoints),
+            ["rejected"] => Ok(AdminCommand::Rejected),
+            ["snapshot"] => Ok(AdminCommand::Snapshot),
+            ["checkpoint_after", seq_no] => seq_no.parse()
+                .map(AdminCommand::CheckpointAfter)
+                .with_context(|| format!("Invalid sequence number {:?}", seq_no)),
+            _ => Err(anyhow!("Unknown command {:?}, expected status, checkpoints, rejected, snapshot or checkpoint_after <seq_no>", s)),
+        }
+    }
+}
+
+fn admin_port(node: &BootstrapNode, config: &AdminConfig) -> atlas_common::error::Result<u16> {
+    node.port.checked_add(config.port_offset)
+        .ok_or_else(|| anyhow!("Admin port offset {} overflows the port of replica {}", config.port_offset, node.node_id))
+}
+
+/// Where the admin server of the replica listens
+pub fn admin_bind_address(node: &BootstrapNode, config: &AdminConfig) -> atlas_common::error::Result<SocketAddr> {
+    Ok(SocketAddr::new(config.bind_ip, admin_port(node, config)?))
+}
+
+/// Whether the admin server only listens on localhost, while the replica is listed on another
+/// address in nodes.toml, which tools running on other hosts would try to reach it at
+pub fn only_reachable_locally(node: &BootstrapNode, config: &AdminConfig) -> bool {
+    let listed_locally = match node.ip.parse::<IpAddr>() {
+        Ok(ip) => ip.is_loopback(),
+        Err(_) => node.ip == "localhost",
+    };
+
+    config.bind_ip.is_loopback() && !listed_locally
+}
+
+/// Where to reach the admin server of the replica from elsewhere
+pub fn admin_address(node: &BootstrapNode, config: &AdminConfig) -> atlas_common::error::Result<SocketAddr> {
+    let port = admin_port(node, config)?;
+
+    format!("{}:{}", node.ip, port).parse()
+        .with_context(|| format!("Invalid address for replica {}", node.node_id))
+}
+
+/// Send a command over an admin connection, and wait for its reply
+pub fn send_command<R, W>(mut reader: R, mut writer: W, command: AdminCommand) -> atlas_common::error::Result<AdminReply>
+    where R: BufRead, W: Write {
+    writeln!(writer, "{}", command)?;
+    writer.flush()?;
+
+    let mut line = String::new();
+
+    if reader.read_line(&mut line)? == 0 {
+        return Err(anyhow!("The admin server closed the connection"));
+    }
+
+    serde_json::from_str(&line).context("Invalid reply from the admin server")
+}
+
+fn default_bind_ip() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::LOCALHOST)
+}
+
+fn default_port_offset() -> u16 {
+    2000
+}
+
+fn default_read_timeout() -> u64 {
+    30000
+}
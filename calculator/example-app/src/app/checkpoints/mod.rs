@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+use serde::Deserialize;
+use atlas_common::crypto::hash::Digest;
+use atlas_common::ordering::SeqNo;
+use crate::snapshot::Snapshot;
+use crate::state::CalculatorState;
+
+/// How often the digest of the state is recorded, and how many of them are kept
+#[derive(Deserialize, Clone, Debug)]
+pub struct CheckpointDigestConfig {
+    /// Should match the checkpoint period of the ordering protocol, so the
+    /// digests line up with the checkpoints
+    #[serde(default = "default_period")]
+    pub period: u32,
+    #[serde(default = "default_history")]
+    pub history: usize,
+}
+
+/// The digest of the state right after executing a sequence number
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheckpointDigest {
+    pub seq_no: SeqNo,
+    pub digest: Digest,
+}
+
+/// The digests of the state at the latest checkpoints, oldest first
+pub struct CheckpointDigests {
+    period: u32,
+    history: usize,
+    /// The last sequence number executed, checkpoint or not
+    executed: AtomicU32,
+    digests: Mutex<VecDeque<CheckpointDigest>>,
+    checkpoint_recorded: Condvar,
+    /// Whether someone is waiting for the state at the next checkpoint
+    snapshot_requested: AtomicBool,
+    /// The state at the last checkpoint a snapshot was requested for. It is only
+    /// copied on request, as the state can hold every client session
+    snapshot: Mutex<Option<Snapshot>>,
+    snapshot_taken: Condvar,
+}
+
+impl Default for CheckpointDigestConfig {
+    fn default() -> Self {
+        Self {
+            period: default_period(),
+            history: default_history(),
+        }
+    }
+}
+
+impl CheckpointDigests {
+    pub fn new(config: &CheckpointDigestConfig) -> Self {
+        Self {
+            period: config.period.max(1),
+            history: config.history.max(1),
+            executed: AtomicU32::new(0),
+            digests: Mutex::new(VecDeque::with_capacity(config.history)),
+            checkpoint_recorded: Condvar::new(),
+            snapshot_requested: AtomicBool::new(false),
+            snapshot: Mutex::new(None),
+            snapshot_taken: Condvar::new(),
+        }
+    }
+
+    /// Record the digest of the state if the sequence number it just executed is a checkpoint
+    pub(crate) fn record_if_due(&self, seq_no: SeqNo, state: &CalculatorState) {
+        self.executed.store(u32::from(seq_no), Ordering::Relaxed);
+
+        if u32::from(seq_no) % self.period != 0 {
+            return;
+        }
+
+        let digest = state.digest();
+
+        {
+            let mut digests = self.digests.lock().unwrap();
+
+            if digests.len() == self.history {
+                digests.pop_front();
+            }
+
+            digests.push_back(CheckpointDigest { seq_no, digest });
+        }
+
+        self.checkpoint_recorded.notify_all();
+
+        if self.snapshot_requested.swap(false, Ordering::AcqRel) {
+            *self.snapshot.lock().unwrap() = Some(Snapshot::new(seq_no, state.clone()));
+
+            self.snapshot_taken.notify_all();
+        }
+    }
+
+    pub fn executed(&self) -> SeqNo {
+        SeqNo::from(self.executed.load(Ordering::Relaxed))
+    }
+
+    pub fn recent(&self) -> Vec<CheckpointDigest> {
+        self.digests.lock().unwrap().iter().copied().collect()
+    }
+
+    /// The latest checkpoint past `after`, waiting at most `timeout` for the replica
+    /// to record one. Returns [None] when it does not get there in time
+    pub fn checkpoint_after(&self, after: SeqNo, timeout: Duration) -> Option<CheckpointDigest> {
+        let digests = self.digests.lock().unwrap();
+
+        let (digests, _) = self.checkpoint_recorded
+            .wait_timeout_while(digests, timeout, |digests| digests.back().is_none_or(|checkpoint| checkpoint.seq_no <= after))
+            .unwrap();
+
+        digests.back()
+            .filter(|checkpoint| checkpoint.seq_no > after)
+            .copied()
+    }
+
+    /// The state at the next checkpoint, waiting at most `timeout` for the replica
+    /// to reach it. Returns [None] when it does not get there in time
+    pub fn next_snapshot(&self, timeout: Duration) -> Option<Snapshot> {
+        let since = self.executed();
+
+        let snapshot = self.snapshot.lock().unwrap();
+
+        self.snapshot_requested.store(true, Ordering::Release);
+
+        let (snapshot, _) = self.snapshot_taken
+            .wait_timeout_while(snapshot, timeout, |snapshot| snapshot.as_ref().is_none_or(|snapshot| snapshot.seq_no <= since))
+            .unwrap();
+
+        snapshot.as_ref()
+            .filter(|snapshot| snapshot.seq_no > since)
+            .cloned()
+    }
+}
+
+fn default_period() -> u32 {
+    1000
+}
+
+fn default_history() -> usize {
+    16
+}
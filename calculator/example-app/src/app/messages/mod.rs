@@ -1,7 +1,16 @@
 use std::io::{Read, Write};
+use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 use atlas_smr_application::serialize::ApplicationData;
 use anyhow::Context;
+use thiserror::Error;
+use atlas_common::crypto::hash::Digest;
+use atlas_common::ordering::SeqNo;
+use crate::app::wire;
+use crate::app::wire::compact;
+use crate::app::wire::limits::MessageKind;
+use crate::app::wire::protobuf::{ReplyProto, RequestProto};
+use crate::app::wire::{WireFormatError, WireMessage};
 
 pub struct AppData;
 
@@ -9,49 +18,121 @@ impl ApplicationData for AppData {
     type Request = Request;
     type Reply = Reply;
 
-    fn serialize_request<W>(mut w: W, request: &Self::Request) -> atlas_common::error::Result<()> where W: Write {
-        bincode::serde::encode_into_std_write(request, &mut w, bincode::config::standard())
-            .context("Failed to serialize request")?;
-
-        Ok(())
+    fn serialize_request<W>(w: W, request: &Self::Request) -> atlas_common::error::Result<()> where W: Write {
+        wire::encode(w, request).context("Failed to serialize request")
     }
 
-    fn deserialize_request<R>(mut r: R) -> atlas_common::error::Result<Self::Request> where R: Read {
-        bincode::serde::decode_from_std_read(&mut r, bincode::config::standard())
-            .context("Failed to deserialize request")
+    fn deserialize_request<R>(r: R) -> atlas_common::error::Result<Self::Request> where R: Read {
+        wire::decode_buffered(r).context("Failed to deserialize request")
     }
 
-    fn serialize_reply<W>(mut w: W, reply: &Self::Reply) -> atlas_common::error::Result<()> where W: Write {
-        bincode::serde::encode_into_std_write(reply, &mut w, bincode::config::standard())
-            .context("Failed to serialize request")?;
-
-        Ok(())
+    fn serialize_reply<W>(w: W, reply: &Self::Reply) -> atlas_common::error::Result<()> where W: Write {
+        wire::encode(w, reply).context("Failed to serialize reply")
     }
 
-    fn deserialize_reply<R>(mut r: R) -> atlas_common::error::Result<Self::Reply> where R: Read {
-        bincode::serde::decode_from_std_read(&mut r, bincode::config::standard())
-            .context("Failed to deserialize request")
+    fn deserialize_reply<R>(r: R) -> atlas_common::error::Result<Self::Reply> where R: Read {
+        wire::decode_buffered(r).context("Failed to deserialize reply")
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// New variants must be appended, so that the variant indexes of the
+/// existing ones stay the same across wire format versions
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum Operation {
     Add,
     Sub,
     Mult,
     Divide,
     Remainder,
-    Exponent
+    Exponent,
+    /// Read the current value, ignoring the request's value
+    Get { consistency: ReadConsistency },
+    /// Set the value to the request's value, if the current one is `expected`
+    CompareAndSwap { expected: i32 },
+    /// Read the current value along with the digest of the whole state
+    Digest,
+    /// Read how many faults the replicas were deployed to tolerate, as the reply's value
+    Tolerance,
+    /// Open a new session for the client, replacing any it had. The reply's value is the
+    /// epoch of the session, see [crate::state::session_base]
+    OpenSession,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// How up to date the value returned by a read has to be
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadConsistency {
+    /// Whatever the first replica to reply has. Atlas picks how many unordered
+    /// replies to wait for per connection, so a client that waits for f+1 matching
+    /// replies serves these like [ReadConsistency::BFT]
+    Any,
+    /// A value that at least f+1 replicas agree on
+    #[default]
+    BFT,
+    /// The read is ordered along with the updates
+    Linearizable,
+    /// A value that reflects at least the given sequence number, usually
+    /// the latest one the client has seen
+    ReadYourWrites { after: SeqNo },
+}
+
+/// Why an operation could not be applied. The value is left untouched when this happens
+#[derive(Error, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum OperationError {
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Negative exponent {0}")]
+    NegativeExponent(i32),
+    #[error("The operation overflowed")]
+    Overflow,
+    #[error("Compare and swap failed, the current value is {current}")]
+    CompareAndSwapFailed { current: i32 },
+    #[error("Request {0} is too old to tell whether it was already executed")]
+    ExpiredRequest(u64),
+    #[error("The replica has only executed up to {executed:?}, but the read requires {required:?}")]
+    NotExecutedYet { executed: SeqNo, required: SeqNo },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Request {
     operation: Operation,
-    value: i32
+    value: i32,
+    /// Identifies the request among those of its client, so that a resubmitted
+    /// request is only executed once
+    id: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Reply {
+    result: Result<i32, OperationError>,
+    /// The sequence number the reply reflects. Replies to ordered requests carry the
+    /// sequence number they were executed at, while unordered ones carry the latest
+    /// one the replica executed
+    seq_no: SeqNo,
+    /// The digest of the state, in replies to [Operation::Digest]
+    digest: Option<Digest>,
+}
+
+/// The operations of the legacy encoding (version 0), which predates the format header
+#[derive(Deserialize)]
+enum LegacyOperation {
+    Add,
+    Sub,
+    Mult,
+    Divide,
+    Remainder,
+    Exponent
+}
+
+/// A request as laid out by the legacy encoding (version 0)
+#[derive(Deserialize)]
+struct LegacyRequest {
+    operation: LegacyOperation,
+    value: i32
+}
+
+/// A reply as laid out by the legacy encoding (version 0), which only carried the value
+#[derive(Deserialize)]
+struct LegacyReply {
     value: i32
 }
 
@@ -59,20 +140,163 @@ impl Request {
     pub fn new(operation: Operation, value: i32) -> Self {
         Request {
             operation,
-            value
+            value,
+            id: None,
         }
     }
 
+    pub fn with_id(self, id: u64) -> Self {
+        Request {
+            id: Some(id),
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> Option<u64> {
+        self.id
+    }
+
+    pub fn operation(&self) -> &Operation {
+        &self.operation
+    }
+
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
     pub fn into(self) -> (Operation, i32) {
         (self.operation, self.value)
     }
 
 }
 
+impl FromStr for ReadConsistency {
+    type Err = anyhow::Error;
+
+    /// Read your writes is parsed as being after [SeqNo::ZERO], for the client to
+    /// fill in with the latest sequence number it has seen
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "any" => Ok(ReadConsistency::Any),
+            "bft" => Ok(ReadConsistency::BFT),
+            "linearizable" => Ok(ReadConsistency::Linearizable),
+            "read_your_writes" => Ok(ReadConsistency::ReadYourWrites { after: SeqNo::ZERO }),
+            _ => Err(anyhow::anyhow!("Unknown read consistency {}, expected any, bft, linearizable or read_your_writes", s)),
+        }
+    }
+}
+
 impl Reply {
-    pub fn new(value: i32) -> Self {
+    pub fn new(result: Result<i32, OperationError>, seq_no: SeqNo) -> Self {
+        Reply {
+            result,
+            seq_no,
+            digest: None,
+        }
+    }
+
+    pub fn with_digest(self, digest: Digest) -> Self {
         Reply {
-            value
+            digest: Some(digest),
+            ..self
+        }
+    }
+
+    pub fn digest(&self) -> Option<&Digest> {
+        self.digest.as_ref()
+    }
+
+    pub fn result(&self) -> &Result<i32, OperationError> {
+        &self.result
+    }
+
+    pub fn seq_no(&self) -> SeqNo {
+        self.seq_no
+    }
+
+    pub fn into_result(self) -> Result<i32, OperationError> {
+        self.result
+    }
+}
+
+impl From<LegacyOperation> for Operation {
+    fn from(operation: LegacyOperation) -> Self {
+        match operation {
+            LegacyOperation::Add => Operation::Add,
+            LegacyOperation::Sub => Operation::Sub,
+            LegacyOperation::Mult => Operation::Mult,
+            LegacyOperation::Divide => Operation::Divide,
+            LegacyOperation::Remainder => Operation::Remainder,
+            LegacyOperation::Exponent => Operation::Exponent,
         }
     }
-}
\ No newline at end of file
+}
+
+impl WireMessage for Request {
+    type Proto = RequestProto;
+
+    const KIND: MessageKind = MessageKind::Request;
+
+    const COMPACT_SIZE: Option<usize> = Some(compact::REQUEST_SIZE);
+
+    fn to_proto(&self) -> Self::Proto {
+        self.into()
+    }
+
+    fn from_proto(proto: Self::Proto) -> Result<Self, WireFormatError> {
+        proto.try_into()
+    }
+
+    fn decode_legacy<R: Read>(r: R) -> Result<Self, WireFormatError> {
+        let LegacyRequest { operation, value } = wire::decode_bincode(0, r)?;
+
+        Ok(Request::new(operation.into(), value))
+    }
+
+    fn encode_compact(&self, buf: &mut [u8]) -> Result<(), WireFormatError> {
+        compact::encode_request(self, buf)
+    }
+
+    fn decode_compact(buf: &[u8]) -> Result<Self, WireFormatError> {
+        compact::decode_request(buf)
+    }
+}
+
+impl WireMessage for Reply {
+    type Proto = ReplyProto;
+
+    const KIND: MessageKind = MessageKind::Reply;
+
+    const COMPACT_SIZE: Option<usize> = Some(compact::REPLY_SIZE);
+
+    /// The compact layout has no room for a digest
+    fn compact_size(&self) -> Option<usize> {
+        match self.digest {
+            Some(_) => None,
+            None => Self::COMPACT_SIZE,
+        }
+    }
+
+    fn decode_legacy<R: Read>(r: R) -> Result<Self, WireFormatError> {
+        let LegacyReply { value } = wire::decode_bincode(0, r)?;
+
+        // The legacy reply had neither an error nor a sequence number
+        Ok(Reply::new(Ok(value), SeqNo::ZERO))
+    }
+
+    fn to_proto(&self) -> Self::Proto {
+        self.into()
+    }
+
+    fn from_proto(proto: Self::Proto) -> Result<Self, WireFormatError> {
+        proto.try_into()
+    }
+
+    fn encode_compact(&self, buf: &mut [u8]) -> Result<(), WireFormatError> {
+        compact::encode_reply(self, buf)
+    }
+
+    fn decode_compact(buf: &[u8]) -> Result<Self, WireFormatError> {
+        compact::decode_reply(buf)
+    }
+}
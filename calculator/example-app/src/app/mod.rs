@@ -1,13 +1,96 @@
+pub mod admin;
+pub mod checkpoints;
 pub mod messages;
+pub mod registers;
+pub mod subscriptions;
+pub mod wire;
 
-use atlas_smr_application::app::{Application, Reply, Request};
-use crate::state::CalculatorState;
+use std::sync::{Arc, OnceLock};
 
-pub struct App;
+use atlas_common::node_id::NodeId;
+use atlas_smr_application::app::{Application, BatchReplies, Reply, Request, UpdateBatch};
+use crate::app::checkpoints::CheckpointDigests;
+use crate::app::messages::{OperationError, ReadConsistency};
+use crate::app::subscriptions::SubscriptionRegistry;
+use crate::state::{CalculatorState, PastRequest};
+use crate::trace::{RequestTraceId, Stage, Tracer};
+
+/// The state replicas start from when their persistent log is empty, see [set_initial_state]
+static INITIAL_STATE: OnceLock<CalculatorState> = OnceLock::new();
+
+/// Start from this state instead of an empty one, such as the state of a snapshot.
+///
+/// Every replica of a cluster must start from the same state, and this has to be
+/// called before the replica is started
+pub fn set_initial_state(state: CalculatorState) -> Result<(), CalculatorState> {
+    INITIAL_STATE.set(state)
+}
+
+pub struct App {
+    tracer: Option<Tracer>,
+    /// Notified after every batch that changes the value
+    subscriptions: Option<Arc<SubscriptionRegistry>>,
+    /// Where the digest of the state is recorded at every checkpoint
+    checkpoints: Option<Arc<CheckpointDigests>>,
+    /// What [messages::Operation::Tolerance] is answered with
+    tolerated_faults: usize,
+}
 
 impl App {
     pub fn init() -> Self {
-        Self {}
+        Self {
+            tracer: None,
+            subscriptions: None,
+            checkpoints: None,
+            tolerated_faults: 0,
+        }
+    }
+
+    /// Answer [messages::Operation::Tolerance] with `f`, so clients can check
+    /// they size their quorums like the replicas do
+    pub fn with_tolerated_faults(self, f: usize) -> Self {
+        Self {
+            tolerated_faults: f,
+            ..self
+        }
+    }
+
+    pub fn with_checkpoint_digests(self, checkpoints: Option<Arc<CheckpointDigests>>) -> Self {
+        Self {
+            checkpoints,
+            ..self
+        }
+    }
+
+    pub fn with_subscriptions(self, subscriptions: Option<Arc<SubscriptionRegistry>>) -> Self {
+        Self {
+            subscriptions,
+            ..self
+        }
+    }
+
+    /// Emit a span for the execution of every request
+    pub fn with_tracer(self, tracer: Option<Tracer>) -> Self {
+        Self {
+            tracer,
+            ..self
+        }
+    }
+
+    /// Execute a request that carries an id, unless its client already had it executed,
+    /// in which case it gets the same reply as the first time
+    fn update_once(&self, state: &mut CalculatorState, from: NodeId, request_id: u64, request: messages::Request) -> messages::Reply {
+        match state.past_request(from, request_id) {
+            PastRequest::Executed(reply) => reply.clone(),
+            PastRequest::Expired => messages::Reply::new(Err(OperationError::ExpiredRequest(request_id)), state.executed()),
+            PastRequest::New => {
+                let reply = self.update(state, request);
+
+                state.record_reply(from, request_id, reply.clone());
+
+                reply
+            }
+        }
     }
 }
 
@@ -15,36 +98,133 @@ impl Application<CalculatorState> for App {
     type AppData = messages::AppData;
 
     fn initial_state() -> atlas_common::error::Result<CalculatorState> {
-        Ok(Default::default())
+        Ok(INITIAL_STATE.get().cloned().unwrap_or_default())
     }
 
-    fn unordered_execution(&self, state: &CalculatorState, _: Request<Self, CalculatorState>) -> Reply<Self, CalculatorState> {
-        messages::Reply::new(state.value())
+    fn unordered_execution(&self, state: &CalculatorState, request: Request<Self, CalculatorState>) -> Reply<Self, CalculatorState> {
+        let result = match request.operation() {
+            messages::Operation::Get { consistency: ReadConsistency::ReadYourWrites { after } } if state.executed() < *after => {
+                Err(OperationError::NotExecutedYet { executed: state.executed(), required: *after })
+            }
+            messages::Operation::Tolerance => Ok(self.tolerated_faults as i32),
+            _ => Ok(state.value()),
+        };
+
+        // Replicas that executed up to different sequence numbers send different replies,
+        // which is why clients order the reads that f+1 replicas fail to agree on
+        let reply = messages::Reply::new(result, state.executed());
+
+        match request.operation() {
+            messages::Operation::Digest => reply.with_digest(state.digest()),
+            _ => reply,
+        }
     }
+
     fn update(&self, state: &mut CalculatorState, request: messages::Request) -> messages::Reply {
         let (op, value) = request.into();
 
+        if op == messages::Operation::Tolerance {
+            return messages::Reply::new(Ok(self.tolerated_faults as i32), state.executed());
+        }
+
+        let result = apply_operation(state.value(), &op, value);
+
+        if let Ok(new_value) = result {
+            state.set_value(new_value);
+        }
+
+        let reply = messages::Reply::new(result, state.executed());
+
         match op {
-            messages::Operation::Add => {
-                state.set_value(state.value() + value);
-            },
-            messages::Operation::Sub => {
-                state.set_value(state.value() - value);
-            },
-            messages::Operation::Mult => {
-                state.set_value(state.value() * value);
-            },
-            messages::Operation::Divide => {
-                state.set_value(state.value() / value);
-            },
-            messages::Operation::Remainder => {
-                state.set_value(state.value() % value);
-            },
-            messages::Operation::Exponent => {
-                state.set_value(state.value().pow(value as u32));
+            messages::Operation::Digest => reply.with_digest(state.digest()),
+            _ => reply,
+        }
+    }
+
+    fn update_batch(&self, state: &mut CalculatorState, batch: UpdateBatch<Request<Self, CalculatorState>>) -> BatchReplies<Reply<Self, CalculatorState>> {
+        let seq_no = batch.sequence_number();
+        let batch_size = batch.len();
+
+        let mut replies = BatchReplies::with_capacity(batch_size);
+
+        state.set_executed(seq_no);
+
+        let previous = state.value();
+
+        for update in batch.into_inner() {
+            let (from, session, operation_id, request) = update.into_inner();
+
+            // Only requests with an id can be told apart by their client as well
+            let span = self.tracer.as_ref().zip(request.id()).map(|(tracer, request_id)| {
+                let mut span = tracer.start_span(RequestTraceId::new(from, request_id), Stage::OrderedExecution);
+
+                span.set_attribute("smr.seq_no", seq_no);
+                span.set_attribute("smr.batch_size", batch_size);
+
+                span
+            });
+
+            let reply = match (request.operation(), request.id()) {
+                // Sessions are opened here, as [Application::update] does not know who sent the request
+                (messages::Operation::OpenSession, _) => messages::Reply::new(Ok(state.open_session(from) as i32), state.executed()),
+                (_, Some(request_id)) => self.update_once(state, from, request_id, request),
+                (_, None) => self.update(state, request),
+            };
+
+            if let Some(span) = span {
+                span.finish();
             }
+
+            replies.add(from, session, operation_id, reply);
+        }
+
+        if let Some(subscriptions) = &self.subscriptions {
+            subscriptions.notify(previous, state.value(), seq_no);
         }
 
-        messages::Reply::new(state.value())
+        if let Some(checkpoints) = &self.checkpoints {
+            checkpoints.record_if_due(seq_no, state);
+        }
+
+        replies
+    }
+}
+
+/// Apply an operation to the current value, returning the resulting value.
+///
+/// All arithmetic is checked, so the outcome does not depend on how the replica was compiled
+pub(crate) fn apply_operation(current: i32, op: &messages::Operation, value: i32) -> Result<i32, OperationError> {
+    match op {
+        messages::Operation::Add => current.checked_add(value).ok_or(OperationError::Overflow),
+        messages::Operation::Sub => current.checked_sub(value).ok_or(OperationError::Overflow),
+        messages::Operation::Mult => current.checked_mul(value).ok_or(OperationError::Overflow),
+        messages::Operation::Divide => {
+            if value == 0 {
+                return Err(OperationError::DivisionByZero);
+            }
+
+            current.checked_div(value).ok_or(OperationError::Overflow)
+        }
+        messages::Operation::Remainder => {
+            if value == 0 {
+                return Err(OperationError::DivisionByZero);
+            }
+
+            current.checked_rem(value).ok_or(OperationError::Overflow)
+        }
+        messages::Operation::Exponent => {
+            let exponent = u32::try_from(value).map_err(|_| OperationError::NegativeExponent(value))?;
+
+            current.checked_pow(exponent).ok_or(OperationError::Overflow)
+        }
+        messages::Operation::Get { .. } | messages::Operation::Digest | messages::Operation::Tolerance
+        | messages::Operation::OpenSession => Ok(current),
+        messages::Operation::CompareAndSwap { expected } => {
+            if current == *expected {
+                Ok(value)
+            } else {
+                Err(OperationError::CompareAndSwapFailed { current })
+            }
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,188 @@
+use std::io::{Read, Write};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use atlas_common::crypto::hash::Digest;
+use atlas_smr_application::app::{Application, BatchReplies, Reply, Request, UpdateBatch};
+use atlas_smr_application::serialize::ApplicationData;
+use crate::app::apply_operation;
+use crate::app::messages;
+use crate::app::messages::{Operation, OperationError};
+use crate::app::wire;
+use crate::app::wire::{WireFormatError, WireMessage};
+use crate::app::wire::limits::MessageKind;
+use crate::app::wire::protobuf::{RegisterReplyProto, RegisterRequestProto};
+use crate::state::registers::RegisterState;
+
+/// The calculator application over a [RegisterState], where each request
+/// targets one of the registers instead of a single value.
+///
+/// It keeps no client sessions, so requests that carry an id and [Operation::OpenSession]
+/// are answered with [RegisterReply::Unsupported] instead of being executed more than once
+pub struct RegisterApp {
+    /// What [Operation::Tolerance] is answered with
+    tolerated_faults: usize,
+}
+
+impl RegisterApp {
+    pub fn init() -> Self {
+        Self {
+            tolerated_faults: 0,
+        }
+    }
+
+    /// Answer [Operation::Tolerance] with `f`, so clients can check
+    /// they size their quorums like the replicas do
+    pub fn with_tolerated_faults(self, f: usize) -> Self {
+        Self {
+            tolerated_faults: f,
+        }
+    }
+}
+
+pub struct RegisterAppData;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RegisterRequest {
+    register: u32,
+    request: messages::Request,
+}
+
+/// New variants must be appended, like those of [Operation]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum RegisterReply {
+    Value(i32),
+    Error(OperationError),
+    UnknownRegister(u32),
+    /// The digest of every register, in replies to [Operation::Digest]
+    Digest(Digest),
+    /// The request carried an id or opened a session, which need sessions this app does not keep
+    Unsupported,
+}
+
+impl RegisterRequest {
+    pub fn new(register: u32, request: messages::Request) -> Self {
+        Self { register, request }
+    }
+
+    pub fn register(&self) -> u32 {
+        self.register
+    }
+
+    pub fn request(&self) -> &messages::Request {
+        &self.request
+    }
+}
+
+impl ApplicationData for RegisterAppData {
+    type Request = RegisterRequest;
+    type Reply = RegisterReply;
+
+    fn serialize_request<W>(w: W, request: &Self::Request) -> atlas_common::error::Result<()> where W: Write {
+        wire::encode(w, request).context("Failed to serialize request")
+    }
+
+    fn deserialize_request<R>(r: R) -> atlas_common::error::Result<Self::Request> where R: Read {
+        wire::decode_buffered(r).context("Failed to deserialize request")
+    }
+
+    fn serialize_reply<W>(w: W, reply: &Self::Reply) -> atlas_common::error::Result<()> where W: Write {
+        wire::encode(w, reply).context("Failed to serialize reply")
+    }
+
+    fn deserialize_reply<R>(r: R) -> atlas_common::error::Result<Self::Reply> where R: Read {
+        wire::decode_buffered(r).context("Failed to deserialize reply")
+    }
+}
+
+/// Register messages have no compact layout, so [wire::Codec::Compact] encodes them with bincode
+impl WireMessage for RegisterRequest {
+    type Proto = RegisterRequestProto;
+
+    const KIND: MessageKind = MessageKind::Request;
+
+    fn to_proto(&self) -> Self::Proto {
+        self.into()
+    }
+
+    fn from_proto(proto: Self::Proto) -> Result<Self, WireFormatError> {
+        proto.try_into()
+    }
+}
+
+impl WireMessage for RegisterReply {
+    type Proto = RegisterReplyProto;
+
+    const KIND: MessageKind = MessageKind::Reply;
+
+    fn to_proto(&self) -> Self::Proto {
+        self.into()
+    }
+
+    fn from_proto(proto: Self::Proto) -> Result<Self, WireFormatError> {
+        proto.try_into()
+    }
+}
+
+impl Application<RegisterState> for RegisterApp {
+    type AppData = RegisterAppData;
+
+    fn initial_state() -> atlas_common::error::Result<RegisterState> {
+        Ok(Default::default())
+    }
+
+    fn unordered_execution(&self, state: &RegisterState, request: Request<Self, RegisterState>) -> Reply<Self, RegisterState> {
+        match request.request.operation() {
+            Operation::Tolerance => RegisterReply::Value(self.tolerated_faults as i32),
+            Operation::Digest => RegisterReply::Digest(state.digest()),
+            _ => match state.register(request.register) {
+                Some(value) => RegisterReply::Value(value),
+                None => RegisterReply::UnknownRegister(request.register),
+            },
+        }
+    }
+
+    fn update(&self, state: &mut RegisterState, request: Request<Self, RegisterState>) -> Reply<Self, RegisterState> {
+        let RegisterRequest { register, request } = request;
+
+        // Without sessions, a resubmitted request could not be told apart from a new one
+        if request.id().is_some() {
+            return RegisterReply::Unsupported;
+        }
+
+        match request.operation() {
+            Operation::OpenSession => return RegisterReply::Unsupported,
+            Operation::Tolerance => return RegisterReply::Value(self.tolerated_faults as i32),
+            Operation::Digest => return RegisterReply::Digest(state.digest()),
+            _ => {}
+        }
+
+        let Some(current) = state.register(register) else {
+            return RegisterReply::UnknownRegister(register);
+        };
+
+        let (op, value) = request.into();
+
+        match apply_operation(current, &op, value) {
+            Ok(new_value) => {
+                state.set_register(register, new_value);
+
+                RegisterReply::Value(new_value)
+            }
+            Err(error) => RegisterReply::Error(error),
+        }
+    }
+
+    fn update_batch(&self, state: &mut RegisterState, batch: UpdateBatch<Request<Self, RegisterState>>) -> BatchReplies<Reply<Self, RegisterState>> {
+        let mut replies = BatchReplies::with_capacity(batch.len());
+
+        state.set_executed(batch.sequence_number());
+
+        for update in batch.into_inner() {
+            let (from, session, operation_id, request) = update.into_inner();
+
+            replies.add(from, session, operation_id, self.update(state, request));
+        }
+
+        replies
+    }
+}
@@ -0,0 +1,234 @@
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::ops::Deref;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use anyhow::{anyhow, Context};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use atlas_common::ordering::SeqNo;
+use crate::tolerance::BootstrapNode;
+
+/// Watch messages are tiny, so anything larger than this is not one of ours
+const MAX_WATCH_MESSAGE_SIZE: usize = 1024;
+
+/// Where replicas accept watch subscriptions
+#[derive(Deserialize, Clone, Debug)]
+pub struct WatchConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Each replica listens on its own port (from `nodes.toml`) plus this offset
+    #[serde(default = "default_port_offset")]
+    pub port_offset: u16,
+    /// How many notifications may wait for a subscriber before it is disconnected
+    #[serde(default = "default_max_pending_notifications")]
+    pub max_pending_notifications: usize,
+    /// How many subscribers may be connected at once, further ones are turned away
+    #[serde(default = "default_max_subscribers")]
+    pub max_subscribers: usize,
+}
+
+/// Which changes of the value a subscriber wants to hear about
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchFilter {
+    /// Every change of the value
+    All,
+    /// Only changes that take the value from below the threshold to at or above it, or the opposite
+    Crossing { threshold: i32 },
+}
+
+/// The value after an ordered batch changed it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Notification {
+    pub value: i32,
+    pub seq_no: SeqNo,
+}
+
+/// The messages exchanged over a watch connection, each framed with its length as a big endian `u32`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum WatchMessage {
+    /// Sent by the client, once, when it connects
+    Subscribe(WatchFilter),
+    /// Sent by the replica
+    Notification(Notification),
+    /// Sent by the replica once it registers the subscription, with the amount of faults
+    /// it was deployed to tolerate, before any notification
+    Subscribed { f: usize },
+}
+
+/// The subscribers of a replica, which the [crate::app::App] notifies after executing each batch.
+///
+/// Notifying never blocks the execution: a subscriber that lets `max_pending` notifications
+/// pile up is dropped, which closes its connection once the pending ones are sent
+pub struct SubscriptionRegistry {
+    subscribers: Mutex<Subscribers>,
+    max_pending: usize,
+}
+
+#[derive(Default)]
+struct Subscribers {
+    list: Vec<Subscriber>,
+    next_id: u64,
+}
+
+struct Subscriber {
+    id: u64,
+    filter: WatchFilter,
+    tx: SyncSender<Notification>,
+}
+
+/// The notifications of a subscriber, which is unregistered when this is dropped
+pub struct Subscription<'a> {
+    registry: &'a SubscriptionRegistry,
+    id: u64,
+    notifications: Receiver<Notification>,
+}
+
+impl WatchFilter {
+    pub fn matches(&self, previous: i32, current: i32) -> bool {
+        if previous == current {
+            return false;
+        }
+
+        match self {
+            WatchFilter::All => true,
+            WatchFilter::Crossing { threshold } => (previous < *threshold) != (current < *threshold),
+        }
+    }
+}
+
+impl SubscriptionRegistry {
+    pub fn new(max_pending: usize) -> Self {
+        Self {
+            subscribers: Mutex::new(Subscribers::default()),
+            max_pending,
+        }
+    }
+
+    pub fn subscribe(&self, filter: WatchFilter) -> Subscription<'_> {
+        let (tx, notifications) = mpsc::sync_channel(self.max_pending);
+
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        let id = subscribers.next_id;
+
+        subscribers.next_id += 1;
+        subscribers.list.push(Subscriber { id, filter, tx });
+
+        Subscription {
+            registry: self,
+            id,
+            notifications,
+        }
+    }
+
+    /// Notify the subscribers whose filter matches the change, forgetting those
+    /// that went away and those that fell too far behind
+    pub fn notify(&self, previous: i32, current: i32, seq_no: SeqNo) {
+        let notification = Notification { value: current, seq_no };
+
+        self.subscribers.lock().unwrap().list.retain(|subscriber| {
+            if !subscriber.filter.matches(previous, current) {
+                return true;
+            }
+
+            match subscriber.tx.try_send(notification) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    warn!("Dropping a watch subscriber with {} notifications pending", self.max_pending);
+
+                    false
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap().list.len()
+    }
+}
+
+impl Deref for Subscription<'_> {
+    type Target = Receiver<Notification>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.notifications
+    }
+}
+
+impl Drop for Subscription<'_> {
+    fn drop(&mut self) {
+        self.registry.subscribers.lock().unwrap().list.retain(|subscriber| subscriber.id != self.id);
+    }
+}
+
+impl Default for SubscriptionRegistry {
+    fn default() -> Self {
+        Self::new(default_max_pending_notifications())
+    }
+}
+
+/// The address a replica accepts watch subscriptions on
+pub fn watch_address(node: &BootstrapNode, config: &WatchConfig) -> atlas_common::error::Result<SocketAddr> {
+    let port = node.port.checked_add(config.port_offset)
+        .ok_or_else(|| anyhow!("Watch port offset {} overflows the port of replica {}", config.port_offset, node.node_id))?;
+
+    format!("{}:{}", node.ip, port).parse()
+        .with_context(|| format!("Invalid address for replica {}", node.node_id))
+}
+
+pub fn write_message<W>(mut w: W, message: &WatchMessage) -> atlas_common::error::Result<()> where W: Write {
+    let bytes = bincode::serde::encode_to_vec(message, bincode::config::standard())
+        .context("Failed to serialize watch message")?;
+
+    w.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    w.write_all(&bytes)?;
+    w.flush()?;
+
+    Ok(())
+}
+
+pub fn read_message<R>(mut r: R) -> atlas_common::error::Result<WatchMessage> where R: Read {
+    let mut length = [0; 4];
+
+    r.read_exact(&mut length)?;
+
+    let length = u32::from_be_bytes(length) as usize;
+
+    if length > MAX_WATCH_MESSAGE_SIZE {
+        return Err(anyhow!("Watch message of {} bytes exceeds the maximum of {}", length, MAX_WATCH_MESSAGE_SIZE));
+    }
+
+    let mut bytes = vec![0; length];
+
+    r.read_exact(&mut bytes)?;
+
+    let (message, _) = bincode::serde::decode_from_slice(&bytes, bincode::config::standard())
+        .context("Failed to deserialize watch message")?;
+
+    Ok(message)
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port_offset: default_port_offset(),
+            max_pending_notifications: default_max_pending_notifications(),
+            max_subscribers: default_max_subscribers(),
+        }
+    }
+}
+
+fn default_port_offset() -> u16 {
+    1000
+}
+
+fn default_max_pending_notifications() -> usize {
+    256
+}
+
+fn default_max_subscribers() -> usize {
+    64
+}
@@ -0,0 +1,206 @@
+//! A fixed layout encoding of [Request] and [Reply], which is encoded into and decoded
+//! from a byte buffer without allocating. All integers are little endian.
+//!
+//! A request takes [REQUEST_SIZE] bytes:
+//!
+//! | offset | size | field                                                             |
+//! |--------|------|-------------------------------------------------------------------|
+//! | 0      | 1    | operation                                                         |
+//! | 1      | 1    | read consistency, for reads                                       |
+//! | 2      | 4    | value                                                             |
+//! | 6      | 4    | the sequence number of a read your writes read, or the expected   |
+//! |        |      | value of a compare and swap                                       |
+//! | 10     | 1    | whether the request has an id                                     |
+//! | 11     | 8    | id                                                                |
+//!
+//! A reply takes [REPLY_SIZE] bytes:
+//!
+//! | offset | size | field                                                             |
+//! |--------|------|-------------------------------------------------------------------|
+//! | 0      | 1    | 0 for a value, otherwise 1 + the kind of error                    |
+//! | 1      | 4    | value, negative exponent or current value of a failed swap        |
+//! | 5      | 8    | expired request id                                                |
+//! | 13     | 4    | executed sequence number of a read that is ahead of the replica   |
+//! | 17     | 4    | required sequence number of a read that is ahead of the replica   |
+//! | 21     | 4    | sequence number of the reply                                      |
+
+use atlas_common::ordering::SeqNo;
+use crate::app::messages::{Operation, OperationError, ReadConsistency, Reply, Request};
+use crate::app::wire::WireFormatError;
+
+pub const REQUEST_SIZE: usize = 19;
+
+pub const REPLY_SIZE: usize = 25;
+
+/// The largest of the compact layouts, so a stack buffer of this size fits any of them
+pub const MAX_COMPACT_SIZE: usize = REPLY_SIZE;
+
+pub fn encode_request(request: &Request, buf: &mut [u8]) -> Result<(), WireFormatError> {
+    let buf = sized_mut(buf, REQUEST_SIZE)?;
+
+    let (operation, consistency, aux) = match request.operation() {
+        Operation::Add => (0, 0, 0),
+        Operation::Sub => (1, 0, 0),
+        Operation::Mult => (2, 0, 0),
+        Operation::Divide => (3, 0, 0),
+        Operation::Remainder => (4, 0, 0),
+        Operation::Exponent => (5, 0, 0),
+        Operation::Get { consistency } => match consistency {
+            ReadConsistency::Any => (6, 0, 0),
+            ReadConsistency::BFT => (6, 1, 0),
+            ReadConsistency::Linearizable => (6, 2, 0),
+            ReadConsistency::ReadYourWrites { after } => (6, 3, u32::from(*after)),
+        },
+        Operation::CompareAndSwap { expected } => (7, 0, *expected as u32),
+        Operation::Digest => (8, 0, 0),
+        Operation::Tolerance => (9, 0, 0),
+        Operation::OpenSession => (10, 0, 0),
+    };
+
+    buf[0] = operation;
+    buf[1] = consistency;
+    buf[2..6].copy_from_slice(&request.value().to_le_bytes());
+    buf[6..10].copy_from_slice(&aux.to_le_bytes());
+    buf[10] = request.id().is_some() as u8;
+    buf[11..19].copy_from_slice(&request.id().unwrap_or(0).to_le_bytes());
+
+    Ok(())
+}
+
+pub fn decode_request(buf: &[u8]) -> Result<Request, WireFormatError> {
+    let buf = sized(buf, REQUEST_SIZE)?;
+
+    let aux = read_u32(buf, 6);
+
+    let operation = match buf[0] {
+        0 => Operation::Add,
+        1 => Operation::Sub,
+        2 => Operation::Mult,
+        3 => Operation::Divide,
+        4 => Operation::Remainder,
+        5 => Operation::Exponent,
+        6 => {
+            let consistency = match buf[1] {
+                0 => ReadConsistency::Any,
+                1 => ReadConsistency::BFT,
+                2 => ReadConsistency::Linearizable,
+                3 => ReadConsistency::ReadYourWrites { after: SeqNo::from(aux) },
+                tag => return Err(WireFormatError::InvalidCompactTag { field: "read consistency", tag }),
+            };
+
+            Operation::Get { consistency }
+        }
+        7 => Operation::CompareAndSwap { expected: aux as i32 },
+        8 => Operation::Digest,
+        9 => Operation::Tolerance,
+        10 => Operation::OpenSession,
+        tag => return Err(WireFormatError::InvalidCompactTag { field: "operation", tag }),
+    };
+
+    let request = Request::new(operation, read_u32(buf, 2) as i32);
+
+    match buf[10] {
+        0 => Ok(request),
+        1 => Ok(request.with_id(read_u64(buf, 11))),
+        tag => Err(WireFormatError::InvalidCompactTag { field: "request id", tag }),
+    }
+}
+
+/// Leaves out the digest the reply may carry, so replies with one are sent with another codec
+pub fn encode_reply(reply: &Reply, buf: &mut [u8]) -> Result<(), WireFormatError> {
+    let buf = sized_mut(buf, REPLY_SIZE)?;
+
+    buf.fill(0);
+
+    let tag = match reply.result() {
+        Ok(value) => {
+            buf[1..5].copy_from_slice(&value.to_le_bytes());
+
+            0
+        }
+        Err(OperationError::DivisionByZero) => 1,
+        Err(OperationError::NegativeExponent(exponent)) => {
+            buf[1..5].copy_from_slice(&exponent.to_le_bytes());
+
+            2
+        }
+        Err(OperationError::Overflow) => 3,
+        Err(OperationError::CompareAndSwapFailed { current }) => {
+            buf[1..5].copy_from_slice(&current.to_le_bytes());
+
+            4
+        }
+        Err(OperationError::ExpiredRequest(request)) => {
+            buf[5..13].copy_from_slice(&request.to_le_bytes());
+
+            5
+        }
+        Err(OperationError::NotExecutedYet { executed, required }) => {
+            buf[13..17].copy_from_slice(&u32::from(*executed).to_le_bytes());
+            buf[17..21].copy_from_slice(&u32::from(*required).to_le_bytes());
+
+            6
+        }
+    };
+
+    buf[0] = tag;
+    buf[21..25].copy_from_slice(&u32::from(reply.seq_no()).to_le_bytes());
+
+    Ok(())
+}
+
+pub fn decode_reply(buf: &[u8]) -> Result<Reply, WireFormatError> {
+    let buf = sized(buf, REPLY_SIZE)?;
+
+    let value = read_u32(buf, 1) as i32;
+
+    let result = match buf[0] {
+        0 => Ok(value),
+        1 => Err(OperationError::DivisionByZero),
+        2 => Err(OperationError::NegativeExponent(value)),
+        3 => Err(OperationError::Overflow),
+        4 => Err(OperationError::CompareAndSwapFailed { current: value }),
+        5 => Err(OperationError::ExpiredRequest(read_u64(buf, 5))),
+        6 => Err(OperationError::NotExecutedYet {
+            executed: SeqNo::from(read_u32(buf, 13)),
+            required: SeqNo::from(read_u32(buf, 17)),
+        }),
+        tag => return Err(WireFormatError::InvalidCompactTag { field: "reply result", tag }),
+    };
+
+    Ok(Reply::new(result, SeqNo::from(read_u32(buf, 21))))
+}
+
+fn sized(buf: &[u8], expected: usize) -> Result<&[u8], WireFormatError> {
+    if buf.len() != expected {
+        return Err(WireFormatError::CompactLength { expected, found: buf.len() });
+    }
+
+    Ok(buf)
+}
+
+fn sized_mut(buf: &mut [u8], expected: usize) -> Result<&mut [u8], WireFormatError> {
+    let found = buf.len();
+
+    if found != expected {
+        return Err(WireFormatError::CompactLength { expected, found });
+    }
+
+    Ok(buf)
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+
+    bytes.copy_from_slice(&buf[offset..offset + 4]);
+
+    u32::from_le_bytes(bytes)
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+
+    bytes.copy_from_slice(&buf[offset..offset + 8]);
+
+    u64::from_le_bytes(bytes)
+}
@@ -0,0 +1,152 @@
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::{Deserialize, Serialize};
+
+/// The most bincode will ever read for a single message, whatever the configured limits.
+/// Bincode only takes its limit at compile time, so the configured limits are enforced
+/// on the bytes read instead. It bounds requests as much as states, which is safe as long
+/// as a forged length prefix cannot make us allocate, see [super::decode_bincode]
+pub const MAX_DECODE_SIZE: usize = 256 * 1024 * 1024;
+
+/// The state limit when none is configured, which a state with every session full fits,
+/// see [crate::state::DEDUPLICATION_WINDOW]
+pub const DEFAULT_MAX_STATE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Which kind of message is being decoded, each with its own size limit
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    Request,
+    Reply,
+    State,
+}
+
+/// The largest encoded messages we accept, in bytes, including the format header
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct DecodeLimits {
+    #[serde(default = "default_max_request_size")]
+    pub max_request_size: usize,
+    #[serde(default = "default_max_reply_size")]
+    pub max_reply_size: usize,
+    #[serde(default = "default_max_state_size")]
+    pub max_state_size: usize,
+}
+
+/// How many payloads of each kind were rejected for exceeding their limit
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct RejectedPayloads {
+    pub requests: u64,
+    pub replies: u64,
+    pub states: u64,
+}
+
+static REJECTED_REQUESTS: AtomicU64 = AtomicU64::new(0);
+static REJECTED_REPLIES: AtomicU64 = AtomicU64::new(0);
+static REJECTED_STATES: AtomicU64 = AtomicU64::new(0);
+
+/// Stops reading once the limit is reached, remembering whether the message went past it
+pub(super) struct LimitedReader<R> {
+    inner: R,
+    remaining: usize,
+    exceeded: bool,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_request_size: default_max_request_size(),
+            max_reply_size: default_max_reply_size(),
+            max_state_size: default_max_state_size(),
+        }
+    }
+}
+
+impl DecodeLimits {
+    pub fn max_size(&self, kind: MessageKind) -> usize {
+        let limit = match kind {
+            MessageKind::Request => self.max_request_size,
+            MessageKind::Reply => self.max_reply_size,
+            MessageKind::State => self.max_state_size,
+        };
+
+        limit.min(MAX_DECODE_SIZE)
+    }
+}
+
+impl MessageKind {
+    fn counter(self) -> &'static AtomicU64 {
+        match self {
+            MessageKind::Request => &REJECTED_REQUESTS,
+            MessageKind::Reply => &REJECTED_REPLIES,
+            MessageKind::State => &REJECTED_STATES,
+        }
+    }
+}
+
+pub fn rejected_payloads() -> RejectedPayloads {
+    RejectedPayloads {
+        requests: REJECTED_REQUESTS.load(Ordering::Relaxed),
+        replies: REJECTED_REPLIES.load(Ordering::Relaxed),
+        states: REJECTED_STATES.load(Ordering::Relaxed),
+    }
+}
+
+/// Count a rejected payload, returning how many of its kind were rejected so far
+pub(super) fn record_rejection(kind: MessageKind) -> u64 {
+    kind.counter().fetch_add(1, Ordering::Relaxed) + 1
+}
+
+impl<R> LimitedReader<R> where R: Read {
+    pub(super) fn new(inner: R, limit: usize) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+            exceeded: false,
+        }
+    }
+
+    pub(super) fn exceeded(&self) -> bool {
+        self.exceeded
+    }
+}
+
+impl<R> Read for LimitedReader<R> where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.remaining == 0 {
+            // Only a message that actually goes on past the limit is too large
+            let mut probe = [0u8; 1];
+
+            if self.inner.read(&mut probe)? == 0 {
+                return Ok(0);
+            }
+
+            self.exceeded = true;
+
+            return Err(std::io::Error::other("The message exceeds its size limit"));
+        }
+
+        let len = buf.len().min(self.remaining);
+
+        let read = self.inner.read(&mut buf[..len])?;
+
+        self.remaining -= read;
+
+        Ok(read)
+    }
+}
+
+fn default_max_request_size() -> usize {
+    4 * 1024
+}
+
+fn default_max_reply_size() -> usize {
+    4 * 1024
+}
+
+fn default_max_state_size() -> usize {
+    DEFAULT_MAX_STATE_SIZE
+}
@@ -0,0 +1,423 @@
+pub mod compact;
+pub mod limits;
+pub mod protobuf;
+
+use std::io::{Cursor, Read, Write};
+use std::sync::OnceLock;
+use std::time::Instant;
+use serde::de::DeserializeOwned;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::app::wire::limits::{DecodeLimits, LimitedReader, MessageKind, MAX_DECODE_SIZE};
+
+/// Marks an encoding that starts with a format header.
+///
+/// Legacy encodings start with a bincode varint (the variant index of the request's operation,
+/// or the value of the reply or of the state), which never starts with this byte
+pub const FORMAT_MAGIC: u8 = 0xFF;
+
+/// The format version written by this build, whose header names the codec of the body
+pub const CURRENT_VERSION: u8 = 1;
+
+/// The oldest format version this build still decodes. Version 0 is the legacy
+/// encoding, which carries no header at all. It is decoded with the layouts of the
+/// original release, where requests had no id, replies only carried the value and
+/// the state was only the value
+pub const MIN_SUPPORTED_VERSION: u8 = 0;
+
+/// The codec of every serialized state. Replicas digest and compare the serialized state
+/// (checkpoints, state transfer, snapshots), so it must not depend on any process' settings
+pub const STATE_CODEC: Codec = Codec::Bincode;
+
+/// Messages that fit in this many bytes are decoded by [decode_buffered] without allocating a buffer
+pub const STACK_BUFFER_SIZE: usize = 256;
+
+/// The codec this process encodes with, see [set_codec]
+static CODEC: OnceLock<Codec> = OnceLock::new();
+
+/// The size limits this process decodes with, see [set_limits]
+static LIMITS: OnceLock<DecodeLimits> = OnceLock::new();
+
+/// How the body of requests and replies is encoded. The state is always encoded
+/// with [STATE_CODEC], whatever codec the process picked.
+///
+/// Since the header names the codec, a process decodes whatever codec its peers picked
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Codec {
+    #[default]
+    Bincode,
+    Protobuf,
+    /// Readable, for debugging
+    Json,
+    /// The fixed layout of [compact], for requests and replies. Messages without
+    /// one, like the state, are encoded with bincode instead
+    Compact,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct CodecConfig {
+    #[serde(default)]
+    pub codec: Codec,
+    #[serde(default)]
+    pub limits: DecodeLimits,
+}
+
+/// The encoded size of a set of messages, and how long it took to encode and decode them
+#[derive(Serialize, Clone, Debug)]
+pub struct CodecStats {
+    pub kind: MessageKind,
+    pub codec: Codec,
+    pub messages: usize,
+    /// Including the format header
+    pub mean_size: f64,
+    pub mean_encode_ns: f64,
+    pub mean_decode_ns: f64,
+}
+
+#[derive(Error, Debug)]
+pub enum WireFormatError {
+    #[error("Unsupported wire format version {found}, this build supports versions {min} to {max}")]
+    UnsupportedVersion { found: u8, min: u8, max: u8 },
+    #[error("Unknown codec {0}")]
+    UnknownCodec(u8),
+    #[error("The message ended before its format header")]
+    MissingHeader,
+    #[error("Failed to read the message")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to encode the message")]
+    Encode(#[from] bincode::error::EncodeError),
+    #[error("Failed to decode a version {version} message")]
+    Decode {
+        version: u8,
+        #[source]
+        source: bincode::error::DecodeError,
+    },
+    #[error("Failed to encode or decode a json message")]
+    Json(#[from] serde_json::Error),
+    #[error("Failed to decode a protobuf message")]
+    ProtobufDecode(#[from] prost::DecodeError),
+    #[error("Invalid protobuf message: {0}")]
+    InvalidProtobuf(String),
+    #[error("This message has no compact layout")]
+    NoCompactLayout,
+    #[error("A compact message takes {expected} bytes, got {found}")]
+    CompactLength { expected: usize, found: usize },
+    #[error("Invalid {field} {tag} in a compact message")]
+    InvalidCompactTag { field: &'static str, tag: u8 },
+    #[error("The {kind:?} exceeds the limit of {limit} bytes")]
+    TooLarge { kind: MessageKind, limit: usize },
+}
+
+/// A message that can be encoded with any of the [Codec]s
+pub trait WireMessage: Serialize + DeserializeOwned + Sized {
+    type Proto: prost::Message + Default;
+
+    /// Picks the size limit the message is decoded with
+    const KIND: MessageKind;
+
+    fn to_proto(&self) -> Self::Proto;
+
+    fn from_proto(proto: Self::Proto) -> Result<Self, WireFormatError>;
+
+    /// The size of the [compact] layout of the message, if it has one
+    const COMPACT_SIZE: Option<usize> = None;
+
+    /// Whether this particular message fits its [compact] layout
+    fn compact_size(&self) -> Option<usize> {
+        Self::COMPACT_SIZE
+    }
+
+    /// Decode a message of the legacy encoding (version 0), for messages
+    /// whose layout changed since the original release
+    fn decode_legacy<R: Read>(r: R) -> Result<Self, WireFormatError> {
+        decode_bincode(0, r)
+    }
+
+    /// Encode into a buffer of exactly [Self::COMPACT_SIZE] bytes
+    fn encode_compact(&self, _buf: &mut [u8]) -> Result<(), WireFormatError> {
+        Err(WireFormatError::NoCompactLayout)
+    }
+
+    fn decode_compact(_buf: &[u8]) -> Result<Self, WireFormatError> {
+        Err(WireFormatError::NoCompactLayout)
+    }
+}
+
+impl Codec {
+    pub const ALL: [Codec; 4] = [Codec::Bincode, Codec::Protobuf, Codec::Json, Codec::Compact];
+
+    fn id(self) -> u8 {
+        match self {
+            Codec::Bincode => 0,
+            Codec::Protobuf => 1,
+            Codec::Json => 2,
+            Codec::Compact => 3,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self, WireFormatError> {
+        match id {
+            0 => Ok(Codec::Bincode),
+            1 => Ok(Codec::Protobuf),
+            2 => Ok(Codec::Json),
+            3 => Ok(Codec::Compact),
+            unknown => Err(WireFormatError::UnknownCodec(unknown)),
+        }
+    }
+}
+
+/// Pick the codec this process encodes with. Can only be done once, before
+/// anything is encoded, otherwise the codec that is already in use is returned
+pub fn set_codec(codec: Codec) -> Result<(), Codec> {
+    CODEC.set(codec)
+}
+
+pub fn codec() -> Codec {
+    CODEC.get().copied().unwrap_or_default()
+}
+
+/// Pick the size limits this process decodes with, like [set_codec]
+pub fn set_limits(limits: DecodeLimits) -> Result<(), DecodeLimits> {
+    LIMITS.set(limits)
+}
+
+pub fn limits() -> DecodeLimits {
+    LIMITS.get().copied().unwrap_or_default()
+}
+
+/// Write the format header followed by the message, with the codec of this process
+pub fn encode<T, W>(w: W, message: &T) -> Result<(), WireFormatError>
+    where T: WireMessage, W: Write {
+    encode_with(codec(), w, message)
+}
+
+pub fn encode_with<T, W>(codec: Codec, mut w: W, message: &T) -> Result<(), WireFormatError>
+    where T: WireMessage, W: Write {
+    let codec = match (codec, message.compact_size()) {
+        (Codec::Compact, None) => Codec::Bincode,
+        (codec, _) => codec,
+    };
+
+    w.write_all(&[FORMAT_MAGIC, CURRENT_VERSION, codec.id()])?;
+
+    match codec {
+        Codec::Bincode => {
+            bincode::serde::encode_into_std_write(message, &mut w, bincode::config::standard())?;
+        }
+        Codec::Protobuf => w.write_all(&prost::Message::encode_to_vec(&message.to_proto()))?,
+        Codec::Json => serde_json::to_writer(w, message)?,
+        Codec::Compact => {
+            let mut buf = [0u8; compact::MAX_COMPACT_SIZE];
+            let buf = &mut buf[..message.compact_size().unwrap_or_default()];
+
+            message.encode_compact(buf)?;
+
+            w.write_all(buf)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read a message written by [encode], with any codec, or by a build that predates the format header.
+///
+/// Messages larger than the limit of their kind are rejected with [WireFormatError::TooLarge]
+pub fn decode<T, R>(r: R) -> Result<T, WireFormatError>
+    where T: WireMessage, R: Read {
+    decode_limited(r, limits().max_size(T::KIND))
+}
+
+pub fn decode_limited<T, R>(r: R, limit: usize) -> Result<T, WireFormatError>
+    where T: WireMessage, R: Read {
+    let mut r = LimitedReader::new(r, limit);
+
+    match decode_unlimited(&mut r) {
+        Err(_) if r.exceeded() => Err(reject::<T>(limit)),
+        Err(WireFormatError::Decode { source: bincode::error::DecodeError::LimitExceeded, .. }) => Err(reject::<T>(limit)),
+        result => result,
+    }
+}
+
+fn decode_unlimited<T, R>(mut r: R) -> Result<T, WireFormatError>
+    where T: WireMessage, R: Read {
+    let mut first = [0u8; 1];
+
+    if r.read(&mut first)? == 0 {
+        return Err(WireFormatError::MissingHeader);
+    }
+
+    if first[0] != FORMAT_MAGIC {
+        // A legacy message, so the byte we peeked at is already part of its body
+        return T::decode_legacy(Cursor::new(first).chain(r));
+    }
+
+    let mut version = [0u8; 1];
+
+    r.read_exact(&mut version).map_err(|_| WireFormatError::MissingHeader)?;
+
+    match version[0] {
+        CURRENT_VERSION => {
+            let mut codec = [0u8; 1];
+
+            r.read_exact(&mut codec).map_err(|_| WireFormatError::MissingHeader)?;
+
+            match Codec::from_id(codec[0])? {
+                Codec::Bincode => decode_bincode(CURRENT_VERSION, r),
+                Codec::Protobuf => {
+                    let mut body = Vec::new();
+
+                    r.read_to_end(&mut body)?;
+
+                    T::from_proto(prost::Message::decode(body.as_slice())?)
+                }
+                Codec::Json => Ok(serde_json::from_reader(r)?),
+                Codec::Compact => {
+                    let size = T::COMPACT_SIZE.ok_or(WireFormatError::NoCompactLayout)?;
+
+                    let mut buf = [0u8; compact::MAX_COMPACT_SIZE];
+
+                    r.read_exact(&mut buf[..size])?;
+
+                    T::decode_compact(&buf[..size])
+                }
+            }
+        }
+        found => Err(WireFormatError::UnsupportedVersion {
+            found,
+            min: MIN_SUPPORTED_VERSION,
+            max: CURRENT_VERSION,
+        }),
+    }
+}
+
+/// Read the whole message into a buffer, then decode it with [decode_from_slice].
+///
+/// Atlas' `ApplicationData` hands each request and reply over as a reader holding only
+/// that message, not as a slice, so this is how the replica gets to the slice decoder.
+/// Messages up to [STACK_BUFFER_SIZE] bytes are read into a buffer on the stack,
+/// larger ones are rejected after reading one byte past the limit of their kind
+pub fn decode_buffered<T, R>(mut r: R) -> Result<T, WireFormatError>
+    where T: WireMessage, R: Read {
+    let limit = limits().max_size(T::KIND);
+
+    let mut stack = [0u8; STACK_BUFFER_SIZE];
+    let mut read = 0;
+
+    while read < stack.len() {
+        match r.read(&mut stack[read..])? {
+            0 => return decode_from_slice_limited(&stack[..read], limit),
+            n => read += n,
+        }
+    }
+
+    let mut buf = stack.to_vec();
+
+    r.take((limit + 1).saturating_sub(read) as u64).read_to_end(&mut buf)?;
+
+    decode_from_slice_limited(&buf, limit)
+}
+
+/// Decode a message that was already received into a buffer.
+///
+/// Unlike [decode], this reads the body in place, so the [Codec::Compact] and
+/// [Codec::Bincode] paths do not allocate beyond what the message itself needs
+pub fn decode_from_slice<T>(buf: &[u8]) -> Result<T, WireFormatError>
+    where T: WireMessage {
+    decode_from_slice_limited(buf, limits().max_size(T::KIND))
+}
+
+pub fn decode_from_slice_limited<T>(buf: &[u8], limit: usize) -> Result<T, WireFormatError>
+    where T: WireMessage {
+    if buf.len() > limit {
+        return Err(reject::<T>(limit));
+    }
+
+    match decode_slice_unlimited(buf) {
+        Err(WireFormatError::Decode { source: bincode::error::DecodeError::LimitExceeded, .. }) => Err(reject::<T>(limit)),
+        result => result,
+    }
+}
+
+fn decode_slice_unlimited<T>(buf: &[u8]) -> Result<T, WireFormatError>
+    where T: WireMessage {
+    match buf {
+        [] | [FORMAT_MAGIC] | [FORMAT_MAGIC, CURRENT_VERSION] => Err(WireFormatError::MissingHeader),
+        [FORMAT_MAGIC, CURRENT_VERSION, codec, body @ ..] => match Codec::from_id(*codec)? {
+            Codec::Bincode => decode_bincode_slice(CURRENT_VERSION, body),
+            Codec::Protobuf => T::from_proto(prost::Message::decode(body)?),
+            Codec::Json => Ok(serde_json::from_slice(body)?),
+            Codec::Compact => T::decode_compact(body),
+        },
+        [FORMAT_MAGIC, found, ..] => Err(WireFormatError::UnsupportedVersion {
+            found: *found,
+            min: MIN_SUPPORTED_VERSION,
+            max: CURRENT_VERSION,
+        }),
+        legacy => T::decode_legacy(legacy),
+    }
+}
+
+/// Encode and decode every message with the given codec
+pub fn measure<T>(codec: Codec, messages: &[T]) -> Result<CodecStats, WireFormatError>
+    where T: WireMessage {
+    let mut encoded = Vec::with_capacity(messages.len());
+
+    let started = Instant::now();
+
+    for message in messages {
+        let mut buf = Vec::new();
+
+        encode_with(codec, &mut buf, message)?;
+
+        encoded.push(buf);
+    }
+
+    let encoding = started.elapsed();
+
+    let started = Instant::now();
+
+    for buf in &encoded {
+        decode_from_slice::<T>(buf)?;
+    }
+
+    let decoding = started.elapsed();
+
+    let count = messages.len().max(1) as f64;
+
+    Ok(CodecStats {
+        kind: T::KIND,
+        codec,
+        messages: messages.len(),
+        mean_size: encoded.iter().map(Vec::len).sum::<usize>() as f64 / count,
+        mean_encode_ns: encoding.as_nanos() as f64 / count,
+        mean_decode_ns: decoding.as_nanos() as f64 / count,
+    })
+}
+
+fn reject<T: WireMessage>(limit: usize) -> WireFormatError {
+    let rejected = limits::record_rejection(T::KIND);
+
+    warn!("Rejected a {:?} larger than {} bytes, {} rejected so far", T::KIND, limit, rejected);
+
+    WireFormatError::TooLarge { kind: T::KIND, limit }
+}
+
+/// Through serde, bincode hands the length prefix of a container to serde as a mere size hint.
+/// Serde never preallocates more than 1 MiB from a hint (and maps, which our messages hold,
+/// not at all), and every element has to be decoded from bytes that are actually there, so a
+/// length prefix claiming a huge container fails once the input runs out, without allocating
+/// anywhere near [MAX_DECODE_SIZE]
+pub(crate) fn decode_bincode<T, R>(version: u8, mut r: R) -> Result<T, WireFormatError>
+    where T: DeserializeOwned, R: Read {
+    bincode::serde::decode_from_std_read(&mut r, bincode::config::standard().with_limit::<MAX_DECODE_SIZE>())
+        .map_err(|source| WireFormatError::Decode { version, source })
+}
+
+fn decode_bincode_slice<T>(version: u8, body: &[u8]) -> Result<T, WireFormatError>
+    where T: DeserializeOwned {
+    bincode::serde::decode_from_slice(body, bincode::config::standard().with_limit::<MAX_DECODE_SIZE>())
+        .map(|(message, _)| message)
+        .map_err(|source| WireFormatError::Decode { version, source })
+}
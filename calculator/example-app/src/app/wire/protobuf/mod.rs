@@ -0,0 +1,390 @@
+//! Protobuf messages for the [Codec::Protobuf](super::Codec::Protobuf) codec. They are
+//! derived by hand, so that building the replica does not need protoc
+
+use atlas_common::crypto::hash::Digest;
+use atlas_common::ordering::SeqNo;
+use crate::app::messages::{Operation, OperationError, ReadConsistency, Reply, Request};
+use crate::app::registers::{RegisterReply, RegisterRequest};
+use crate::app::wire::WireFormatError;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RequestProto {
+    #[prost(enumeration = "OperationKind", tag = "1")]
+    pub operation: i32,
+    #[prost(int32, tag = "2")]
+    pub value: i32,
+    #[prost(uint64, optional, tag = "3")]
+    pub id: Option<u64>,
+    /// Only for [OperationKind::Get]
+    #[prost(enumeration = "ConsistencyKind", tag = "4")]
+    pub consistency: i32,
+    /// Only for [ConsistencyKind::ReadYourWrites]
+    #[prost(uint32, tag = "5")]
+    pub after: u32,
+    /// Only for [OperationKind::CompareAndSwap]
+    #[prost(int32, tag = "6")]
+    pub expected: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ReplyProto {
+    #[prost(oneof = "ReplyResult", tags = "1, 2")]
+    pub result: Option<ReplyResult>,
+    #[prost(uint32, tag = "3")]
+    pub seq_no: u32,
+    #[prost(bytes = "vec", optional, tag = "4")]
+    pub digest: Option<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum ReplyResult {
+    #[prost(int32, tag = "1")]
+    Value(i32),
+    #[prost(message, tag = "2")]
+    Error(ErrorProto),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ErrorProto {
+    #[prost(enumeration = "ErrorKind", tag = "1")]
+    pub kind: i32,
+    /// The negative exponent, or the current value of a failed compare and swap
+    #[prost(int32, tag = "2")]
+    pub value: i32,
+    #[prost(uint64, tag = "3")]
+    pub request: u64,
+    #[prost(uint32, tag = "4")]
+    pub executed: u32,
+    #[prost(uint32, tag = "5")]
+    pub required: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StateProto {
+    #[prost(int32, tag = "1")]
+    pub value: i32,
+    #[prost(uint32, tag = "2")]
+    pub executed: u32,
+    #[prost(message, repeated, tag = "3")]
+    pub sessions: Vec<SessionProto>,
+    #[prost(uint32, tag = "4")]
+    pub sessions_opened: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SessionProto {
+    #[prost(uint32, tag = "1")]
+    pub client: u32,
+    #[prost(message, repeated, tag = "2")]
+    pub replies: Vec<RecordedReplyProto>,
+    #[prost(uint64, optional, tag = "3")]
+    pub evicted: Option<u64>,
+    #[prost(uint32, tag = "4")]
+    pub last_executed: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RecordedReplyProto {
+    #[prost(uint64, tag = "1")]
+    pub request: u64,
+    #[prost(message, optional, tag = "2")]
+    pub reply: Option<ReplyProto>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RegisterRequestProto {
+    #[prost(uint32, tag = "1")]
+    pub register: u32,
+    #[prost(message, optional, tag = "2")]
+    pub request: Option<RequestProto>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RegisterReplyProto {
+    #[prost(oneof = "RegisterReplyResult", tags = "1, 2, 3, 4, 5")]
+    pub result: Option<RegisterReplyResult>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum RegisterReplyResult {
+    #[prost(int32, tag = "1")]
+    Value(i32),
+    #[prost(message, tag = "2")]
+    Error(ErrorProto),
+    #[prost(uint32, tag = "3")]
+    UnknownRegister(u32),
+    #[prost(bytes = "vec", tag = "4")]
+    Digest(Vec<u8>),
+    #[prost(bool, tag = "5")]
+    Unsupported(bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum OperationKind {
+    Add = 0,
+    Sub = 1,
+    Mult = 2,
+    Divide = 3,
+    Remainder = 4,
+    Exponent = 5,
+    Get = 6,
+    CompareAndSwap = 7,
+    Digest = 8,
+    Tolerance = 9,
+    OpenSession = 10,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum ConsistencyKind {
+    Any = 0,
+    Bft = 1,
+    Linearizable = 2,
+    ReadYourWrites = 3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum ErrorKind {
+    DivisionByZero = 0,
+    NegativeExponent = 1,
+    Overflow = 2,
+    CompareAndSwapFailed = 3,
+    ExpiredRequest = 4,
+    NotExecutedYet = 5,
+}
+
+impl From<&Request> for RequestProto {
+    fn from(request: &Request) -> Self {
+        let mut proto = RequestProto {
+            value: request.value(),
+            id: request.id(),
+            ..RequestProto::default()
+        };
+
+        let kind = match request.operation() {
+            Operation::Add => OperationKind::Add,
+            Operation::Sub => OperationKind::Sub,
+            Operation::Mult => OperationKind::Mult,
+            Operation::Divide => OperationKind::Divide,
+            Operation::Remainder => OperationKind::Remainder,
+            Operation::Exponent => OperationKind::Exponent,
+            Operation::Get { consistency } => {
+                let consistency = match consistency {
+                    ReadConsistency::Any => ConsistencyKind::Any,
+                    ReadConsistency::BFT => ConsistencyKind::Bft,
+                    ReadConsistency::Linearizable => ConsistencyKind::Linearizable,
+                    ReadConsistency::ReadYourWrites { after } => {
+                        proto.after = u32::from(*after);
+
+                        ConsistencyKind::ReadYourWrites
+                    }
+                };
+
+                proto.consistency = consistency as i32;
+
+                OperationKind::Get
+            }
+            Operation::CompareAndSwap { expected } => {
+                proto.expected = *expected;
+
+                OperationKind::CompareAndSwap
+            }
+            Operation::Digest => OperationKind::Digest,
+            Operation::Tolerance => OperationKind::Tolerance,
+            Operation::OpenSession => OperationKind::OpenSession,
+        };
+
+        proto.operation = kind as i32;
+
+        proto
+    }
+}
+
+impl TryFrom<RequestProto> for Request {
+    type Error = WireFormatError;
+
+    fn try_from(proto: RequestProto) -> Result<Self, Self::Error> {
+        let kind = OperationKind::try_from(proto.operation)
+            .map_err(|_| WireFormatError::InvalidProtobuf(format!("Unknown operation {}", proto.operation)))?;
+
+        let operation = match kind {
+            OperationKind::Add => Operation::Add,
+            OperationKind::Sub => Operation::Sub,
+            OperationKind::Mult => Operation::Mult,
+            OperationKind::Divide => Operation::Divide,
+            OperationKind::Remainder => Operation::Remainder,
+            OperationKind::Exponent => Operation::Exponent,
+            OperationKind::Get => {
+                let consistency = ConsistencyKind::try_from(proto.consistency)
+                    .map_err(|_| WireFormatError::InvalidProtobuf(format!("Unknown read consistency {}", proto.consistency)))?;
+
+                let consistency = match consistency {
+                    ConsistencyKind::Any => ReadConsistency::Any,
+                    ConsistencyKind::Bft => ReadConsistency::BFT,
+                    ConsistencyKind::Linearizable => ReadConsistency::Linearizable,
+                    ConsistencyKind::ReadYourWrites => ReadConsistency::ReadYourWrites { after: SeqNo::from(proto.after) },
+                };
+
+                Operation::Get { consistency }
+            }
+            OperationKind::CompareAndSwap => Operation::CompareAndSwap { expected: proto.expected },
+            OperationKind::Digest => Operation::Digest,
+            OperationKind::Tolerance => Operation::Tolerance,
+            OperationKind::OpenSession => Operation::OpenSession,
+        };
+
+        let request = Request::new(operation, proto.value);
+
+        Ok(match proto.id {
+            Some(id) => request.with_id(id),
+            None => request,
+        })
+    }
+}
+
+impl From<&Reply> for ReplyProto {
+    fn from(reply: &Reply) -> Self {
+        let result = match reply.result() {
+            Ok(value) => ReplyResult::Value(*value),
+            Err(error) => ReplyResult::Error(error_to_proto(error)),
+        };
+
+        ReplyProto {
+            result: Some(result),
+            seq_no: u32::from(reply.seq_no()),
+            digest: reply.digest().map(|digest| digest.as_ref().to_vec()),
+        }
+    }
+}
+
+impl TryFrom<ReplyProto> for Reply {
+    type Error = WireFormatError;
+
+    fn try_from(proto: ReplyProto) -> Result<Self, Self::Error> {
+        let result = match proto.result {
+            Some(ReplyResult::Value(value)) => Ok(value),
+            Some(ReplyResult::Error(error)) => Err(error_from_proto(error)?),
+            None => return Err(WireFormatError::InvalidProtobuf("Reply without a result".to_string())),
+        };
+
+        let reply = Reply::new(result, SeqNo::from(proto.seq_no));
+
+        match proto.digest {
+            Some(digest) => Ok(reply.with_digest(digest_from_proto(&digest)?)),
+            None => Ok(reply),
+        }
+    }
+}
+
+impl From<&RegisterRequest> for RegisterRequestProto {
+    fn from(request: &RegisterRequest) -> Self {
+        RegisterRequestProto {
+            register: request.register(),
+            request: Some(request.request().into()),
+        }
+    }
+}
+
+impl TryFrom<RegisterRequestProto> for RegisterRequest {
+    type Error = WireFormatError;
+
+    fn try_from(proto: RegisterRequestProto) -> Result<Self, Self::Error> {
+        let request = proto.request
+            .ok_or_else(|| WireFormatError::InvalidProtobuf("Register request without a request".to_string()))?;
+
+        Ok(RegisterRequest::new(proto.register, request.try_into()?))
+    }
+}
+
+impl From<&RegisterReply> for RegisterReplyProto {
+    fn from(reply: &RegisterReply) -> Self {
+        let result = match reply {
+            RegisterReply::Value(value) => RegisterReplyResult::Value(*value),
+            RegisterReply::Error(error) => RegisterReplyResult::Error(error_to_proto(error)),
+            RegisterReply::UnknownRegister(register) => RegisterReplyResult::UnknownRegister(*register),
+            RegisterReply::Digest(digest) => RegisterReplyResult::Digest(digest.as_ref().to_vec()),
+            RegisterReply::Unsupported => RegisterReplyResult::Unsupported(true),
+        };
+
+        RegisterReplyProto {
+            result: Some(result),
+        }
+    }
+}
+
+impl TryFrom<RegisterReplyProto> for RegisterReply {
+    type Error = WireFormatError;
+
+    fn try_from(proto: RegisterReplyProto) -> Result<Self, WireFormatError> {
+        Ok(match proto.result {
+            Some(RegisterReplyResult::Value(value)) => RegisterReply::Value(value),
+            Some(RegisterReplyResult::Error(error)) => RegisterReply::Error(error_from_proto(error)?),
+            Some(RegisterReplyResult::UnknownRegister(register)) => RegisterReply::UnknownRegister(register),
+            Some(RegisterReplyResult::Digest(digest)) => RegisterReply::Digest(digest_from_proto(&digest)?),
+            Some(RegisterReplyResult::Unsupported(_)) => RegisterReply::Unsupported,
+            None => return Err(WireFormatError::InvalidProtobuf("Register reply without a result".to_string())),
+        })
+    }
+}
+
+fn digest_from_proto(digest: &[u8]) -> Result<Digest, WireFormatError> {
+    if digest.len() != Digest::LENGTH {
+        return Err(WireFormatError::InvalidProtobuf(format!("A digest takes {} bytes, got {}", Digest::LENGTH, digest.len())));
+    }
+
+    Digest::from_bytes(digest)
+        .map_err(|err| WireFormatError::InvalidProtobuf(format!("Invalid digest: {}", err)))
+}
+
+fn error_to_proto(error: &OperationError) -> ErrorProto {
+    let mut proto = ErrorProto::default();
+
+    let kind = match error {
+        OperationError::DivisionByZero => ErrorKind::DivisionByZero,
+        OperationError::NegativeExponent(exponent) => {
+            proto.value = *exponent;
+
+            ErrorKind::NegativeExponent
+        }
+        OperationError::Overflow => ErrorKind::Overflow,
+        OperationError::CompareAndSwapFailed { current } => {
+            proto.value = *current;
+
+            ErrorKind::CompareAndSwapFailed
+        }
+        OperationError::ExpiredRequest(request) => {
+            proto.request = *request;
+
+            ErrorKind::ExpiredRequest
+        }
+        OperationError::NotExecutedYet { executed, required } => {
+            proto.executed = u32::from(*executed);
+            proto.required = u32::from(*required);
+
+            ErrorKind::NotExecutedYet
+        }
+    };
+
+    proto.kind = kind as i32;
+
+    proto
+}
+
+fn error_from_proto(proto: ErrorProto) -> Result<OperationError, WireFormatError> {
+    let kind = ErrorKind::try_from(proto.kind)
+        .map_err(|_| WireFormatError::InvalidProtobuf(format!("Unknown operation error {}", proto.kind)))?;
+
+    Ok(match kind {
+        ErrorKind::DivisionByZero => OperationError::DivisionByZero,
+        ErrorKind::NegativeExponent => OperationError::NegativeExponent(proto.value),
+        ErrorKind::Overflow => OperationError::Overflow,
+        ErrorKind::CompareAndSwapFailed => OperationError::CompareAndSwapFailed { current: proto.value },
+        ErrorKind::ExpiredRequest => OperationError::ExpiredRequest(proto.request),
+        ErrorKind::NotExecutedYet => OperationError::NotExecutedYet {
+            executed: SeqNo::from(proto.executed),
+            required: SeqNo::from(proto.required),
+        },
+    })
+}
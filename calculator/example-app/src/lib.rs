@@ -1,2 +1,7 @@
 pub mod app;
-pub mod state;
\ No newline at end of file
+pub mod logging;
+pub mod settings;
+pub mod snapshot;
+pub mod state;
+pub mod tolerance;
+pub mod trace;
\ No newline at end of file
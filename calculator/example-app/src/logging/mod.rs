@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::str::FromStr;
+use anyhow::{anyhow, Context};
+use log::{LevelFilter, Record};
+use log4rs::append::console::{ConsoleAppender, Target};
+use log4rs::append::rolling_file::policy::compound::CompoundPolicy;
+use log4rs::append::rolling_file::policy::compound::roll::fixed_window::FixedWindowRoller;
+use log4rs::append::rolling_file::policy::compound::trigger::size::SizeTrigger;
+use log4rs::append::rolling_file::RollingFileAppender;
+use log4rs::config::{Appender, Logger, Root};
+use log4rs::encode::Encode;
+use log4rs::encode::pattern::PatternEncoder;
+use log4rs::Handle;
+use serde::{Deserialize, Serialize};
+use atlas_common::node_id::NodeId;
+
+const CONSOLE_APPENDER: &str = "console";
+const FILE_APPENDER: &str = "file";
+
+/// The role of the node that is producing the logs.
+/// Attached to every log line so merged logs of a cluster can be told apart
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeRole {
+    Replica,
+    Client,
+    /// The operator tools, which are not part of the cluster
+    Tool,
+}
+
+/// The format in which each log line is written
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LoggingConfig {
+    /// The level applied to every module without an explicit entry in [LoggingConfig::modules]
+    #[serde(default = "default_level")]
+    pub level: LevelFilter,
+    /// Per module levels, indexed by the module path (ex: `atlas_smr_replica = "debug"`)
+    #[serde(default)]
+    pub modules: BTreeMap<String, LevelFilter>,
+    #[serde(default)]
+    pub format: LogFormat,
+    /// When present, logs are also written to rotating files
+    #[serde(default)]
+    pub file: Option<FileLoggingConfig>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FileLoggingConfig {
+    /// The folder (relative to the node's directory) in which to place the log files
+    #[serde(default = "default_log_directory")]
+    pub directory: PathBuf,
+    /// The size, in bytes, at which the current log file is rotated
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// How many rotated files to keep around
+    #[serde(default = "default_max_files")]
+    pub max_files: u32,
+}
+
+/// Initialize the global logger, according to the given configuration.
+///
+/// Every line is tagged with the id and role of this node. The console logs go to stderr,
+/// leaving stdout to the output of the tools and reports.
+/// The returned handle must be kept alive for as long as logging is required
+pub fn init_logging(node_id: NodeId, role: NodeRole, config: &LoggingConfig) -> atlas_common::error::Result<Handle> {
+    let mut builder = log4rs::Config::builder();
+    let mut root = Root::builder().appender(CONSOLE_APPENDER);
+
+    let console = ConsoleAppender::builder()
+        .target(Target::Stderr)
+        .encoder(encoder_for(node_id, role, config.format))
+        .build();
+
+    builder = builder.appender(Appender::builder().build(CONSOLE_APPENDER, Box::new(console)));
+
+    if let Some(file_config) = &config.file {
+        let file_name = format!("{}-{}", role, node_id.0);
+
+        let log_path = file_config.directory.join(format!("{}.log", file_name));
+
+        let roll_pattern = file_config.directory.join(format!("{}.{{}}.log", file_name));
+
+        let roll_pattern = roll_pattern.to_str()
+            .ok_or_else(|| anyhow!("Log directory {:?} is not valid UTF-8", file_config.directory))?;
+
+        let roller = FixedWindowRoller::builder()
+            .build(roll_pattern, file_config.max_files)
+            .context("Failed to build log file roller")?;
+
+        let policy = CompoundPolicy::new(Box::new(SizeTrigger::new(file_config.max_file_size)), Box::new(roller));
+
+        let file = RollingFileAppender::builder()
+            .encoder(encoder_for(node_id, role, config.format))
+            .build(&log_path, Box::new(policy))
+            .with_context(|| format!("Failed to open log file {:?}", log_path))?;
+
+        builder = builder.appender(Appender::builder().build(FILE_APPENDER, Box::new(file)));
+        root = root.appender(FILE_APPENDER);
+    }
+
+    for (module, level) in &config.modules {
+        builder = builder.logger(Logger::builder().build(module, *level));
+    }
+
+    let log_config = builder.build(root.build(config.level))
+        .context("Invalid logging configuration")?;
+
+    log4rs::init_config(log_config).context("Failed to install logger")
+}
+
+fn encoder_for(node_id: NodeId, role: NodeRole, format: LogFormat) -> Box<dyn Encode> {
+    match format {
+        LogFormat::Text => {
+            Box::new(PatternEncoder::new(&format!("{{d(%Y-%m-%d %H:%M:%S%.3f)}} {{l:<5}} [{} {}] [{{T}}] {{t}} - {{m}}{{n}}", role, node_id.0)))
+        }
+        LogFormat::Json => {
+            Box::new(JsonLineEncoder { node_id, role })
+        }
+    }
+}
+
+/// Encodes each record as a single line JSON object, carrying the node's id and role
+#[derive(Debug)]
+struct JsonLineEncoder {
+    node_id: NodeId,
+    role: NodeRole,
+}
+
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    time: String,
+    level: &'a str,
+    node_id: u32,
+    role: NodeRole,
+    thread: Option<&'a str>,
+    target: &'a str,
+    message: String,
+}
+
+impl Encode for JsonLineEncoder {
+    fn encode(&self, w: &mut dyn log4rs::encode::Write, record: &Record) -> anyhow::Result<()> {
+        let thread = std::thread::current();
+
+        let line = JsonLine {
+            time: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            level: record.level().as_str(),
+            node_id: self.node_id.0,
+            role: self.role,
+            thread: thread.name(),
+            target: record.target(),
+            message: record.args().to_string(),
+        };
+
+        serde_json::to_writer(&mut *w, &line)?;
+        w.write_all(b"\n")?;
+
+        Ok(())
+    }
+}
+
+impl Display for NodeRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeRole::Replica => write!(f, "replica"),
+            NodeRole::Client => write!(f, "client"),
+            NodeRole::Tool => write!(f, "tool"),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(anyhow!("Unknown log format {}, expected one of text, json", s)),
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_level(),
+            modules: Default::default(),
+            format: Default::default(),
+            file: None,
+        }
+    }
+}
+
+impl Default for FileLoggingConfig {
+    fn default() -> Self {
+        Self {
+            directory: default_log_directory(),
+            max_file_size: default_max_file_size(),
+            max_files: default_max_files(),
+        }
+    }
+}
+
+fn default_level() -> LevelFilter {
+    LevelFilter::Info
+}
+
+fn default_log_directory() -> PathBuf {
+    PathBuf::from("logs")
+}
+
+fn default_max_file_size() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_max_files() -> u32 {
+    5
+}
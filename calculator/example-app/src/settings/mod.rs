@@ -0,0 +1,85 @@
+use config::{Config, Source};
+use atlas_common::error::*;
+use crate::app::admin::AdminConfig;
+use crate::app::subscriptions::WatchConfig;
+use crate::app::wire::CodecConfig;
+use crate::logging::LoggingConfig;
+use crate::tolerance::{DeploymentConfig, NodesConfig};
+use crate::trace::TracingConfig;
+
+pub fn parse_logging_conf<T>(source: T) -> Result<LoggingConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let logging_config: LoggingConfig = settings.try_deserialize()?;
+
+    Ok(logging_config)
+}
+
+pub fn parse_tracing_conf<T>(source: T) -> Result<TracingConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let tracing_config: TracingConfig = settings.try_deserialize()?;
+
+    Ok(tracing_config)
+}
+
+pub fn parse_nodes_conf<T>(source: T) -> Result<NodesConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let nodes_config: NodesConfig = settings.try_deserialize()?;
+
+    Ok(nodes_config)
+}
+
+pub fn parse_deployment_conf<T>(source: T) -> Result<DeploymentConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let deployment_config: DeploymentConfig = settings.try_deserialize()?;
+
+    Ok(deployment_config)
+}
+
+pub fn parse_watch_conf<T>(source: T) -> Result<WatchConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let watch_config: WatchConfig = settings.try_deserialize()?;
+
+    Ok(watch_config)
+}
+
+pub fn parse_codec_conf<T>(source: T) -> Result<CodecConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let codec_config: CodecConfig = settings.try_deserialize()?;
+
+    Ok(codec_config)
+}
+
+pub fn parse_admin_conf<T>(source: T) -> Result<AdminConfig>
+    where T: Source + Send + Sync + 'static {
+    let settings = Config::builder()
+        .add_source(source)
+        .build()?;
+
+    let admin_config: AdminConfig = settings.try_deserialize()?;
+
+    Ok(admin_config)
+}
@@ -0,0 +1,188 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use anyhow::Context;
+use thiserror::Error;
+use atlas_common::crypto::hash::{Context as DigestContext, Digest};
+use atlas_common::ordering::SeqNo;
+use crate::app::wire;
+use crate::app::wire::limits::MAX_DECODE_SIZE;
+use crate::app::wire::WireFormatError;
+use crate::state::{digest_hex, CalculatorState};
+
+/// Starts every snapshot file
+const SNAPSHOT_MAGIC: &[u8; 8] = b"CALCSNAP";
+
+const SNAPSHOT_VERSION: u8 = 1;
+
+/// The magic, the version, the sequence number and the length of the state
+const HEADER_SIZE: usize = SNAPSHOT_MAGIC.len() + 1 + 4 + 8;
+
+/// Fed first into the checksum, so it can never match a digest of something else
+const SNAPSHOT_DIGEST_DOMAIN: &[u8] = b"atlas-examples/calculator-snapshot/v1";
+
+/// The state of a replica after executing a sequence number, which can be
+/// carried to another cluster in a file.
+///
+/// The file holds, in order:
+///
+/// | Bytes            | Contents                                               |
+/// |------------------|--------------------------------------------------------|
+/// | 8                | `CALCSNAP`                                             |
+/// | 1                | The version of the layout                              |
+/// | 4                | The sequence number, little endian                     |
+/// | 8                | The length of the state, little endian                 |
+/// | length           | The state, in the wire format                          |
+/// | [Digest::LENGTH] | The checksum of everything before it                   |
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub seq_no: SeqNo,
+    pub state: CalculatorState,
+}
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("Not a snapshot file")]
+    NotASnapshot,
+    #[error("Unsupported snapshot version {found}, this build reads version {expected}")]
+    UnsupportedVersion { found: u8, expected: u8 },
+    #[error("The snapshot holds a state of {size} bytes, more than the {limit} we decode")]
+    TooLarge { size: u64, limit: usize },
+    #[error("The snapshot holds a state of {size} bytes, which does not fit a file of {file_size} bytes")]
+    SizeMismatch { size: u64, file_size: u64 },
+    #[error("The snapshot is corrupted, its checksum is {found} but its contents digest to {computed}")]
+    ChecksumMismatch { found: String, computed: String },
+    #[error("Failed to read or write the snapshot")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to encode or decode the state of the snapshot")]
+    Wire(#[from] WireFormatError),
+}
+
+impl Snapshot {
+    pub fn new(seq_no: SeqNo, state: CalculatorState) -> Self {
+        Self { seq_no, state }
+    }
+
+    pub fn write<W>(&self, mut w: W) -> Result<(), SnapshotError> where W: Write {
+        let mut state = Vec::new();
+
+        wire::encode_with(wire::STATE_CODEC, &mut state, &self.state)?;
+
+        let mut contents = Vec::with_capacity(HEADER_SIZE + state.len() + Digest::LENGTH);
+
+        contents.extend_from_slice(SNAPSHOT_MAGIC);
+        contents.push(SNAPSHOT_VERSION);
+        contents.extend_from_slice(&u32::from(self.seq_no).to_le_bytes());
+        contents.extend_from_slice(&(state.len() as u64).to_le_bytes());
+        contents.extend_from_slice(&state);
+
+        w.write_all(&contents)?;
+        w.write_all(checksum(&contents).as_ref())?;
+        w.flush()?;
+
+        Ok(())
+    }
+
+    /// Read a snapshot, checking its checksum before decoding the state
+    pub fn read<R>(r: R) -> Result<Self, SnapshotError> where R: Read {
+        Self::read_sized(r, None)
+    }
+
+    /// Read a snapshot that should take `file_size` bytes, if known, rejecting a header
+    /// that claims otherwise before reading the state
+    fn read_sized<R>(mut r: R, file_size: Option<u64>) -> Result<Self, SnapshotError> where R: Read {
+        let mut header = [0u8; HEADER_SIZE];
+
+        r.read_exact(&mut header).map_err(|_| SnapshotError::NotASnapshot)?;
+
+        if &header[..SNAPSHOT_MAGIC.len()] != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+
+        let (version, rest) = (header[SNAPSHOT_MAGIC.len()], &header[SNAPSHOT_MAGIC.len() + 1..]);
+
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion { found: version, expected: SNAPSHOT_VERSION });
+        }
+
+        let seq_no = u32::from_le_bytes(rest[..4].try_into().unwrap());
+        let size = u64::from_le_bytes(rest[4..].try_into().unwrap());
+
+        if size > MAX_DECODE_SIZE as u64 {
+            return Err(SnapshotError::TooLarge { size, limit: MAX_DECODE_SIZE });
+        }
+
+        match file_size {
+            Some(file_size) if file_size != (HEADER_SIZE + Digest::LENGTH) as u64 + size => {
+                return Err(SnapshotError::SizeMismatch { size, file_size });
+            }
+            _ => {}
+        }
+
+        let mut contents = header.to_vec();
+
+        // Grows with what is actually read, instead of trusting the length of a snapshot
+        // coming from a stream whose size we do not know
+        (&mut r).take(size).read_to_end(&mut contents)?;
+
+        if contents.len() != HEADER_SIZE + size as usize {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let mut found = [0u8; Digest::LENGTH];
+
+        r.read_exact(&mut found)?;
+
+        let computed = checksum(&contents);
+
+        if found.as_slice() != computed.as_ref() {
+            return Err(SnapshotError::ChecksumMismatch {
+                found: found.iter().map(|byte| format!("{:02x}", byte)).collect(),
+                computed: digest_hex(&computed),
+            });
+        }
+
+        let state = wire::decode_from_slice_limited(&contents[HEADER_SIZE..], MAX_DECODE_SIZE)?;
+
+        Ok(Self { seq_no: SeqNo::from(seq_no), state })
+    }
+
+    /// Write the snapshot to a file, replacing it only once the whole snapshot was written
+    pub fn save(&self, path: &Path) -> atlas_common::error::Result<()> {
+        let partial = path.with_extension("partial");
+
+        let mut file = fs::File::create(&partial)
+            .with_context(|| format!("Failed to create {}", partial.display()))?;
+
+        self.write(&mut file)
+            .with_context(|| format!("Failed to write the snapshot to {}", partial.display()))?;
+
+        // Otherwise a crash after the rename could leave a snapshot that was never written out
+        file.sync_all()
+            .with_context(|| format!("Failed to sync the snapshot to {}", partial.display()))?;
+
+        fs::rename(&partial, path)
+            .with_context(|| format!("Failed to move the snapshot to {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> atlas_common::error::Result<Self> {
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to open the snapshot {}", path.display()))?;
+
+        let file_size = file.metadata()
+            .with_context(|| format!("Failed to read the size of the snapshot {}", path.display()))?
+            .len();
+
+        Self::read_sized(std::io::BufReader::new(file), Some(file_size))
+            .with_context(|| format!("Failed to read the snapshot {}", path.display()))
+    }
+}
+
+fn checksum(contents: &[u8]) -> Digest {
+    let mut context = DigestContext::new();
+
+    context.update(SNAPSHOT_DIGEST_DOMAIN);
+    context.update(contents);
+
+    context.finish()
+}
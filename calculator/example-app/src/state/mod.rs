@@ -1,17 +1,88 @@
+use std::collections::BTreeMap;
 use std::io::{Read, Write};
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
+use atlas_common::crypto::hash::{Context as DigestContext, Digest};
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
 use atlas_smr_application::state::monolithic_state::MonolithicState;
+use crate::app::messages::Reply;
+use crate::app::wire;
+use crate::app::wire::compact;
+use crate::app::wire::limits::{MessageKind, DEFAULT_MAX_STATE_SIZE};
+use crate::app::wire::protobuf::{RecordedReplyProto, SessionProto, StateProto};
+use crate::app::wire::{WireFormatError, WireMessage};
 
-#[derive(Serialize, Deserialize, Clone)]
+pub mod registers;
+
+/// How many replies we keep for each client, to answer resubmitted requests.
+///
+/// Sized so that [MAX_SESSIONS] sessions with a full window still fit the default state limit,
+/// as replicas that cannot decode the state cannot recover through state transfer either
+pub const DEDUPLICATION_WINDOW: usize = DEFAULT_MAX_STATE_SIZE / MAX_SESSIONS / MAX_RECORDED_REPLY_SIZE;
+
+/// How many client sessions we keep. Opening one more evicts the session
+/// which executed a request the longest ago
+pub const MAX_SESSIONS: usize = 4096;
+
+/// The most a recorded reply takes in the encoded state, along with its request id,
+/// rounded up from a reply that carries a digest
+const MAX_RECORDED_REPLY_SIZE: usize = 64;
+
+/// Fed first into the [CalculatorState::digest], so it can never match a digest of something else
+const STATE_DIGEST_DOMAIN: &[u8] = b"atlas-examples/calculator-state/v1";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct CalculatorState {
+    value: i32,
+    /// The sequence number of the last batch applied to the value
+    executed: SeqNo,
+    /// Part of the state, so every replica (and any replica recovering
+    /// through state transfer) agrees on what was already executed
+    sessions: BTreeMap<NodeId, ClientSession>,
+    /// The epoch of the latest session opened
+    sessions_opened: u32,
+}
+
+/// The state as laid out by the legacy encoding (wire format version 0), which only held the value
+#[derive(Deserialize)]
+struct LegacyState {
     value: i32
 }
 
+/// The replies to the latest requests of a client
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ClientSession {
+    replies: BTreeMap<u64, Reply>,
+    /// The highest request id that no longer fits the window
+    evicted: Option<u64>,
+    /// The sequence number at which the session last executed a request
+    last_executed: SeqNo,
+}
+
+/// What we know about a request of a client
+pub enum PastRequest<'a> {
+    /// The request was never executed
+    New,
+    /// The request was executed, with this reply
+    Executed(&'a Reply),
+    /// The request is older than the deduplication window, or its session was evicted
+    Expired,
+}
+
+/// The first request id of the session with the given epoch. Epochs keep growing, so every id
+/// of a new session is above the ids of the client's previous sessions
+pub fn session_base(epoch: u32) -> u64 {
+    (epoch as u64) << 32
+}
+
 impl Default for CalculatorState {
     fn default() -> Self {
         CalculatorState {
             value: 0,
+            executed: SeqNo::ZERO,
+            sessions: BTreeMap::new(),
+            sessions_opened: 0,
         }
     }
 }
@@ -24,16 +95,236 @@ impl CalculatorState {
     pub fn set_value(&mut self, value: i32) {
         self.value = value;
     }
+
+    pub fn executed(&self) -> SeqNo {
+        self.executed
+    }
+
+    pub fn set_executed(&mut self, seq_no: SeqNo) {
+        self.executed = seq_no;
+    }
+
+    /// Start over at sequence number 0, for a state that seeds a new cluster.
+    ///
+    /// The sessions go too, as their replies carry sequence numbers of the old cluster.
+    /// New sessions keep getting later epochs, so the requests of the old ones stay expired
+    /// instead of being executed again when their clients retry them
+    pub fn restart(&mut self) {
+        self.executed = SeqNo::ZERO;
+        self.sessions.clear();
+    }
+
+    /// Requests of clients without a session are expired, as we cannot tell whether
+    /// an evicted session already executed them
+    pub fn past_request(&self, client: NodeId, request: u64) -> PastRequest<'_> {
+        self.sessions.get(&client)
+            .map_or(PastRequest::Expired, |session| session.past_request(request))
+    }
+
+    /// Open a new session for the client, returning its epoch.
+    ///
+    /// Any session the client had is dropped, so the requests it left
+    /// unanswered are expired instead of executed again
+    pub fn open_session(&mut self, client: NodeId) -> u32 {
+        self.sessions_opened = self.sessions_opened.wrapping_add(1).max(1);
+
+        let epoch = self.sessions_opened;
+
+        self.sessions.insert(client, ClientSession {
+            replies: BTreeMap::new(),
+            evicted: Some(session_base(epoch) - 1),
+            last_executed: self.executed,
+        });
+
+        self.evict_sessions(client);
+
+        epoch
+    }
+
+    /// How many clients have a session in the state
+    pub fn session_count(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// A digest of the contents of the state, which only depends on what the state holds.
+    ///
+    /// Every field is fed in a fixed layout, in the order of the maps, and each reply in its
+    /// [compact] layout, so replicas that agree on the state agree on the digest, whatever
+    /// codec or build they run
+    pub fn digest(&self) -> Digest {
+        let mut context = DigestContext::new();
+
+        context.update(STATE_DIGEST_DOMAIN);
+        context.update(&self.value.to_le_bytes());
+        context.update(&u32::from(self.executed).to_le_bytes());
+        context.update(&self.sessions_opened.to_le_bytes());
+        context.update(&(self.sessions.len() as u64).to_le_bytes());
+
+        let mut reply_buf = [0u8; compact::REPLY_SIZE];
+
+        for (client, session) in &self.sessions {
+            context.update(&client.0.to_le_bytes());
+            context.update(&[session.evicted.is_some() as u8]);
+            context.update(&session.evicted.unwrap_or(0).to_le_bytes());
+            context.update(&u32::from(session.last_executed).to_le_bytes());
+            context.update(&(session.replies.len() as u64).to_le_bytes());
+
+            for (request, reply) in &session.replies {
+                compact::encode_reply(reply, &mut reply_buf)
+                    .expect("A reply buffer fits the compact layout of a reply");
+
+                context.update(&request.to_le_bytes());
+                context.update(&reply_buf);
+
+                // The compact layout leaves out the digest a reply may carry
+                match reply.digest() {
+                    Some(digest) => {
+                        context.update(&[1]);
+                        context.update(digest.as_ref());
+                    }
+                    None => context.update(&[0]),
+                }
+            }
+        }
+
+        context.finish()
+    }
+
+    /// Remember the reply to a request, evicting the oldest one when the window is full
+    pub fn record_reply(&mut self, client: NodeId, request: u64, reply: Reply) {
+        let executed = self.executed;
+
+        let session = self.sessions.entry(client).or_insert_with(|| ClientSession::new(executed));
+
+        session.replies.insert(request, reply);
+        session.last_executed = executed;
+
+        while session.replies.len() > DEDUPLICATION_WINDOW {
+            if let Some((evicted, _)) = session.replies.pop_first() {
+                session.evicted = session.evicted.max(Some(evicted));
+            }
+        }
+
+        self.evict_sessions(client);
+    }
+
+    /// Evict the sessions that executed a request the longest ago (the lowest client
+    /// id first, among those that did so at the same time) until we are within
+    /// [MAX_SESSIONS], keeping the session of `current`
+    fn evict_sessions(&mut self, current: NodeId) {
+        while self.sessions.len() > MAX_SESSIONS {
+            let evicted = self.sessions.iter()
+                .filter(|(client, _)| **client != current)
+                .min_by_key(|(client, session)| (session.last_executed, **client))
+                .map(|(client, _)| *client);
+
+            match evicted {
+                Some(client) => self.sessions.remove(&client),
+                None => break,
+            };
+        }
+    }
+}
+
+impl ClientSession {
+    fn new(executed: SeqNo) -> Self {
+        Self {
+            replies: BTreeMap::new(),
+            evicted: None,
+            last_executed: executed,
+        }
+    }
+
+    fn past_request(&self, request: u64) -> PastRequest<'_> {
+        if let Some(reply) = self.replies.get(&request) {
+            return PastRequest::Executed(reply);
+        }
+
+        match self.evicted {
+            Some(evicted) if request <= evicted => PastRequest::Expired,
+            _ => PastRequest::New,
+        }
+    }
+}
+
+impl WireMessage for CalculatorState {
+    type Proto = StateProto;
+
+    const KIND: MessageKind = MessageKind::State;
+
+    fn decode_legacy<R: Read>(r: R) -> Result<Self, WireFormatError> {
+        let LegacyState { value } = wire::decode_bincode(0, r)?;
+
+        let mut state = CalculatorState::default();
+
+        state.set_value(value);
+
+        Ok(state)
+    }
+
+    fn to_proto(&self) -> Self::Proto {
+        let sessions = self.sessions.iter()
+            .map(|(client, session)| SessionProto {
+                client: client.0,
+                replies: session.replies.iter()
+                    .map(|(request, reply)| RecordedReplyProto {
+                        request: *request,
+                        reply: Some(reply.to_proto()),
+                    })
+                    .collect(),
+                evicted: session.evicted,
+                last_executed: u32::from(session.last_executed),
+            })
+            .collect();
+
+        StateProto {
+            value: self.value,
+            executed: u32::from(self.executed),
+            sessions,
+            sessions_opened: self.sessions_opened,
+        }
+    }
+
+    fn from_proto(proto: Self::Proto) -> Result<Self, WireFormatError> {
+        let mut sessions = BTreeMap::new();
+
+        for session in proto.sessions {
+            let mut replies = BTreeMap::new();
+
+            for recorded in session.replies {
+                let reply = recorded.reply
+                    .ok_or_else(|| WireFormatError::InvalidProtobuf(format!("Missing the reply to request {}", recorded.request)))?;
+
+                replies.insert(recorded.request, Reply::from_proto(reply)?);
+            }
+
+            sessions.insert(NodeId(session.client), ClientSession {
+                replies,
+                evicted: session.evicted,
+                last_executed: SeqNo::from(session.last_executed),
+            });
+        }
+
+        Ok(CalculatorState {
+            value: proto.value,
+            executed: SeqNo::from(proto.executed),
+            sessions,
+            sessions_opened: proto.sessions_opened,
+        })
+    }
 }
 
 impl MonolithicState for CalculatorState {
-    fn serialize_state<W>(mut w: W, request: &Self) -> atlas_common::error::Result<()> where W: Write {
-        bincode::serde::encode_into_std_write(request, &mut w, bincode::config::standard()).context("Failed to serialize state")?;
-        
-        Ok(())
+    fn serialize_state<W>(w: W, request: &Self) -> atlas_common::error::Result<()> where W: Write {
+        wire::encode_with(wire::STATE_CODEC, w, request).context("Failed to serialize state")
     }
 
-    fn deserialize_state<R>(mut r: R) -> atlas_common::error::Result<Self> where R: Read, Self: Sized {
-        bincode::serde::decode_from_std_read(&mut r, bincode::config::standard()).context("Failed to deserialize state")
+    fn deserialize_state<R>(r: R) -> atlas_common::error::Result<Self> where R: Read, Self: Sized {
+        wire::decode(r).context("Failed to deserialize state")
     }
 }
+
+/// The digest in hexadecimal, to print and compare
+pub fn digest_hex(digest: &Digest) -> String {
+    digest.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,315 @@
+use anyhow::{anyhow, Context as _};
+use serde::{Deserialize, Serialize};
+use atlas_common::crypto::hash::{Context, Digest};
+use atlas_common::ordering::{Orderable, SeqNo};
+use atlas_smr_application::state::divisible_state::{DivisibleState, DivisibleStateDescriptor, PartId, StatePart};
+
+/// The amount of shards the register state is split into, when not specified
+pub const DEFAULT_SHARD_COUNT: usize = 16;
+/// The amount of registers in each shard, when not specified
+pub const DEFAULT_REGISTERS_PER_SHARD: usize = 64;
+
+/// A calculator with many registers, grouped into shards.
+///
+/// Each shard is a part of the state which can be transferred independently,
+/// so a replica that is catching up only has to fetch the shards that changed
+/// since its last checkpoint.
+#[derive(Clone)]
+pub struct RegisterState {
+    shards: Vec<Shard>,
+    registers_per_shard: usize,
+    descriptor: RegisterStateDescriptor,
+    /// The sequence number of the latest batch that was executed, which
+    /// the next checkpoint is taken at
+    executed: SeqNo,
+}
+
+#[derive(Clone)]
+struct Shard {
+    registers: Vec<i32>,
+    description: ShardDescription,
+    /// Whether this shard was modified since the last checkpoint
+    dirty: bool,
+}
+
+/// Describes the content of a shard at a given checkpoint
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd)]
+pub struct ShardDescription {
+    id: u64,
+    /// The sequence number of the checkpoint in which this shard was last modified
+    seq: SeqNo,
+    digest: Digest,
+}
+
+/// Describes the whole state at a given checkpoint.
+///
+/// Descriptors are equal when their parts are: a replica that got its shards through a state
+/// transfer only knows the latest checkpoint one of them was modified at, which can be older
+/// than the checkpoint the other replicas describe the same shards at
+#[derive(Serialize, Deserialize, Clone, Debug, Eq)]
+pub struct RegisterStateDescriptor {
+    seq: SeqNo,
+    parts: Vec<ShardDescription>,
+}
+
+/// A serialized shard, as sent by the state transfer protocol
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RegisterShard {
+    description: ShardDescription,
+    bytes: Vec<u8>,
+}
+
+impl RegisterState {
+    pub fn new(shard_count: usize, registers_per_shard: usize) -> Self {
+        let shards: Vec<Shard> = (0..shard_count)
+            .map(|id| Shard::new(id as u64, vec![0; registers_per_shard], SeqNo::ZERO))
+            .collect();
+
+        let descriptor = RegisterStateDescriptor {
+            seq: SeqNo::ZERO,
+            parts: shards.iter().map(|shard| shard.description.clone()).collect(),
+        };
+
+        Self {
+            shards,
+            registers_per_shard,
+            descriptor,
+            executed: SeqNo::ZERO,
+        }
+    }
+
+    pub fn executed(&self) -> SeqNo {
+        self.executed
+    }
+
+    /// Record the sequence number of the batch that is being executed
+    pub fn set_executed(&mut self, seq: SeqNo) {
+        self.executed = seq;
+    }
+
+    /// The digest of every register, whether or not it was modified since the last checkpoint
+    pub fn digest(&self) -> Digest {
+        let mut context = Context::new();
+
+        for shard in &self.shards {
+            for register in &shard.registers {
+                context.update(&register.to_le_bytes());
+            }
+        }
+
+        context.finish()
+    }
+
+    pub fn register_count(&self) -> usize {
+        self.shards.len() * self.registers_per_shard
+    }
+
+    pub fn register(&self, register: u32) -> Option<i32> {
+        let (shard, index) = self.locate(register)?;
+
+        Some(self.shards[shard].registers[index])
+    }
+
+    /// Set the value of a register, marking its shard as modified.
+    /// Returns [None] if the register does not exist
+    pub fn set_register(&mut self, register: u32, value: i32) -> Option<()> {
+        let (shard, index) = self.locate(register)?;
+
+        let shard = &mut self.shards[shard];
+
+        shard.registers[index] = value;
+        shard.dirty = true;
+
+        Some(())
+    }
+
+    fn locate(&self, register: u32) -> Option<(usize, usize)> {
+        let register = register as usize;
+
+        let shard = register / self.registers_per_shard;
+
+        if shard >= self.shards.len() {
+            return None;
+        }
+
+        Some((shard, register % self.registers_per_shard))
+    }
+}
+
+impl Default for RegisterState {
+    fn default() -> Self {
+        Self::new(DEFAULT_SHARD_COUNT, DEFAULT_REGISTERS_PER_SHARD)
+    }
+}
+
+impl Shard {
+    fn new(id: u64, registers: Vec<i32>, seq: SeqNo) -> Self {
+        let digest = digest_registers(&registers);
+
+        Self {
+            registers,
+            description: ShardDescription { id, seq, digest },
+            dirty: false,
+        }
+    }
+
+    fn serialize(&self) -> atlas_common::error::Result<Vec<u8>> {
+        bincode::serde::encode_to_vec(&self.registers, bincode::config::standard())
+            .context("Failed to serialize register shard")
+    }
+}
+
+fn digest_registers(registers: &[i32]) -> Digest {
+    let mut context = Context::new();
+
+    for register in registers {
+        context.update(&register.to_le_bytes());
+    }
+
+    context.finish()
+}
+
+impl DivisibleState for RegisterState {
+    type PartDescription = ShardDescription;
+    type StateDescriptor = RegisterStateDescriptor;
+    type StatePart = RegisterShard;
+
+    fn get_descriptor(&self) -> Self::StateDescriptor {
+        self.descriptor.clone()
+    }
+
+    fn accept_parts(&mut self, parts: Vec<Self::StatePart>) -> atlas_common::error::Result<()> {
+        for part in parts {
+            let (registers, _): (Vec<i32>, usize) = bincode::serde::decode_from_slice(&part.bytes, bincode::config::standard())
+                .context("Failed to deserialize register shard")?;
+
+            if registers.len() != self.registers_per_shard {
+                return Err(anyhow!("Shard {} has {} registers, expected {}", part.description.id, registers.len(), self.registers_per_shard));
+            }
+
+            let received = Shard::new(part.description.id, registers, part.description.seq);
+
+            if received.description != part.description {
+                return Err(anyhow!("Shard {} does not match its description", part.description.id));
+            }
+
+            let shard = self.shards.get_mut(part.description.id as usize)
+                .ok_or_else(|| anyhow!("Received unknown shard {}", part.description.id))?;
+
+            *shard = received;
+        }
+
+        Ok(())
+    }
+
+    /// Checkpoints are taken at the sequence number of the latest executed batch, so every
+    /// replica describes its modified shards with the same sequence number
+    fn prepare_checkpoint(&mut self) -> atlas_common::error::Result<Self::StateDescriptor> {
+        let seq = self.executed;
+
+        for shard in self.shards.iter_mut().filter(|shard| shard.dirty) {
+            *shard = Shard::new(shard.description.id, std::mem::take(&mut shard.registers), seq);
+        }
+
+        self.descriptor = RegisterStateDescriptor {
+            seq,
+            parts: self.shards.iter().map(|shard| shard.description.clone()).collect(),
+        };
+
+        Ok(self.descriptor.clone())
+    }
+
+    fn get_parts(&self, parts: &Vec<Self::PartDescription>) -> atlas_common::error::Result<Vec<Self::StatePart>> {
+        parts.iter().map(|description| {
+            let shard = self.shards.get(description.id as usize)
+                .ok_or_else(|| anyhow!("Requested unknown shard {}", description.id))?;
+
+            Ok(RegisterShard {
+                description: shard.description.clone(),
+                bytes: shard.serialize()?,
+            })
+        }).collect()
+    }
+
+    fn finalize_transfer(&mut self) -> atlas_common::error::Result<()> {
+        let seq = self.shards.iter()
+            .map(|shard| shard.description.seq)
+            .max()
+            .unwrap_or(SeqNo::ZERO);
+
+        self.descriptor = RegisterStateDescriptor {
+            seq,
+            parts: self.shards.iter().map(|shard| shard.description.clone()).collect(),
+        };
+
+        Ok(())
+    }
+}
+
+impl PartId for ShardDescription {
+    fn content_description(&self) -> &Digest {
+        &self.digest
+    }
+
+    fn seq_no(&self) -> &SeqNo {
+        &self.seq
+    }
+}
+
+impl PartialEq for RegisterStateDescriptor {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts == other.parts
+    }
+}
+
+impl Orderable for RegisterStateDescriptor {
+    fn sequence_number(&self) -> SeqNo {
+        self.seq
+    }
+}
+
+impl DivisibleStateDescriptor<RegisterState> for RegisterStateDescriptor {
+    fn parts(&self) -> &Vec<ShardDescription> {
+        &self.parts
+    }
+
+    /// The parts of the other descriptor which differ from ours
+    fn compare_descriptors(&self, other: &Self) -> Vec<ShardDescription> {
+        other.parts.iter()
+            .filter(|part| self.parts.get(part.id as usize) != Some(*part))
+            .cloned()
+            .collect()
+    }
+
+    fn get_digest(&self) -> Option<Digest> {
+        let mut context = Context::new();
+
+        for part in &self.parts {
+            context.update(part.digest.as_ref());
+        }
+
+        Some(context.finish())
+    }
+}
+
+impl StatePart<RegisterState> for RegisterShard {
+    fn descriptor(&self) -> ShardDescription {
+        self.description.clone()
+    }
+
+    fn id(&self) -> u64 {
+        self.description.id
+    }
+
+    fn length(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn hash(&self) -> Digest {
+        self.description.digest
+    }
+}
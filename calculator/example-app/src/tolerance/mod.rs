@@ -0,0 +1,119 @@
+use std::marker::PhantomData;
+use serde::Deserialize;
+use thiserror::Error;
+use atlas_core::ordering_protocol::OrderProtocolTolerance;
+
+/// Tolerance of protocols that handle byzantine faults, which require `3f+1` replicas
+pub struct BFT;
+
+/// The tolerance every client and tool sizes its quorums with. Replicas check when they
+/// are built that their ordering protocol has the same one, and clients check with the
+/// replicas that they agree on `f` when they connect
+pub type Tolerance = BFT;
+
+impl OrderProtocolTolerance for BFT {
+    fn get_n_for_f(f: usize) -> usize {
+        3 * f + 1
+    }
+
+    fn get_quorum_for_n(n: usize) -> usize {
+        Self::get_f_for_n(n) * 2 + 1
+    }
+
+    fn get_f_for_n(n: usize) -> usize {
+        n.saturating_sub(1) / 3
+    }
+}
+
+/// The part of `nodes.toml` we need in order to know how many replicas are deployed
+#[derive(Deserialize, Clone, Debug)]
+pub struct NodesConfig {
+    #[serde(default)]
+    bootstrap_nodes: Vec<BootstrapNode>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BootstrapNode {
+    pub node_id: u32,
+    pub ip: String,
+    pub port: u16,
+    node_type: String,
+}
+
+/// What the deployment was set up to tolerate, shared by every client and replica
+#[derive(Deserialize, Clone, Debug)]
+pub struct DeploymentConfig {
+    /// The number of faulty replicas the deployment is expected to tolerate
+    pub f: usize,
+}
+
+/// The fault tolerance of a deployment, as seen by a node with the tolerance `T`
+#[derive(Debug)]
+pub struct Quorum<T> {
+    n: usize,
+    f: usize,
+    quorum: usize,
+    tolerance: PhantomData<fn() -> T>,
+}
+
+#[derive(Error, Debug)]
+pub enum ToleranceError {
+    #[error("{n} replicas cannot tolerate any fault, at least {required} are needed to tolerate one")]
+    NoFaultsTolerated { n: usize, required: usize },
+    #[error("The deployment expects f = {expected}, but {n} replicas only tolerate f = {actual} (f = {expected} needs {required} replicas)")]
+    FaultsMismatch { n: usize, expected: usize, actual: usize, required: usize },
+}
+
+impl NodesConfig {
+    pub fn replica_count(&self) -> usize {
+        self.replicas().count()
+    }
+
+    pub fn replicas(&self) -> impl Iterator<Item=&BootstrapNode> {
+        self.bootstrap_nodes.iter()
+            .filter(|node| node.node_type.eq_ignore_ascii_case("Replica"))
+    }
+}
+
+impl<T> Quorum<T> where T: OrderProtocolTolerance {
+    /// Check that the `n` replicas of the deployment tolerate at least one fault,
+    /// and exactly the amount of faults the deployment expects.
+    ///
+    /// This only checks the node's own `deployment.toml`, so clients also ask the
+    /// replicas for their `f` when they connect, refusing to go on if it differs
+    pub fn check(n: usize, deployment: &DeploymentConfig) -> Result<Self, ToleranceError> {
+        let f = T::get_f_for_n(n);
+
+        if f == 0 {
+            return Err(ToleranceError::NoFaultsTolerated { n, required: T::get_n_for_f(1) });
+        }
+
+        if f != deployment.f {
+            return Err(ToleranceError::FaultsMismatch {
+                n,
+                expected: deployment.f,
+                actual: f,
+                required: T::get_n_for_f(deployment.f),
+            });
+        }
+
+        Ok(Self {
+            n,
+            f,
+            quorum: T::get_quorum_for_n(n),
+            tolerance: PhantomData,
+        })
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    pub fn f(&self) -> usize {
+        self.f
+    }
+
+    pub fn quorum(&self) -> usize {
+        self.quorum
+    }
+}
@@ -0,0 +1,480 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use anyhow::{anyhow, Context};
+use log::{error, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use crate::logging::NodeRole;
+
+/// How many spans we accumulate before exporting them
+const EXPORT_BATCH_SIZE: usize = 64;
+/// The maximum amount of time a finished span waits before being exported
+const EXPORT_INTERVAL: Duration = Duration::from_secs(1);
+/// How many finished spans can wait for the exporter. Spans finished while it is full
+/// are dropped, so a slow or unreachable collector cannot grow the node's memory
+const EXPORT_QUEUE_CAPACITY: usize = 4096;
+/// How long connecting to the collector, and sending or receiving from it, may take
+const COLLECTOR_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TracingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub exporter: TraceExporterConfig,
+}
+
+/// Where the finished spans are sent to.
+/// Both exporters produce OTLP/JSON `ExportTraceServiceRequest` documents
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TraceExporterConfig {
+    /// Append one document per line to the given file
+    File { path: PathBuf },
+    /// POST each document to an OTLP/HTTP collector (ex: `127.0.0.1:4318`)
+    Collector { endpoint: String },
+}
+
+/// The identity of a traced request, as known by both the client and the replicas.
+///
+/// Every id of the request's trace is derived from this, so the spans emitted by
+/// different nodes end up in the same trace. Nothing is added to the request for this,
+/// the identity is the id it already carries (see [crate::app::messages::Request::id])
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RequestTraceId {
+    client: NodeId,
+    request: u64,
+    /// The request carries no id, so only its client records spans of it
+    local: bool,
+}
+
+/// The stages of a request's lifecycle which we emit spans for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// From the moment the client sends the request until it has gathered the reply
+    ClientRequest,
+    /// The execution of an ordered request by [crate::app::App::update]. Pre-processing,
+    /// proposal and consensus happen inside Atlas, which offers no hook to time them, so
+    /// they show up as the time between the client's span and this one
+    OrderedExecution,
+}
+
+#[derive(Clone, Debug)]
+pub enum AttributeValue {
+    Int(i64),
+    Str(String),
+}
+
+/// Handle used to record spans. Cheap to clone, the export is done in a separate thread
+#[derive(Clone)]
+pub struct Tracer {
+    tx: SyncSender<ExporterMessage>,
+    /// Spans dropped since the last export, as the exporter could not keep up
+    dropped: Arc<AtomicUsize>,
+}
+
+/// A span that is currently being recorded. It is exported when [ActiveSpan::finish] is called
+pub struct ActiveSpan<'a> {
+    tracer: &'a Tracer,
+    request: RequestTraceId,
+    stage: Stage,
+    start: SystemTime,
+    attributes: Vec<(&'static str, AttributeValue)>,
+}
+
+enum ExporterMessage {
+    Span(FinishedSpan),
+    /// Export everything that is pending and then notify the given channel
+    Flush(mpsc::Sender<()>),
+}
+
+struct FinishedSpan {
+    request: RequestTraceId,
+    stage: Stage,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(&'static str, AttributeValue)>,
+}
+
+enum SpanSink {
+    File(File),
+    Collector(String),
+}
+
+impl RequestTraceId {
+    /// A request that carries the given id, so the replicas' spans join the client's trace
+    pub fn new(client: NodeId, request: u64) -> Self {
+        Self { client, request, local: false }
+    }
+
+    /// A request that carries no id, such as an unordered read, numbered by its client alone.
+    /// Its trace only holds the client's span, and never mixes with the traces of ids
+    pub fn local(client: NodeId, request: u64) -> Self {
+        Self { client, request, local: true }
+    }
+
+    /// The trace id is just the request identity laid out in big endian,
+    /// so it can be read back from any trace viewer
+    pub fn trace_id(&self) -> [u8; 16] {
+        let mut id = [0; 16];
+
+        id[0..4].copy_from_slice(&self.client.0.to_be_bytes());
+        id[4..12].copy_from_slice(&self.request.to_be_bytes());
+        id[12] = self.local as u8;
+        // Make sure the id is never all zeroes, which OTLP considers invalid
+        id[15] = 1;
+
+        id
+    }
+
+    /// The id of the span recorded by the given node for the given stage
+    pub fn span_id(&self, node: NodeId, stage: Stage) -> [u8; 8] {
+        let mut hash = Fnv1a::default();
+
+        hash.write(&self.trace_id());
+        hash.write(&node.0.to_be_bytes());
+        hash.write(&[stage as u8]);
+
+        hash.finish().to_be_bytes()
+    }
+
+    /// The root span of every request's trace is the span recorded by the client
+    pub fn root_span_id(&self) -> [u8; 8] {
+        self.span_id(self.client, Stage::ClientRequest)
+    }
+}
+
+impl Stage {
+    fn name(&self) -> &'static str {
+        match self {
+            Stage::ClientRequest => "client.request",
+            Stage::OrderedExecution => "app.update",
+        }
+    }
+
+    fn kind(&self) -> u32 {
+        // OTLP span kinds: 2 = server, 3 = client
+        match self {
+            Stage::ClientRequest => 3,
+            Stage::OrderedExecution => 2,
+        }
+    }
+}
+
+impl Tracer {
+    /// Set up the tracer for this node, spawning the exporting thread.
+    /// Returns [None] when tracing is disabled
+    pub fn init(node_id: NodeId, role: NodeRole, config: &TracingConfig) -> atlas_common::error::Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let sink = match &config.exporter {
+            TraceExporterConfig::File { path } => SpanSink::File(open_trace_file(path)?),
+            TraceExporterConfig::Collector { endpoint } => SpanSink::Collector(endpoint.clone()),
+        };
+
+        let (tx, rx) = mpsc::sync_channel(EXPORT_QUEUE_CAPACITY);
+
+        let dropped = Arc::new(AtomicUsize::new(0));
+
+        let resource = json!({
+            "attributes": [
+                attribute("service.name", &AttributeValue::Str(format!("calculator-{}", role))),
+                attribute("node.id", &AttributeValue::Int(node_id.0 as i64)),
+            ]
+        });
+
+        let exporter_dropped = dropped.clone();
+
+        std::thread::Builder::new()
+            .name(String::from("Trace exporter"))
+            .spawn(move || export_spans(node_id, resource, sink, rx, exporter_dropped))
+            .context("Failed to spawn trace exporter thread")?;
+
+        Ok(Some(Self { tx, dropped }))
+    }
+
+    pub fn start_span(&self, request: RequestTraceId, stage: Stage) -> ActiveSpan<'_> {
+        ActiveSpan {
+            tracer: self,
+            request,
+            stage,
+            start: SystemTime::now(),
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Export every span finished so far, blocking until that is done.
+    /// Should be called before the process exits
+    pub fn flush(&self) {
+        let (tx, rx) = mpsc::channel();
+
+        if self.tx.send(ExporterMessage::Flush(tx)).is_ok() {
+            let _ = rx.recv();
+        }
+    }
+}
+
+impl<'a> ActiveSpan<'a> {
+    pub fn set_attribute(&mut self, key: &'static str, value: impl Into<AttributeValue>) {
+        self.attributes.push((key, value.into()));
+    }
+
+    pub fn finish(self) {
+        let span = FinishedSpan {
+            request: self.request,
+            stage: self.stage,
+            start: self.start,
+            end: SystemTime::now(),
+            attributes: self.attributes,
+        };
+
+        // The exporter only goes away when the process is shutting down
+        if let Err(TrySendError::Full(_)) = self.tracer.tx.try_send(ExporterMessage::Span(span)) {
+            self.tracer.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn open_trace_file(path: &Path) -> atlas_common::error::Result<File> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create trace folder {:?}", parent))?;
+    }
+
+    OpenOptions::new().create(true).append(true).open(path)
+        .with_context(|| format!("Failed to open trace file {:?}", path))
+}
+
+fn export_spans(node_id: NodeId, resource: Value, mut sink: SpanSink, rx: Receiver<ExporterMessage>, dropped: Arc<AtomicUsize>) {
+    let mut pending = Vec::with_capacity(EXPORT_BATCH_SIZE);
+
+    // When the oldest pending span has to be exported by
+    let mut deadline = None;
+
+    loop {
+        let mut flushed = None;
+
+        let timeout = deadline.map_or(EXPORT_INTERVAL, |deadline: Instant| deadline.saturating_duration_since(Instant::now()));
+
+        let disconnected = match rx.recv_timeout(timeout) {
+            Ok(ExporterMessage::Span(span)) => {
+                pending.push(span_to_json(node_id, &span));
+
+                let deadline = *deadline.get_or_insert_with(|| Instant::now() + EXPORT_INTERVAL);
+
+                if pending.len() < EXPORT_BATCH_SIZE && Instant::now() < deadline {
+                    continue;
+                }
+
+                false
+            }
+            Ok(ExporterMessage::Flush(notify)) => {
+                flushed = Some(notify);
+
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        };
+
+        deadline = None;
+
+        if !pending.is_empty() {
+            let document = json!({
+                "resourceSpans": [{
+                    "resource": resource,
+                    "scopeSpans": [{
+                        "scope": { "name": "example-app" },
+                        "spans": std::mem::take(&mut pending),
+                    }]
+                }]
+            });
+
+            if let Err(err) = sink.export(&document) {
+                warn!("Failed to export spans: {:?}", err);
+            }
+        }
+
+        match dropped.swap(0, Ordering::Relaxed) {
+            0 => {}
+            count => warn!("Dropped {} spans, as more than {} were waiting to be exported", count, EXPORT_QUEUE_CAPACITY),
+        }
+
+        if let Some(notify) = flushed {
+            let _ = notify.send(());
+        }
+
+        if disconnected {
+            break;
+        }
+    }
+}
+
+fn span_to_json(node_id: NodeId, span: &FinishedSpan) -> Value {
+    let mut attributes = vec![
+        attribute("calculator.client", &AttributeValue::Int(span.request.client.0 as i64)),
+        // OTLP integers are signed, so the id is kept as is in a string
+        attribute("calculator.request", &AttributeValue::Str(span.request.request.to_string())),
+    ];
+
+    attributes.extend(span.attributes.iter().map(|(key, value)| attribute(key, value)));
+
+    let mut value = json!({
+        "traceId": to_hex(&span.request.trace_id()),
+        "spanId": to_hex(&span.request.span_id(node_id, span.stage)),
+        "name": span.stage.name(),
+        "kind": span.stage.kind(),
+        "startTimeUnixNano": unix_nanos(span.start).to_string(),
+        "endTimeUnixNano": unix_nanos(span.end).to_string(),
+        "attributes": attributes,
+    });
+
+    if span.stage != Stage::ClientRequest {
+        value["parentSpanId"] = Value::String(to_hex(&span.request.root_span_id()));
+    }
+
+    value
+}
+
+fn attribute(key: &str, value: &AttributeValue) -> Value {
+    // OTLP/JSON encodes 64 bit integers as strings
+    let value = match value {
+        AttributeValue::Int(value) => json!({ "intValue": value.to_string() }),
+        AttributeValue::Str(value) => json!({ "stringValue": value }),
+    };
+
+    json!({ "key": key, "value": value })
+}
+
+impl SpanSink {
+    fn export(&mut self, document: &Value) -> atlas_common::error::Result<()> {
+        match self {
+            SpanSink::File(file) => {
+                serde_json::to_writer(&mut *file, document)?;
+                file.write_all(b"\n")?;
+
+                Ok(())
+            }
+            SpanSink::Collector(endpoint) => post_to_collector(endpoint, document),
+        }
+    }
+}
+
+/// Minimal OTLP/HTTP export, so we don't drag a whole HTTP stack into the replicas
+fn post_to_collector(endpoint: &str, document: &Value) -> atlas_common::error::Result<()> {
+    let body = serde_json::to_vec(document)?;
+
+    let address = endpoint.to_socket_addrs()
+        .with_context(|| format!("Failed to resolve trace collector address {}", endpoint))?
+        .next()
+        .ok_or_else(|| anyhow!("Trace collector address {} resolved to nothing", endpoint))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, COLLECTOR_TIMEOUT)
+        .with_context(|| format!("Failed to connect to trace collector at {}", endpoint))?;
+
+    stream.set_read_timeout(Some(COLLECTOR_TIMEOUT))?;
+    stream.set_write_timeout(Some(COLLECTOR_TIMEOUT))?;
+
+    write!(stream, "POST /v1/traces HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+           endpoint, body.len())?;
+    stream.write_all(&body)?;
+
+    let mut status_line = String::new();
+
+    BufReader::new(stream).read_line(&mut status_line)?;
+
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(anyhow!("Trace collector rejected the export: {}", status_line.trim())),
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_nanos(),
+        Err(err) => {
+            error!("System clock is set before the unix epoch: {:?}", err);
+
+            0
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 64 bit FNV-1a, which is stable across processes and platforms (unlike the std hasher)
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for TraceExporterConfig {
+    fn default() -> Self {
+        TraceExporterConfig::File { path: PathBuf::from("traces/spans.jsonl") }
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        AttributeValue::Int(value)
+    }
+}
+
+impl From<u32> for AttributeValue {
+    fn from(value: u32) -> Self {
+        AttributeValue::Int(value as i64)
+    }
+}
+
+impl From<usize> for AttributeValue {
+    fn from(value: usize) -> Self {
+        AttributeValue::Int(value as i64)
+    }
+}
+
+impl From<SeqNo> for AttributeValue {
+    fn from(value: SeqNo) -> Self {
+        AttributeValue::Int(u32::from(value) as i64)
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::Str(value)
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::Str(value.to_string())
+    }
+}
@@ -0,0 +1,195 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use proptest::prelude::*;
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use example_app::app::messages::{Operation, Reply, Request};
+use example_app::app::wire;
+use example_app::app::wire::limits::{rejected_payloads, MessageKind};
+use example_app::app::wire::{Codec, WireFormatError};
+use example_app::state::{session_base, CalculatorState, DEDUPLICATION_WINDOW, MAX_SESSIONS};
+
+/// Remembers the largest allocation made by each thread, so a test can tell what decoding allocated
+struct LargestAllocation;
+
+thread_local! {
+    static LARGEST: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for LargestAllocation {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation(layout.size());
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_allocation(new_size);
+
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: LargestAllocation = LargestAllocation;
+
+fn record_allocation(size: usize) {
+    // The thread local may already be gone while the thread exits
+    let _ = LARGEST.try_with(|largest| largest.set(largest.get().max(size)));
+}
+
+fn largest_allocation_while<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    LARGEST.with(|largest| largest.set(0));
+
+    let result = f();
+
+    (result, LARGEST.with(Cell::get))
+}
+
+fn large_state(clients: u32) -> CalculatorState {
+    let mut state = CalculatorState::default();
+
+    for client in 0..clients {
+        state.record_reply(NodeId(client), 1, Reply::new(Ok(1), SeqNo::ZERO));
+    }
+
+    state
+}
+
+#[test]
+fn oversized_state_is_rejected_and_counted() {
+    let state = large_state(64);
+
+    for codec in Codec::ALL {
+        let mut buf = Vec::new();
+
+        wire::encode_with(codec, &mut buf, &state).unwrap();
+
+        let rejected = rejected_payloads().states;
+
+        let from_reader = wire::decode_limited::<CalculatorState, _>(buf.as_slice(), 64);
+        let from_slice = wire::decode_from_slice_limited::<CalculatorState>(&buf, 64);
+
+        assert!(matches!(from_reader, Err(WireFormatError::TooLarge { kind: MessageKind::State, limit: 64 })), "{:?}", codec);
+        assert!(matches!(from_slice, Err(WireFormatError::TooLarge { kind: MessageKind::State, limit: 64 })), "{:?}", codec);
+
+        // Other tests may be rejecting payloads at the same time
+        assert!(rejected_payloads().states >= rejected + 2);
+
+        assert_eq!(wire::decode_limited::<CalculatorState, _>(buf.as_slice(), buf.len()).unwrap(), state);
+        assert_eq!(wire::decode_from_slice_limited::<CalculatorState>(&buf, buf.len()).unwrap(), state);
+    }
+}
+
+/// Every session holds a full window of the largest replies there are, so the state is
+/// as large as it can grow, and it must still be accepted by the default state limit
+#[test]
+fn a_full_state_fits_the_default_state_limit() {
+    let mut state = CalculatorState::default();
+
+    let reply = Reply::new(Ok(i32::MIN), SeqNo::from(u32::MAX)).with_digest(CalculatorState::default().digest());
+
+    for client in 0..MAX_SESSIONS as u32 {
+        let base = session_base(u32::MAX - client);
+
+        for request in 0..DEDUPLICATION_WINDOW as u64 {
+            state.record_reply(NodeId(u32::MAX - client), base + request, reply.clone());
+        }
+    }
+
+    assert_eq!(state.session_count(), MAX_SESSIONS);
+
+    let mut buf = Vec::new();
+
+    wire::encode_with(wire::STATE_CODEC, &mut buf, &state).unwrap();
+
+    assert!(buf.len() <= wire::limits().max_size(MessageKind::State), "A full state takes {} bytes", buf.len());
+    assert_eq!(wire::decode::<CalculatorState, _>(buf.as_slice()).unwrap(), state);
+}
+
+proptest! {
+    /// A message exactly at the limit is accepted, one byte over is not
+    #[test]
+    fn limit_is_exact(value in any::<i32>(), id in any::<u64>()) {
+        let request = Request::new(Operation::Add, value).with_id(id);
+
+        for codec in Codec::ALL {
+            let mut buf = Vec::new();
+
+            wire::encode_with(codec, &mut buf, &request).unwrap();
+
+            prop_assert_eq!(wire::decode_limited::<Request, _>(buf.as_slice(), buf.len()).unwrap(), request.clone());
+
+            let too_large = matches!(
+                wire::decode_limited::<Request, _>(buf.as_slice(), buf.len() - 1),
+                Err(WireFormatError::TooLarge { kind: MessageKind::Request, .. })
+            );
+
+            prop_assert!(too_large, "{:?}", codec);
+        }
+    }
+}
+
+#[test]
+fn buffered_decoding_matches_the_slice_decoder() {
+    let state = large_state(64);
+
+    for codec in Codec::ALL {
+        let mut buf = Vec::new();
+
+        wire::encode_with(codec, &mut buf, &state).unwrap();
+
+        // Past the stack buffer, so the rest of the message is read into the heap
+        assert!(buf.len() > wire::STACK_BUFFER_SIZE);
+
+        assert_eq!(wire::decode_buffered::<CalculatorState, _>(buf.as_slice()).unwrap(), state);
+    }
+
+    let request = Request::new(Operation::Add, 1).with_id(1);
+
+    let mut buf = Vec::new();
+
+    wire::encode(&mut buf, &request).unwrap();
+
+    assert_eq!(wire::decode_buffered::<Request, _>(buf.as_slice()).unwrap(), request);
+}
+
+#[test]
+fn buffered_decoding_rejects_oversized_requests() {
+    let limit = wire::limits().max_size(MessageKind::Request);
+
+    let mut buf = Vec::new();
+
+    wire::encode(&mut buf, &Request::new(Operation::Add, 1)).unwrap();
+
+    buf.resize(limit + 1, 0);
+
+    assert!(matches!(wire::decode_buffered::<Request, _>(buf.as_slice()), Err(WireFormatError::TooLarge { kind: MessageKind::Request, .. })));
+}
+
+/// A length prefix claiming far more sessions than the bytes that follow, or than memory could hold
+#[test]
+fn forged_container_length_is_rejected_without_allocating_it() {
+    let mut forged = vec![wire::FORMAT_MAGIC, wire::CURRENT_VERSION, 0];
+
+    forged.extend(bincode::serde::encode_to_vec((42i32, SeqNo::from(7)), bincode::config::standard()).unwrap());
+
+    // A bincode varint of 2^60, for the length of the sessions
+    forged.push(0xFD);
+    forged.extend_from_slice(&(1u64 << 60).to_le_bytes());
+    forged.extend_from_slice(&[0; 16]);
+
+    let (from_slice, largest) = largest_allocation_while(|| wire::decode_from_slice::<CalculatorState>(&forged));
+
+    assert!(matches!(from_slice, Err(WireFormatError::Decode { .. })), "{:?}", from_slice);
+    assert!(largest < 1024 * 1024, "Allocated {} bytes", largest);
+
+    let (from_reader, largest) = largest_allocation_while(|| wire::decode::<CalculatorState, _>(forged.as_slice()));
+
+    assert!(matches!(from_reader, Err(WireFormatError::Decode { .. })), "{:?}", from_reader);
+    assert!(largest < 1024 * 1024, "Allocated {} bytes", largest);
+}
@@ -0,0 +1,66 @@
+use atlas_common::ordering::SeqNo;
+use atlas_smr_application::app::Application;
+use example_app::app::App;
+use example_app::app::messages::{Operation, OperationError, ReadConsistency, Request};
+use example_app::state::CalculatorState;
+
+fn replica_at(value: i32, executed: u32) -> CalculatorState {
+    let mut state = CalculatorState::default();
+
+    state.set_value(value);
+    state.set_executed(SeqNo::from(executed));
+
+    state
+}
+
+fn read(consistency: ReadConsistency) -> Request {
+    Request::new(Operation::Get { consistency }, 0)
+}
+
+#[test]
+fn unordered_replies_reflect_what_the_replica_executed() {
+    let app = App::init();
+
+    let (behind, ahead) = (replica_at(7, 3), replica_at(7, 9));
+
+    for consistency in [ReadConsistency::Any, ReadConsistency::BFT, ReadConsistency::ReadYourWrites { after: SeqNo::from(2) }] {
+        let (from_behind, from_ahead) = (app.unordered_execution(&behind, read(consistency)), app.unordered_execution(&ahead, read(consistency)));
+
+        assert_eq!((from_behind.result(), from_behind.seq_no()), (&Ok(7), SeqNo::from(3)));
+        assert_eq!((from_ahead.result(), from_ahead.seq_no()), (&Ok(7), SeqNo::from(9)));
+    }
+
+    assert_eq!(app.unordered_execution(&ahead, Request::new(Operation::Tolerance, 0)).seq_no(), SeqNo::from(9));
+}
+
+#[test]
+fn read_your_writes_waits_for_the_requested_sequence_number() {
+    let app = App::init();
+
+    let reply = app.unordered_execution(&replica_at(7, 9), read(ReadConsistency::ReadYourWrites { after: SeqNo::from(5) }));
+
+    assert_eq!(reply.result(), &Ok(7));
+    assert_eq!(reply.seq_no(), SeqNo::from(9));
+
+    let reply = app.unordered_execution(&replica_at(7, 3), read(ReadConsistency::ReadYourWrites { after: SeqNo::from(5) }));
+
+    assert_eq!(reply.result(), &Err(OperationError::NotExecutedYet { executed: SeqNo::from(3), required: SeqNo::from(5) }));
+}
+
+#[test]
+fn read_consistencies_parse_from_their_names() {
+    assert_eq!("bft".parse::<ReadConsistency>().unwrap(), ReadConsistency::BFT);
+    assert_eq!("Read_Your_Writes".parse::<ReadConsistency>().unwrap(), ReadConsistency::ReadYourWrites { after: SeqNo::ZERO });
+    assert!("strong".parse::<ReadConsistency>().is_err());
+}
+
+#[test]
+fn replicas_answer_with_the_faults_they_tolerate() {
+    let app = App::init().with_tolerated_faults(2);
+
+    let mut state = replica_at(7, 3);
+
+    assert_eq!(app.unordered_execution(&state, Request::new(Operation::Tolerance, 0)).result(), &Ok(2));
+    assert_eq!(app.update(&mut state, Request::new(Operation::Tolerance, 0)).result(), &Ok(2));
+    assert_eq!(state.value(), 7);
+}
@@ -0,0 +1,139 @@
+use atlas_common::ordering::{Orderable, SeqNo};
+use atlas_smr_application::app::Application;
+use atlas_smr_application::state::divisible_state::{DivisibleState, DivisibleStateDescriptor, PartId};
+use example_app::app::messages::{Operation, Request};
+use example_app::app::registers::{RegisterApp, RegisterReply, RegisterRequest};
+use example_app::app::wire;
+use example_app::app::wire::Codec;
+use example_app::state::registers::RegisterState;
+
+const SHARDS: usize = 4;
+const REGISTERS_PER_SHARD: usize = 8;
+
+fn execute(app: &RegisterApp, state: &mut RegisterState, seq: u32, register: u32, operation: Operation, value: i32) -> RegisterReply {
+    state.set_executed(SeqNo::from(seq));
+
+    app.update(state, RegisterRequest::new(register, Request::new(operation, value)))
+}
+
+#[test]
+fn checkpoints_are_taken_at_the_executed_sequence_number() {
+    let app = RegisterApp::init();
+
+    let mut state = RegisterState::new(SHARDS, REGISTERS_PER_SHARD);
+
+    assert_eq!(execute(&app, &mut state, 7, 9, Operation::Add, 5), RegisterReply::Value(5));
+
+    let descriptor = state.prepare_checkpoint().unwrap();
+
+    assert_eq!(descriptor.sequence_number(), SeqNo::from(7));
+
+    // Only the shard of register 9 was modified
+    let seqs: Vec<SeqNo> = descriptor.parts().iter().map(|part| *part.seq_no()).collect();
+
+    assert_eq!(seqs, vec![SeqNo::ZERO, SeqNo::from(7), SeqNo::ZERO, SeqNo::ZERO]);
+
+    let mut other = RegisterState::new(SHARDS, REGISTERS_PER_SHARD);
+
+    execute(&app, &mut other, 7, 9, Operation::Add, 5);
+
+    assert_eq!(other.prepare_checkpoint().unwrap(), descriptor);
+}
+
+#[test]
+fn a_transferred_state_matches_its_source() {
+    let app = RegisterApp::init();
+
+    let mut source = RegisterState::new(SHARDS, REGISTERS_PER_SHARD);
+
+    execute(&app, &mut source, 3, 1, Operation::Add, 2);
+    execute(&app, &mut source, 3, 30, Operation::Sub, 4);
+    source.prepare_checkpoint().unwrap();
+
+    // A later checkpoint that modified nothing, so no shard carries its sequence number
+    source.set_executed(SeqNo::from(6));
+    let descriptor = source.prepare_checkpoint().unwrap();
+
+    let mut target = RegisterState::new(SHARDS, REGISTERS_PER_SHARD);
+
+    let missing = target.get_descriptor().compare_descriptors(&descriptor);
+
+    assert_eq!(missing.len(), 2);
+
+    target.accept_parts(source.get_parts(&missing).unwrap()).unwrap();
+    target.finalize_transfer().unwrap();
+
+    assert_eq!(target.get_descriptor(), descriptor);
+    assert!(target.get_descriptor().compare_descriptors(&descriptor).is_empty());
+    assert_eq!(target.get_descriptor().get_digest(), descriptor.get_digest());
+    assert_eq!(target.register(30), Some(-4));
+
+    // Both keep describing the same checkpoints after the transfer
+    execute(&app, &mut source, 9, 2, Operation::Add, 1);
+    execute(&app, &mut target, 9, 2, Operation::Add, 1);
+
+    let descriptor = source.prepare_checkpoint().unwrap();
+
+    assert_eq!(target.prepare_checkpoint().unwrap(), descriptor);
+    assert_eq!(target.get_descriptor().sequence_number(), descriptor.sequence_number());
+}
+
+#[test]
+fn shards_of_another_size_are_rejected() {
+    let mut source = RegisterState::new(SHARDS, REGISTERS_PER_SHARD);
+
+    source.set_register(0, 1);
+
+    let descriptor = source.prepare_checkpoint().unwrap();
+
+    let mut target = RegisterState::new(SHARDS, REGISTERS_PER_SHARD * 2);
+
+    let parts = source.get_parts(&descriptor.parts()[..1].to_vec()).unwrap();
+
+    assert!(target.accept_parts(parts).is_err());
+}
+
+#[test]
+fn requests_that_need_sessions_are_unsupported() {
+    let app = RegisterApp::init().with_tolerated_faults(1);
+
+    let mut state = RegisterState::new(SHARDS, REGISTERS_PER_SHARD);
+
+    let with_id = RegisterRequest::new(0, Request::new(Operation::Add, 1).with_id(1));
+
+    assert_eq!(app.update(&mut state, with_id), RegisterReply::Unsupported);
+    assert_eq!(app.update(&mut state, RegisterRequest::new(0, Request::new(Operation::OpenSession, 0))), RegisterReply::Unsupported);
+    assert_eq!(state.register(0), Some(0));
+
+    assert_eq!(app.update(&mut state, RegisterRequest::new(0, Request::new(Operation::Tolerance, 0))), RegisterReply::Value(1));
+    assert_eq!(app.update(&mut state, RegisterRequest::new(0, Request::new(Operation::Digest, 0))), RegisterReply::Digest(state.digest()));
+    assert_eq!(app.update(&mut state, RegisterRequest::new(1000, Request::new(Operation::Add, 1))), RegisterReply::UnknownRegister(1000));
+}
+
+#[test]
+fn register_messages_roundtrip_with_every_codec() {
+    let request = RegisterRequest::new(12, Request::new(Operation::CompareAndSwap { expected: 3 }, 4));
+
+    let replies = [
+        RegisterReply::Value(-7),
+        RegisterReply::UnknownRegister(99),
+        RegisterReply::Digest(RegisterState::default().digest()),
+        RegisterReply::Unsupported,
+    ];
+
+    for codec in Codec::ALL {
+        let mut buf = Vec::new();
+
+        wire::encode_with(codec, &mut buf, &request).unwrap();
+
+        assert_eq!(wire::decode_from_slice::<RegisterRequest>(&buf).unwrap(), request, "{:?}", codec);
+
+        for reply in &replies {
+            let mut buf = Vec::new();
+
+            wire::encode_with(codec, &mut buf, reply).unwrap();
+
+            assert_eq!(&wire::decode_from_slice::<RegisterReply>(&buf).unwrap(), reply, "{:?}", codec);
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use proptest::prelude::*;
+use atlas_common::crypto::hash::Digest;
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use atlas_smr_application::serialize::ApplicationData;
+use atlas_smr_application::state::monolithic_state::MonolithicState;
+use example_app::app::messages::{AppData, Operation, OperationError, ReadConsistency, Reply, Request};
+use example_app::app::wire;
+use example_app::app::wire::{Codec, WireFormatError, WireMessage};
+use example_app::state::CalculatorState;
+
+fn seq_no() -> impl Strategy<Value = SeqNo> {
+    any::<u32>().prop_map(SeqNo::from)
+}
+
+fn consistency() -> impl Strategy<Value = ReadConsistency> {
+    prop_oneof![
+        Just(ReadConsistency::Any),
+        Just(ReadConsistency::BFT),
+        Just(ReadConsistency::Linearizable),
+        seq_no().prop_map(|after| ReadConsistency::ReadYourWrites { after }),
+    ]
+}
+
+fn operation() -> impl Strategy<Value = Operation> {
+    prop_oneof![
+        Just(Operation::Add),
+        Just(Operation::Sub),
+        Just(Operation::Mult),
+        Just(Operation::Divide),
+        Just(Operation::Remainder),
+        Just(Operation::Exponent),
+        consistency().prop_map(|consistency| Operation::Get { consistency }),
+        any::<i32>().prop_map(|expected| Operation::CompareAndSwap { expected }),
+        Just(Operation::Digest),
+        Just(Operation::Tolerance),
+        Just(Operation::OpenSession),
+    ]
+}
+
+fn request() -> impl Strategy<Value = Request> {
+    (operation(), any::<i32>(), any::<Option<u64>>()).prop_map(|(operation, value, id)| {
+        let request = Request::new(operation, value);
+
+        match id {
+            Some(id) => request.with_id(id),
+            None => request,
+        }
+    })
+}
+
+fn operation_error() -> impl Strategy<Value = OperationError> {
+    prop_oneof![
+        Just(OperationError::DivisionByZero),
+        any::<i32>().prop_map(OperationError::NegativeExponent),
+        Just(OperationError::Overflow),
+        any::<i32>().prop_map(|current| OperationError::CompareAndSwapFailed { current }),
+        any::<u64>().prop_map(OperationError::ExpiredRequest),
+        (seq_no(), seq_no()).prop_map(|(executed, required)| OperationError::NotExecutedYet { executed, required }),
+    ]
+}
+
+fn result() -> impl Strategy<Value = Result<i32, OperationError>> {
+    prop_oneof![any::<i32>().prop_map(Ok), operation_error().prop_map(Err)]
+}
+
+fn reply() -> impl Strategy<Value = Reply> {
+    (result(), seq_no(), any::<Option<[u8; Digest::LENGTH]>>()).prop_map(|(result, seq_no, digest)| {
+        let reply = Reply::new(result, seq_no);
+
+        match digest {
+            Some(digest) => reply.with_digest(Digest::from_bytes(&digest).unwrap()),
+            None => reply,
+        }
+    })
+}
+
+/// The messages and state exactly as the original release (without the format header) defined them
+mod baseline {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub enum Operation {
+        Add,
+        Sub,
+        Mult,
+        Divide,
+        Remainder,
+        Exponent
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct Request {
+        pub operation: Operation,
+        pub value: i32
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct Reply {
+        pub value: i32
+    }
+
+    #[derive(Serialize, Deserialize, Clone)]
+    pub struct CalculatorState {
+        pub value: i32
+    }
+
+    pub const OPERATIONS: [(Operation, super::Operation); 6] = [
+        (Operation::Add, super::Operation::Add),
+        (Operation::Sub, super::Operation::Sub),
+        (Operation::Mult, super::Operation::Mult),
+        (Operation::Divide, super::Operation::Divide),
+        (Operation::Remainder, super::Operation::Remainder),
+        (Operation::Exponent, super::Operation::Exponent),
+    ];
+}
+
+fn state() -> impl Strategy<Value = CalculatorState> {
+    let replies = prop::collection::vec((0u32..8, any::<u64>(), reply()), 0..64);
+
+    (any::<i32>(), seq_no(), replies).prop_map(|(value, executed, replies)| {
+        let mut state = CalculatorState::default();
+
+        state.set_value(value);
+        state.set_executed(executed);
+
+        for (client, request, reply) in replies {
+            state.record_reply(NodeId(client), request, reply);
+        }
+
+        state
+    })
+}
+
+/// Every codec, through both the reader and the in place decoding paths
+fn assert_roundtrip<T>(message: &T) -> Result<(), TestCaseError>
+    where T: WireMessage + PartialEq + std::fmt::Debug {
+    for codec in Codec::ALL {
+        let mut buf = Vec::new();
+
+        wire::encode_with(codec, &mut buf, message).unwrap();
+
+        prop_assert_eq!(&wire::decode::<T, _>(buf.as_slice()).unwrap(), message, "{:?} reader", codec);
+        prop_assert_eq!(&wire::decode_from_slice::<T>(&buf).unwrap(), message, "{:?} slice", codec);
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn request_roundtrip(request in request()) {
+        assert_roundtrip(&request)?;
+
+        let mut buf = Vec::new();
+
+        AppData::serialize_request(&mut buf, &request).unwrap();
+
+        prop_assert_eq!(AppData::deserialize_request(buf.as_slice()).unwrap(), request);
+    }
+
+    #[test]
+    fn reply_roundtrip(reply in reply()) {
+        assert_roundtrip(&reply)?;
+
+        let mut buf = Vec::new();
+
+        AppData::serialize_reply(&mut buf, &reply).unwrap();
+
+        prop_assert_eq!(AppData::deserialize_reply(buf.as_slice()).unwrap(), reply);
+    }
+
+    #[test]
+    fn state_roundtrip(state in state()) {
+        assert_roundtrip(&state)?;
+
+        let mut buf = Vec::new();
+
+        CalculatorState::serialize_state(&mut buf, &state).unwrap();
+
+        prop_assert_eq!(CalculatorState::deserialize_state(buf.as_slice()).unwrap(), state);
+    }
+
+    /// Requests, replies and states written by the original release, before the format header, still decode
+    #[test]
+    fn legacy_roundtrip(operation in 0usize..6, value in any::<i32>(), reply in any::<i32>(), state in any::<i32>()) {
+        let (legacy_operation, operation) = baseline::OPERATIONS[operation].clone();
+
+        let legacy = bincode::serde::encode_to_vec(baseline::Request { operation: legacy_operation, value }, bincode::config::standard()).unwrap();
+
+        prop_assert_eq!(AppData::deserialize_request(legacy.as_slice()).unwrap(), Request::new(operation.clone(), value));
+        prop_assert_eq!(wire::decode_from_slice::<Request>(&legacy).unwrap(), Request::new(operation, value));
+
+        let legacy = bincode::serde::encode_to_vec(baseline::Reply { value: reply }, bincode::config::standard()).unwrap();
+
+        prop_assert_eq!(AppData::deserialize_reply(legacy.as_slice()).unwrap(), Reply::new(Ok(reply), SeqNo::ZERO));
+        prop_assert_eq!(wire::decode_from_slice::<Reply>(&legacy).unwrap(), Reply::new(Ok(reply), SeqNo::ZERO));
+
+        let legacy = bincode::serde::encode_to_vec(baseline::CalculatorState { value: state }, bincode::config::standard()).unwrap();
+
+        let decoded = CalculatorState::deserialize_state(legacy.as_slice()).unwrap();
+
+        prop_assert_eq!(decoded.value(), state);
+        prop_assert_eq!(decoded.executed(), SeqNo::ZERO);
+        prop_assert_eq!(decoded.session_count(), 0);
+    }
+
+    /// The digest only depends on the contents of the state, not on how it was transferred
+    #[test]
+    fn digest_survives_roundtrip(state in state()) {
+        for codec in Codec::ALL {
+            let mut buf = Vec::new();
+
+            wire::encode_with(codec, &mut buf, &state).unwrap();
+
+            prop_assert_eq!(wire::decode::<CalculatorState, _>(buf.as_slice()).unwrap().digest(), state.digest());
+        }
+    }
+
+    #[test]
+    fn arbitrary_bytes_do_not_panic(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+        let _ = AppData::deserialize_request(bytes.as_slice());
+        let _ = AppData::deserialize_reply(bytes.as_slice());
+        let _ = CalculatorState::deserialize_state(bytes.as_slice());
+        let _ = wire::decode_from_slice::<Request>(&bytes);
+        let _ = wire::decode_from_slice::<Reply>(&bytes);
+        let _ = wire::decode_from_slice::<CalculatorState>(&bytes);
+    }
+}
+
+/// The bytes the original release wrote, so the legacy layouts cannot drift along with the baseline module
+#[test]
+fn legacy_bytes_decode() {
+    // Add 5: the variant index of Add, then 5 as a zigzag varint
+    assert_eq!(AppData::deserialize_request(&[0x00, 0x0A][..]).unwrap(), Request::new(Operation::Add, 5));
+    // Exponent -2
+    assert_eq!(wire::decode_from_slice::<Request>(&[0x05, 0x03]).unwrap(), Request::new(Operation::Exponent, -2));
+    // A reply with the value 300, which takes a u16 varint
+    assert_eq!(AppData::deserialize_reply(&[0xFB, 0x58, 0x02][..]).unwrap(), Reply::new(Ok(300), SeqNo::ZERO));
+    // A state with the value -1
+    assert_eq!(CalculatorState::deserialize_state(&[0x01][..]).unwrap().value(), -1);
+}
+
+/// Only the legacy encoding predates the current format version, so any other version is unknown
+#[test]
+fn unknown_versions_are_rejected() {
+    for version in [wire::CURRENT_VERSION + 1, u8::MAX] {
+        let message = [wire::FORMAT_MAGIC, version, 0, 0x00, 0x0A];
+
+        assert!(matches!(wire::decode_from_slice::<Request>(&message), Err(WireFormatError::UnsupportedVersion { found, .. }) if found == version));
+        assert!(matches!(wire::decode::<Request, _>(&message[..]), Err(WireFormatError::UnsupportedVersion { found, .. }) if found == version));
+    }
+}
@@ -0,0 +1,61 @@
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use example_app::app::messages::Reply;
+use example_app::state::{session_base, CalculatorState, PastRequest, MAX_SESSIONS};
+
+fn is_expired(state: &CalculatorState, client: NodeId, request: u64) -> bool {
+    matches!(state.past_request(client, request), PastRequest::Expired)
+}
+
+#[test]
+fn requests_without_a_session_are_expired() {
+    let mut state = CalculatorState::default();
+
+    assert!(is_expired(&state, NodeId(1000), 1));
+
+    let base = session_base(state.open_session(NodeId(1000)));
+
+    assert!(matches!(state.past_request(NodeId(1000), base), PastRequest::New));
+    assert!(is_expired(&state, NodeId(1000), base - 1));
+}
+
+#[test]
+fn reopening_a_session_expires_its_requests() {
+    let mut state = CalculatorState::default();
+
+    let first = session_base(state.open_session(NodeId(1000)));
+
+    state.record_reply(NodeId(1000), first, Reply::new(Ok(5), SeqNo::ZERO));
+
+    let second = session_base(state.open_session(NodeId(1000)));
+
+    assert!(second > first);
+    assert!(is_expired(&state, NodeId(1000), first));
+    assert!(is_expired(&state, NodeId(1000), first + 1));
+    assert!(matches!(state.past_request(NodeId(1000), second), PastRequest::New));
+}
+
+#[test]
+fn the_session_executed_the_longest_ago_is_evicted() {
+    let mut state = CalculatorState::default();
+
+    for client in 0..MAX_SESSIONS as u32 {
+        state.set_executed(SeqNo::from(client));
+
+        let base = session_base(state.open_session(NodeId(client)));
+
+        state.record_reply(NodeId(client), base, Reply::new(Ok(0), state.executed()));
+    }
+
+    // The first client executes again, so the second one is now the oldest
+    state.set_executed(SeqNo::from(MAX_SESSIONS as u32));
+    state.record_reply(NodeId(0), session_base(1) + 1, Reply::new(Ok(0), state.executed()));
+
+    state.set_executed(SeqNo::from(MAX_SESSIONS as u32 + 1));
+    state.open_session(NodeId(1_000_000));
+
+    assert_eq!(state.session_count(), MAX_SESSIONS);
+    assert!(matches!(state.past_request(NodeId(0), session_base(1)), PastRequest::Executed(_)));
+    assert!(is_expired(&state, NodeId(1), session_base(2)));
+    assert!(matches!(state.past_request(NodeId(2), session_base(3)), PastRequest::Executed(_)));
+}
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use atlas_common::node_id::NodeId;
+use atlas_common::ordering::SeqNo;
+use atlas_smr_application::app::{Application, UpdateBatch};
+use example_app::app::App;
+use example_app::app::checkpoints::{CheckpointDigestConfig, CheckpointDigests};
+use example_app::app::messages::{Operation, Reply, Request};
+use example_app::snapshot::{Snapshot, SnapshotError};
+use example_app::state::{session_base, CalculatorState, PastRequest};
+
+fn snapshot() -> Snapshot {
+    let mut state = CalculatorState::default();
+
+    state.set_value(42);
+    state.set_executed(SeqNo::from(2000));
+    state.record_reply(NodeId(1000), 7, Reply::new(Ok(42), SeqNo::from(1999)));
+
+    Snapshot::new(SeqNo::from(2000), state)
+}
+
+fn written(snapshot: &Snapshot) -> Vec<u8> {
+    let mut file = Vec::new();
+
+    snapshot.write(&mut file).unwrap();
+
+    file
+}
+
+#[test]
+fn snapshot_roundtrip() {
+    let snapshot = snapshot();
+
+    let read = Snapshot::read(written(&snapshot).as_slice()).unwrap();
+
+    assert_eq!(read, snapshot);
+}
+
+#[test]
+fn corrupted_snapshot_is_rejected() {
+    let file = written(&snapshot());
+
+    // The version, the sequence number, the state and the checksum itself
+    for at in [8, 10, file.len() / 2] {
+        let mut corrupted = file.clone();
+
+        corrupted[at] ^= 0x01;
+
+        assert!(Snapshot::read(corrupted.as_slice()).is_err(), "flipping byte {} went unnoticed", at);
+    }
+
+    let mut corrupted = file.clone();
+
+    let last = corrupted.len() - 1;
+
+    corrupted[last] ^= 0x01;
+
+    assert!(matches!(Snapshot::read(corrupted.as_slice()), Err(SnapshotError::ChecksumMismatch { .. })));
+}
+
+#[test]
+fn truncated_snapshot_is_rejected() {
+    let file = written(&snapshot());
+
+    assert!(Snapshot::read(&file[..file.len() - 1]).is_err());
+    assert!(matches!(Snapshot::read(&file[..4]), Err(SnapshotError::NotASnapshot)));
+    assert!(matches!(Snapshot::read(&b"not a snapshot at all"[..]), Err(SnapshotError::NotASnapshot)));
+}
+
+#[test]
+fn snapshot_whose_header_does_not_fit_the_file_is_rejected() {
+    let path = std::env::temp_dir().join(format!("example-app-snapshot-{}", std::process::id()));
+
+    snapshot().save(&path).unwrap();
+
+    assert_eq!(Snapshot::load(&path).unwrap(), snapshot());
+
+    // A state one byte longer than the file holds
+    let mut file = std::fs::read(&path).unwrap();
+
+    file[13] = file[13].wrapping_add(1);
+
+    std::fs::write(&path, &file).unwrap();
+
+    let err = Snapshot::load(&path).unwrap_err();
+
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(err.downcast_ref::<SnapshotError>(), Some(SnapshotError::SizeMismatch { .. })), "{:?}", err);
+}
+
+fn execute(app: &App, state: &mut CalculatorState, seq_no: u32, from: NodeId, request: Request) {
+    let mut batch = UpdateBatch::new_with_cap(SeqNo::from(seq_no), 1);
+
+    batch.add(from, SeqNo::ZERO, SeqNo::from(seq_no), request);
+
+    app.update_batch(state, batch);
+}
+
+#[test]
+fn retries_after_starting_from_a_snapshot_are_not_executed_again() {
+    let app = App::init();
+
+    let client = NodeId(1000);
+
+    let mut state = CalculatorState::default();
+
+    execute(&app, &mut state, 1, client, Request::new(Operation::OpenSession, 0));
+
+    let request = Request::new(Operation::Add, 5).with_id(session_base(1));
+
+    execute(&app, &mut state, 2, client, request.clone());
+
+    let mut state = Snapshot::read(written(&Snapshot::new(SeqNo::from(2), state)).as_slice()).unwrap().state;
+
+    state.restart();
+
+    assert_eq!(state.executed(), SeqNo::ZERO);
+    assert_eq!(state.session_count(), 0);
+
+    // The new cluster orders from 0 again, and the client retries the request it got no reply to
+    execute(&app, &mut state, 1, client, request);
+
+    assert_eq!(state.value(), 5);
+    assert!(matches!(state.past_request(client, session_base(1)), PastRequest::Expired));
+
+    // The session the client opens in the new cluster starts above the ids of the old one
+    execute(&app, &mut state, 2, client, Request::new(Operation::OpenSession, 0));
+
+    let base = session_base(2);
+
+    assert!(matches!(state.past_request(client, base), PastRequest::New));
+
+    execute(&app, &mut state, 3, client, Request::new(Operation::Add, 1).with_id(base));
+
+    assert_eq!(state.value(), 6);
+}
+
+fn execute_batches(app: &App, state: &mut CalculatorState, seq_nos: std::ops::RangeInclusive<u32>) {
+    for seq_no in seq_nos {
+        let mut batch = UpdateBatch::new_with_cap(SeqNo::from(seq_no), 1);
+
+        batch.add(NodeId(1000), SeqNo::ZERO, SeqNo::from(seq_no), Request::new(Operation::Add, 1));
+
+        app.update_batch(state, batch);
+    }
+}
+
+#[test]
+fn snapshots_are_only_taken_when_requested() {
+    let checkpoints = Arc::new(CheckpointDigests::new(&CheckpointDigestConfig { period: 2, history: 4 }));
+
+    let app = App::init().with_checkpoint_digests(Some(checkpoints.clone()));
+
+    let mut state = CalculatorState::default();
+
+    execute_batches(&app, &mut state, 1..=4);
+
+    assert_eq!(checkpoints.recent().len(), 2);
+    assert!(checkpoints.next_snapshot(Duration::ZERO).is_none());
+
+    let requested = {
+        let checkpoints = checkpoints.clone();
+
+        thread::spawn(move || checkpoints.next_snapshot(Duration::from_secs(10)))
+    };
+
+    // Keep reaching checkpoints until the request is picked up by one of them
+    let mut executed = 4;
+
+    while !requested.is_finished() {
+        execute_batches(&app, &mut state, executed + 1..=executed + 2);
+
+        executed += 2;
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let snapshot = requested.join().unwrap().expect("The snapshot of the next checkpoint");
+
+    assert!(snapshot.seq_no > SeqNo::from(4));
+    assert_eq!(snapshot.state.value(), u32::from(snapshot.seq_no) as i32);
+}
+
+#[test]
+fn checkpoint_after_waits_for_the_next_checkpoint() {
+    let checkpoints = Arc::new(CheckpointDigests::new(&CheckpointDigestConfig { period: 2, history: 4 }));
+
+    let app = App::init().with_checkpoint_digests(Some(checkpoints.clone()));
+
+    let mut state = CalculatorState::default();
+
+    execute_batches(&app, &mut state, 1..=2);
+
+    assert_eq!(checkpoints.checkpoint_after(SeqNo::ZERO, Duration::ZERO).map(|checkpoint| checkpoint.seq_no), Some(SeqNo::from(2)));
+    assert!(checkpoints.checkpoint_after(SeqNo::from(2), Duration::ZERO).is_none());
+
+    let waiting = {
+        let checkpoints = checkpoints.clone();
+
+        thread::spawn(move || checkpoints.checkpoint_after(SeqNo::from(2), Duration::from_secs(10)))
+    };
+
+    // Past the sequence number, but not a checkpoint yet
+    execute_batches(&app, &mut state, 3..=3);
+
+    thread::sleep(Duration::from_millis(10));
+
+    assert!(!waiting.is_finished());
+
+    execute_batches(&app, &mut state, 4..=4);
+
+    assert_eq!(waiting.join().unwrap().map(|checkpoint| checkpoint.seq_no), Some(SeqNo::from(4)));
+}
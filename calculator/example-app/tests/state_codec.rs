@@ -0,0 +1,25 @@
+//! Picks the codec of the process, so it runs in its own test binary instead of
+//! changing the codec the other tests encode with
+
+use atlas_smr_application::state::monolithic_state::MonolithicState;
+use example_app::app::wire;
+use example_app::app::wire::Codec;
+use example_app::state::CalculatorState;
+
+/// Replicas digest and compare the serialized state, so its encoding cannot follow the codec of the process
+#[test]
+fn state_ignores_process_codec() {
+    wire::set_codec(Codec::Json).unwrap();
+
+    let state = CalculatorState::default();
+
+    let mut serialized = Vec::new();
+
+    CalculatorState::serialize_state(&mut serialized, &state).unwrap();
+
+    let mut bincode = Vec::new();
+
+    wire::encode_with(Codec::Bincode, &mut bincode, &state).unwrap();
+
+    assert_eq!(serialized, bincode);
+}
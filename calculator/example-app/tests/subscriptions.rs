@@ -0,0 +1,64 @@
+use atlas_common::ordering::SeqNo;
+use example_app::app::subscriptions::{SubscriptionRegistry, WatchFilter};
+
+#[test]
+fn a_subscriber_that_falls_behind_is_dropped() {
+    let registry = SubscriptionRegistry::new(2);
+
+    let slow = registry.subscribe(WatchFilter::All);
+    let fast = registry.subscribe(WatchFilter::All);
+
+    for value in 1..=3 {
+        registry.notify(value - 1, value, SeqNo::from(value as u32));
+
+        assert_eq!(fast.recv().unwrap().value, value);
+    }
+
+    assert_eq!(registry.subscriber_count(), 1);
+
+    // The notifications that did fit are still delivered, then the subscription ends
+    let pending: Vec<i32> = slow.iter().map(|notification| notification.value).collect();
+
+    assert_eq!(pending, vec![1, 2]);
+}
+
+#[test]
+fn a_subscriber_that_went_away_is_forgotten() {
+    let registry = SubscriptionRegistry::default();
+
+    drop(registry.subscribe(WatchFilter::All));
+
+    registry.notify(0, 1, SeqNo::ZERO);
+
+    assert_eq!(registry.subscriber_count(), 0);
+}
+
+#[test]
+fn ignored_changes_do_not_fill_the_channel() {
+    let registry = SubscriptionRegistry::new(1);
+
+    let crossing = registry.subscribe(WatchFilter::Crossing { threshold: 100 });
+
+    for value in 1..=10 {
+        registry.notify(value - 1, value, SeqNo::from(value as u32));
+    }
+
+    assert_eq!(registry.subscriber_count(), 1);
+
+    registry.notify(10, 100, SeqNo::from(11));
+
+    assert_eq!(crossing.try_recv().unwrap().value, 100);
+}
+
+#[test]
+fn dropping_a_subscription_unregisters_it_right_away() {
+    let registry = SubscriptionRegistry::default();
+
+    let subscription = registry.subscribe(WatchFilter::All);
+
+    assert_eq!(registry.subscriber_count(), 1);
+
+    drop(subscription);
+
+    assert_eq!(registry.subscriber_count(), 0);
+}